@@ -0,0 +1,94 @@
+//! Memory use and deserialization time of the fixtures, for the owned and borrowed ASTs,
+//! and for the owned AST from the compact binary representation.
+//!
+//! Run with `cargo bench --bench fixtures`.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    mem::size_of,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use djot_ast::{attributes::Meta, binary::Compact, block::Block, borrowed, inline::Inline, Doc};
+
+/// Keeps count of the bytes currently allocated.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ROUNDS: usize = 20;
+
+fn main() {
+    println!(
+        "size of Meta: {} bytes, Block: {} bytes, Inline: {} bytes",
+        size_of::<Meta>(),
+        size_of::<Block>(),
+        size_of::<Inline>()
+    );
+    for (name, json) in [
+        ("readme", include_str!("../tests/fixtures/readme.dj.json")),
+        (
+            "pandoc-manual",
+            include_str!("../tests/fixtures/pandoc-manual.json"),
+        ),
+    ] {
+        println!("{name}: {} KiB of JSON", json.len() / 1024);
+        measure("owned", || serde_json::from_str::<Doc>(json).unwrap());
+        measure("borrowed", || {
+            serde_json::from_str::<borrowed::Doc>(json).unwrap()
+        });
+        measure("borrowed into owned", || {
+            serde_json::from_str::<borrowed::Doc>(json)
+                .unwrap()
+                .into_owned()
+        });
+        let doc: Doc = serde_json::from_str(json).unwrap();
+        let bytes = postcard::to_allocvec(&Compact(&doc)).unwrap();
+        println!("  {} KiB as compact postcard", bytes.len() / 1024);
+        measure("owned from postcard", || {
+            postcard::from_bytes::<Compact<Doc>>(&bytes).unwrap().0
+        });
+    }
+}
+
+/// Report the memory held by what `parse` returns, and the best time taken to run it.
+fn measure<T>(label: &str, parse: impl Fn() -> T) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let parsed = parse();
+    let retained = ALLOCATED.load(Ordering::Relaxed) - before;
+    drop(parsed);
+
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let parsed = black_box(parse());
+        best = best.min(start.elapsed());
+        drop(parsed);
+    }
+    println!(
+        "  {label}: {} KiB in memory, deserialized in {best:.2?} (best of {ROUNDS})",
+        retained / 1024
+    );
+}
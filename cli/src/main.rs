@@ -0,0 +1,248 @@
+//! `djot-ast`: command-line tools for djot documents.
+//!
+//! Documents are read as djot.js JSON (`djot --to astjson`) or CBOR, from a file or standard input.
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use djot_ast::{
+    deserialize::Lenient,
+    normalize::normalize,
+    text::node_text,
+    traverse::NodeRef,
+    validate::{validate, Severity},
+    AstNode, Doc,
+};
+
+mod render;
+mod stats;
+mod toc;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Debug, Parser)]
+#[command(name = "djot-ast", version, about)]
+struct Cli {
+    /// Format of the input.
+    #[arg(long, global = true, value_enum, default_value_t = InputFormat::Json)]
+    from: InputFormat,
+    /// Write output to this file instead of standard output.
+    #[arg(short, long, global = true)]
+    output: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum InputFormat {
+    Json,
+    Cbor,
+    /// Djot source, which needs a djot parser.
+    Djot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Cbor,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check documents for undefined references, duplicate ids and other problems.
+    ///
+    /// Exits unsuccessfully if any errors are found.
+    Validate {
+        /// Files to check, or standard input if none are given.
+        files: Vec<PathBuf>,
+        /// Also exit unsuccessfully if there are warnings.
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Render a document to another format.
+    Render {
+        #[arg(long, value_enum)]
+        to: render::Format,
+        file: Option<PathBuf>,
+    },
+    /// Print a table of contents, as a djot list linking to each heading.
+    Toc {
+        /// Deepest heading level to include.
+        #[arg(long, default_value_t = 6)]
+        depth: u64,
+        file: Option<PathBuf>,
+    },
+    /// Count the words, characters and nodes of each kind in a document.
+    Stats {
+        #[arg(long)]
+        json: bool,
+        file: Option<PathBuf>,
+    },
+    /// Print the path and text of each node matching a selector, e.g. `heading[level=2]` or `div.note > para`.
+    Query {
+        selector: String,
+        file: Option<PathBuf>,
+    },
+    /// Normalize a document and write it back out in the same format.
+    Fmt {
+        /// Write JSON on a single line.
+        #[arg(long)]
+        compact: bool,
+        file: Option<PathBuf>,
+    },
+    /// Convert a document between serialization formats.
+    Convert {
+        #[arg(long, value_enum)]
+        to: OutputFormat,
+        /// Write JSON on a single line.
+        #[arg(long)]
+        compact: bool,
+        file: Option<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("djot-ast: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read(file: Option<&PathBuf>, format: InputFormat) -> Result<AstNode> {
+    let bytes = match file {
+        Some(path) if path.as_os_str() != "-" => {
+            fs::read(path).map_err(|e| format!("could not read '{}': {e}", path.display()))?
+        }
+        _ => {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            bytes
+        }
+    };
+    // read leniently, so that input from a newer djot.js with new types of node still works
+    let Lenient(node) = match format {
+        InputFormat::Json => serde_json::from_slice(&bytes)?,
+        InputFormat::Cbor => ciborium::from_reader(bytes.as_slice())?,
+        InputFormat::Djot => {
+            return Err("reading djot source is not supported yet; \
+                convert it with `djot --to astjson` and use `--from json`"
+                .into())
+        }
+    };
+    Ok(node)
+}
+
+fn read_doc(file: Option<&PathBuf>, format: InputFormat) -> Result<Doc> {
+    match read(file, format)? {
+        AstNode::Doc(doc) => Ok(doc),
+        node => Err(format!(
+            "expected a document, found a '{}' node",
+            NodeRef::from(&node).tag()
+        )
+        .into()),
+    }
+}
+
+fn write_node(node: &AstNode, format: OutputFormat, compact: bool) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match format {
+        OutputFormat::Json if compact => serde_json::to_writer(&mut out, node)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, node)?;
+            out.push(b'\n');
+        }
+        OutputFormat::Cbor => ciborium::into_writer(node, &mut out)?,
+    }
+    Ok(out)
+}
+
+fn run(cli: Cli) -> Result<ExitCode> {
+    let mut code = ExitCode::SUCCESS;
+    let output: Vec<u8> = match cli.command {
+        Command::Validate { files, strict } => {
+            let files = if files.is_empty() {
+                vec![PathBuf::from("-")]
+            } else {
+                files
+            };
+            let mut out = String::new();
+            for file in &files {
+                let doc = read_doc(Some(file), cli.from)?;
+                let name = if file.as_os_str() == "-" {
+                    "<stdin>".into()
+                } else {
+                    file.display().to_string()
+                };
+                for diagnostic in validate(&doc) {
+                    if diagnostic.severity == Severity::Error || strict {
+                        code = ExitCode::FAILURE;
+                    }
+                    out.push_str(&format!("{name}: {diagnostic}\n"));
+                }
+            }
+            out.into_bytes()
+        }
+        Command::Render { to, file } => {
+            let doc = read_doc(file.as_ref(), cli.from)?;
+            render::render(&doc, to).into_bytes()
+        }
+        Command::Toc { depth, file } => {
+            let doc = read_doc(file.as_ref(), cli.from)?;
+            toc::toc(&doc, depth).into_bytes()
+        }
+        Command::Stats { json, file } => {
+            let doc = read_doc(file.as_ref(), cli.from)?;
+            let stats = stats::Stats::new(&doc);
+            if json {
+                let mut out = serde_json::to_vec_pretty(&stats.to_json())?;
+                out.push(b'\n');
+                out
+            } else {
+                stats.to_string().into_bytes()
+            }
+        }
+        Command::Query { selector, file } => {
+            let doc = read_doc(file.as_ref(), cli.from)?;
+            let mut out = String::new();
+            for (path, node) in doc.select(&selector)? {
+                let text = node_text(node).replace('\n', " ");
+                out.push_str(&format!("{path}\t{text}\n"));
+            }
+            out.into_bytes()
+        }
+        Command::Fmt { compact, file } => {
+            let mut node = read(file.as_ref(), cli.from)?;
+            if let AstNode::Doc(doc) = &mut node {
+                normalize(doc);
+            }
+            let format = match cli.from {
+                InputFormat::Cbor => OutputFormat::Cbor,
+                InputFormat::Json | InputFormat::Djot => OutputFormat::Json,
+            };
+            write_node(&node, format, compact)?
+        }
+        Command::Convert { to, compact, file } => {
+            let node = read(file.as_ref(), cli.from)?;
+            write_node(&node, to, compact)?
+        }
+    };
+    match &cli.output {
+        Some(path) => fs::write(path, output)
+            .map_err(|e| format!("could not write '{}': {e}", path.display()))?,
+        None => match io::stdout().write_all(&output) {
+            // e.g. piped into `head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+            result => result?,
+        },
+    }
+    Ok(code)
+}
@@ -0,0 +1,439 @@
+use djot_ast::{
+    attributes::HasAttributes,
+    block::{Alignment, Block, ListItem, NumberStyle},
+    inline::{Inline, SmartPunctuationType},
+    text::inline_text,
+    Doc,
+};
+
+use super::{node_attributes, unknown_comment};
+
+/// Render a document as an HTML fragment, in the style of djot.js.
+pub fn render(doc: &Doc) -> String {
+    let mut renderer = Renderer {
+        doc,
+        out: String::new(),
+        tight: false,
+        footnotes: Vec::new(),
+    };
+    renderer.blocks(&doc.children);
+    renderer.endnotes();
+    renderer.out
+}
+
+struct Renderer<'a> {
+    doc: &'a Doc,
+    out: String,
+    /// Whether paragraphs are in a tight list, so are rendered without `<p>` tags.
+    tight: bool,
+    /// Labels of referenced footnotes, in the order in which they are numbered.
+    footnotes: Vec<String>,
+}
+
+fn escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Whether a key can be written as an HTML attribute name.
+fn is_attribute_name(key: &str) -> bool {
+    !key.is_empty()
+        && key.chars().all(|c| {
+            !c.is_control()
+                && !c.is_whitespace()
+                && !matches!(c, '"' | '\'' | '<' | '>' | '/' | '=')
+        })
+}
+
+impl<'a> Renderer<'a> {
+    fn escaped(&mut self, text: &str) {
+        escape(text, &mut self.out);
+    }
+
+    /// Write a comment in place of a node of an unknown type.
+    fn comment(&mut self, tag: &str) {
+        self.out
+            .push_str(&format!("<!-- {} -->", unknown_comment(tag)));
+    }
+
+    /// Write an opening tag with any extra attributes and the node's own.
+    ///
+    /// The node's classes are added to an extra `class`, and its other attributes replace extra ones of the same name.
+    /// Attributes whose names cannot be written in HTML are left out.
+    fn open(&mut self, tag: &str, node: &impl HasAttributes, extra: &[(&str, &str)]) {
+        self.out.push('<');
+        self.out.push_str(tag);
+        let mut attrs: Vec<(&str, String)> =
+            extra.iter().map(|(k, v)| (*k, v.to_string())).collect();
+        for (key, value) in node_attributes(node, true) {
+            if !is_attribute_name(key) {
+                continue;
+            }
+            match attrs.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, existing)) if key == "class" => {
+                    existing.push(' ');
+                    existing.push_str(value);
+                }
+                Some((_, existing)) => *existing = value.to_string(),
+                None => attrs.push((key, value.to_string())),
+            }
+        }
+        for (key, value) in attrs {
+            self.out.push(' ');
+            self.out.push_str(key);
+            self.out.push_str("=\"");
+            escape(&value, &mut self.out);
+            self.out.push('"');
+        }
+        self.out.push('>');
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Para(i) => {
+                if self.tight {
+                    self.inlines(&i.children);
+                    self.out.push('\n');
+                } else {
+                    self.open("p", i, &[]);
+                    self.inlines(&i.children);
+                    self.out.push_str("</p>\n");
+                }
+            }
+            Block::Heading(i) => {
+                let tag = format!("h{}", i.level.clamp(1, 6));
+                self.open(&tag, i, &[]);
+                self.inlines(&i.children);
+                self.out.push_str(&format!("</{tag}>\n"));
+            }
+            Block::ThematicBreak(i) => {
+                self.open("hr", i, &[]);
+                self.out.push('\n');
+            }
+            Block::Section(i) => {
+                self.open("section", i, &[]);
+                self.out.push('\n');
+                self.blocks(&i.children);
+                self.out.push_str("</section>\n");
+            }
+            Block::Div(i) => {
+                self.open("div", i, &[]);
+                self.out.push('\n');
+                self.blocks(&i.children);
+                self.out.push_str("</div>\n");
+            }
+            Block::CodeBlock(i) => {
+                self.open("pre", i, &[]);
+                match &i.lang {
+                    Some(lang) => {
+                        self.out.push_str("<code class=\"language-");
+                        self.escaped(lang);
+                        self.out.push_str("\">");
+                    }
+                    None => self.out.push_str("<code>"),
+                }
+                self.escaped(&i.text);
+                self.out.push_str("</code></pre>\n");
+            }
+            Block::RawBlock(i) => {
+                if i.format == "html" {
+                    self.out.push_str(&i.text);
+                }
+            }
+            // there is no telling how to render a node of an unknown type, but its children can be
+            Block::Unknown(i) => {
+                if let Some(blocks) = i.blocks() {
+                    self.blocks(&blocks);
+                } else if let Some(inlines) = i.inlines() {
+                    self.open("p", i, &[]);
+                    self.inlines(&inlines);
+                    self.out.push_str("</p>\n");
+                } else {
+                    self.comment(&i.tag);
+                    self.out.push('\n');
+                }
+            }
+            Block::BlockQuote(i) => {
+                self.open("blockquote", i, &[]);
+                self.out.push('\n');
+                self.blocks(&i.children);
+                self.out.push_str("</blockquote>\n");
+            }
+            Block::OrderedList(i) => {
+                let start = i.start.filter(|s| *s != 1).map(|s| s.to_string());
+                let mut extra = Vec::new();
+                if let Some(start) = &start {
+                    extra.push(("start", start.as_str()));
+                }
+                match i.style.number() {
+                    NumberStyle::Arabic => (),
+                    NumberStyle::LowerAlpha => extra.push(("type", "a")),
+                    NumberStyle::UpperAlpha => extra.push(("type", "A")),
+                    NumberStyle::LowerRoman => extra.push(("type", "i")),
+                    NumberStyle::UpperRoman => extra.push(("type", "I")),
+                }
+                self.open("ol", i, &extra);
+                self.out.push('\n');
+                self.list_items(&i.children, i.tight);
+                self.out.push_str("</ol>\n");
+            }
+            Block::BulletList(i) => {
+                self.open("ul", i, &[]);
+                self.out.push('\n');
+                self.list_items(&i.children, i.tight);
+                self.out.push_str("</ul>\n");
+            }
+            Block::TaskList(i) => {
+                self.open("ul", i, &[("class", "task-list")]);
+                self.out.push('\n');
+                let tight = std::mem::replace(&mut self.tight, i.tight);
+                for item in &i.children {
+                    self.open("li", item, &[]);
+                    self.out
+                        .push_str("\n<input disabled=\"\" type=\"checkbox\"");
+                    if item.checkbox.is_checked() {
+                        self.out.push_str(" checked=\"\"");
+                    }
+                    self.out.push_str("/>\n");
+                    self.blocks(&item.children);
+                    self.out.push_str("</li>\n");
+                }
+                self.tight = tight;
+                self.out.push_str("</ul>\n");
+            }
+            Block::DefinitionList(i) => {
+                self.open("dl", i, &[]);
+                self.out.push('\n');
+                let tight = std::mem::replace(&mut self.tight, false);
+                for item in &i.children {
+                    self.open("dt", &item.term, &[]);
+                    self.inlines(&item.term.children);
+                    self.out.push_str("</dt>\n");
+                    self.open("dd", &item.definition, &[]);
+                    self.out.push('\n');
+                    self.blocks(&item.definition.children);
+                    self.out.push_str("</dd>\n");
+                }
+                self.tight = tight;
+                self.out.push_str("</dl>\n");
+            }
+            Block::Table(i) => {
+                self.open("table", i, &[]);
+                self.out.push('\n');
+                if !i.caption.children.is_empty() {
+                    self.open("caption", &i.caption, &[]);
+                    self.inlines(&i.caption.children);
+                    self.out.push_str("</caption>\n");
+                }
+                for row in &i.rows {
+                    self.open("tr", row, &[]);
+                    self.out.push('\n');
+                    for cell in &row.children {
+                        let tag = if cell.head { "th" } else { "td" };
+                        let style = match cell.align {
+                            Alignment::Default => None,
+                            Alignment::Left => Some("text-align: left;"),
+                            Alignment::Right => Some("text-align: right;"),
+                            Alignment::Center => Some("text-align: center;"),
+                        };
+                        let extra: Vec<_> = style.map(|s| ("style", s)).into_iter().collect();
+                        self.open(tag, cell, &extra);
+                        self.inlines(&cell.children);
+                        self.out.push_str(&format!("</{tag}>\n"));
+                    }
+                    self.out.push_str("</tr>\n");
+                }
+                self.out.push_str("</table>\n");
+            }
+        }
+    }
+
+    fn list_items(&mut self, items: &[ListItem], tight: bool) {
+        let tight = std::mem::replace(&mut self.tight, tight);
+        for item in items {
+            self.open("li", item, &[]);
+            self.out.push('\n');
+            self.blocks(&item.children);
+            self.out.push_str("</li>\n");
+        }
+        self.tight = tight;
+    }
+
+    /// Find the destination of a link or image, following its reference if it has one.
+    fn destination(
+        &self,
+        destination: &'a Option<String>,
+        reference: &Option<String>,
+    ) -> Option<&'a str> {
+        match reference {
+            Some(r) => self
+                .doc
+                .references
+                .get(r)
+                .or_else(|| self.doc.auto_references.get(r))
+                .map(|r| r.destination.as_str()),
+            None => destination.as_deref(),
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            self.inline(inline);
+        }
+    }
+
+    fn wrap(&mut self, tag: &str, node: &impl HasAttributes, children: &[Inline]) {
+        self.open(tag, node, &[]);
+        self.inlines(children);
+        self.out.push_str(&format!("</{tag}>"));
+    }
+
+    fn inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Str(i) => {
+                if i.attributes().is_empty() {
+                    self.escaped(&i.text);
+                } else {
+                    self.open("span", i, &[]);
+                    self.escaped(&i.text);
+                    self.out.push_str("</span>");
+                }
+            }
+            Inline::SoftBreak(_) => self.out.push('\n'),
+            Inline::HardBreak(_) => self.out.push_str("<br>\n"),
+            Inline::NonBreakingSpace(_) => self.out.push_str("&nbsp;"),
+            Inline::Symb(i) => {
+                self.out.push(':');
+                self.escaped(&i.alias);
+                self.out.push(':');
+            }
+            Inline::Verbatim(i) => {
+                self.open("code", i, &[]);
+                self.escaped(&i.text);
+                self.out.push_str("</code>");
+            }
+            Inline::RawInline(i) => {
+                if i.format == "html" {
+                    self.out.push_str(&i.text);
+                }
+            }
+            Inline::Unknown(i) => match i.inlines() {
+                Some(inlines) => self.inlines(&inlines),
+                None => self.comment(&i.tag),
+            },
+            Inline::InlineMath(i) => {
+                self.open("span", i, &[("class", "math inline")]);
+                self.out.push_str("\\(");
+                self.escaped(&i.text);
+                self.out.push_str("\\)</span>");
+            }
+            Inline::DisplayMath(i) => {
+                self.open("span", i, &[("class", "math display")]);
+                self.out.push_str("\\[");
+                self.escaped(&i.text);
+                self.out.push_str("\\]</span>");
+            }
+            Inline::Url(i) => {
+                self.open("a", i, &[("href", &i.text)]);
+                self.escaped(&i.text);
+                self.out.push_str("</a>");
+            }
+            Inline::Email(i) => {
+                let href = format!("mailto:{}", i.text);
+                self.open("a", i, &[("href", &href)]);
+                self.escaped(&i.text);
+                self.out.push_str("</a>");
+            }
+            Inline::FootnoteReference(i) => {
+                let n = match self.footnotes.iter().position(|l| *l == i.text) {
+                    Some(idx) => idx + 1,
+                    None => {
+                        self.footnotes.push(i.text.clone());
+                        self.footnotes.len()
+                    }
+                };
+                self.out.push_str(&format!(
+                    "<a id=\"fnref{n}\" href=\"#fn{n}\" role=\"doc-noteref\"><sup>{n}</sup></a>"
+                ));
+            }
+            Inline::SmartPunctuation(i) => self.out.push_str(match i.punc_type {
+                SmartPunctuationType::LeftSingleQuote => "‘",
+                SmartPunctuationType::RightSingleQuote => "’",
+                SmartPunctuationType::LeftDoubleQuote => "“",
+                SmartPunctuationType::RightDoubleQuote => "”",
+                SmartPunctuationType::Ellipses => "…",
+                SmartPunctuationType::EmDash => "—",
+                SmartPunctuationType::EnDash => "–",
+            }),
+            Inline::Emph(i) => self.wrap("em", i, &i.children),
+            Inline::Strong(i) => self.wrap("strong", i, &i.children),
+            Inline::Link(i) => {
+                match self.destination(&i.destination, &i.reference) {
+                    Some(href) => self.open("a", i, &[("href", href)]),
+                    None => self.open("a", i, &[]),
+                }
+                self.inlines(&i.children);
+                self.out.push_str("</a>");
+            }
+            Inline::Image(i) => {
+                let alt = inline_text(&i.children);
+                match self.destination(&i.destination, &i.reference) {
+                    Some(src) => self.open("img", i, &[("alt", &alt), ("src", src)]),
+                    None => self.open("img", i, &[("alt", &alt)]),
+                }
+            }
+            Inline::Span(i) => self.wrap("span", i, &i.children),
+            Inline::Mark(i) => self.wrap("mark", i, &i.children),
+            Inline::Superscript(i) => self.wrap("sup", i, &i.children),
+            Inline::Subscript(i) => self.wrap("sub", i, &i.children),
+            Inline::Insert(i) => self.wrap("ins", i, &i.children),
+            Inline::Delete(i) => self.wrap("del", i, &i.children),
+            Inline::DoubleQuoted(i) => {
+                self.out.push('“');
+                self.inlines(&i.children);
+                self.out.push('”');
+            }
+            Inline::SingleQuoted(i) => {
+                self.out.push('‘');
+                self.inlines(&i.children);
+                self.out.push('’');
+            }
+        }
+    }
+
+    /// Write the referenced footnotes, which may themselves reference further footnotes.
+    fn endnotes(&mut self) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+        self.out
+            .push_str("<section role=\"doc-endnotes\">\n<hr>\n<ol>\n");
+        let mut idx = 0;
+        while idx < self.footnotes.len() {
+            let n = idx + 1;
+            self.out.push_str(&format!("<li id=\"fn{n}\">\n"));
+            if let Some(footnote) = self.doc.footnotes.get(&self.footnotes[idx]) {
+                self.tight = false;
+                self.blocks(&footnote.children);
+            }
+            self.out.push_str(&format!(
+                "<p><a href=\"#fnref{n}\" role=\"doc-backlink\">↩︎︎</a></p>\n</li>\n"
+            ));
+            idx += 1;
+        }
+        self.out.push_str("</ol>\n</section>\n");
+    }
+}
@@ -0,0 +1,397 @@
+use djot_ast::{
+    attributes::HasAttributes,
+    block::{Alignment, Block, NumberStyle, Table},
+    inline::{Inline, SmartPunctuationType},
+    Doc,
+};
+
+use super::unknown_comment;
+
+/// Render a document as a LaTeX fragment.
+///
+/// The output uses the `hyperref`, `graphicx`, `ulem`, `xcolor` and `amssymb` packages.
+/// Footnotes are rendered inline where they are referenced.
+pub fn render(doc: &Doc) -> String {
+    let mut renderer = Renderer {
+        doc,
+        out: String::new(),
+        footnote_depth: 0,
+    };
+    renderer.blocks(&doc.children);
+    renderer.out
+}
+
+struct Renderer<'a> {
+    doc: &'a Doc,
+    out: String,
+    /// How many footnotes are being rendered, to avoid looping on self-referencing footnotes.
+    footnote_depth: usize,
+}
+
+fn escape(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Escape a URL for `\url` or the target of `\href`, which take the rest of their argument verbatim.
+fn escape_url(url: &str, out: &mut String) {
+    for c in url.chars() {
+        if matches!(c, '%' | '#' | '\\' | '{' | '}') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+impl Renderer<'_> {
+    fn escaped(&mut self, text: &str) {
+        escape(text, &mut self.out);
+    }
+
+    /// Write a comment in place of a node of an unknown type, which ends the line.
+    fn comment(&mut self, tag: &str) {
+        self.out.push_str(&format!("% {}\n", unknown_comment(tag)));
+    }
+
+    fn label(&mut self, node: &impl HasAttributes) {
+        if let Some(id) = node.id() {
+            self.out.push_str("\\label{");
+            self.escaped(id);
+            self.out.push('}');
+        }
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.block(block);
+        }
+    }
+
+    fn environment(&mut self, name: &str, blocks: &[Block]) {
+        self.out.push_str(&format!("\\begin{{{name}}}\n"));
+        self.blocks(blocks);
+        self.out.push_str(&format!("\\end{{{name}}}\n\n"));
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Para(i) => {
+                self.inlines(&i.children);
+                self.out.push_str("\n\n");
+            }
+            Block::Heading(i) => {
+                let command = match i.level {
+                    0 | 1 => "section",
+                    2 => "subsection",
+                    3 => "subsubsection",
+                    4 => "paragraph",
+                    _ => "subparagraph",
+                };
+                self.out.push_str(&format!("\\{command}{{"));
+                self.inlines(&i.children);
+                self.out.push('}');
+                self.label(i);
+                self.out.push_str("\n\n");
+            }
+            Block::ThematicBreak(_) => self
+                .out
+                .push_str("\\begin{center}\\rule{0.5\\linewidth}{0.5pt}\\end{center}\n\n"),
+            Block::Section(i) => {
+                // the label goes on the heading so that references point at it
+                if let Some((Block::Heading(h), rest)) = i.children.split_first() {
+                    let mut heading = h.clone();
+                    if let Some(id) = i.id() {
+                        heading
+                            .attributes_mut()
+                            .entry("id".to_string())
+                            .or_insert_with(|| id.to_string());
+                    }
+                    self.block(&Block::Heading(heading));
+                    self.blocks(rest);
+                } else {
+                    self.blocks(&i.children);
+                }
+            }
+            Block::Div(i) => self.blocks(&i.children),
+            Block::CodeBlock(i) => {
+                self.out.push_str("\\begin{verbatim}\n");
+                self.out.push_str(&i.text);
+                if !i.text.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.out.push_str("\\end{verbatim}\n\n");
+            }
+            Block::RawBlock(i) => {
+                if i.format == "latex" {
+                    self.out.push_str(&i.text);
+                    self.out.push('\n');
+                }
+            }
+            // there is no telling how to render a node of an unknown type, but its children can be
+            Block::Unknown(i) => {
+                if let Some(blocks) = i.blocks() {
+                    self.blocks(&blocks);
+                } else if let Some(inlines) = i.inlines() {
+                    self.inlines(&inlines);
+                    self.out.push_str("\n\n");
+                } else {
+                    self.comment(&i.tag);
+                    self.out.push('\n');
+                }
+            }
+            Block::BlockQuote(i) => self.environment("quote", &i.children),
+            Block::OrderedList(i) => {
+                self.out.push_str("\\begin{enumerate}\n");
+                let label = match i.style.number() {
+                    NumberStyle::Arabic => None,
+                    NumberStyle::LowerAlpha => Some("\\alph*"),
+                    NumberStyle::UpperAlpha => Some("\\Alph*"),
+                    NumberStyle::LowerRoman => Some("\\roman*"),
+                    NumberStyle::UpperRoman => Some("\\Roman*"),
+                };
+                if let Some(label) = label {
+                    self.out
+                        .push_str(&format!("\\renewcommand{{\\labelenumi}}{{{label}}}\n"));
+                }
+                if let Some(start) = i.start.filter(|s| *s > 1) {
+                    self.out
+                        .push_str(&format!("\\setcounter{{enumi}}{{{}}}\n", start - 1));
+                }
+                for item in &i.children {
+                    self.out.push_str("\\item ");
+                    self.blocks(&item.children);
+                }
+                self.out.push_str("\\end{enumerate}\n\n");
+            }
+            Block::BulletList(i) => {
+                self.out.push_str("\\begin{itemize}\n");
+                for item in &i.children {
+                    self.out.push_str("\\item ");
+                    self.blocks(&item.children);
+                }
+                self.out.push_str("\\end{itemize}\n\n");
+            }
+            Block::TaskList(i) => {
+                self.out.push_str("\\begin{itemize}\n");
+                for item in &i.children {
+                    if item.checkbox.is_checked() {
+                        self.out.push_str("\\item[$\\boxtimes$] ");
+                    } else {
+                        self.out.push_str("\\item[$\\square$] ");
+                    }
+                    self.blocks(&item.children);
+                }
+                self.out.push_str("\\end{itemize}\n\n");
+            }
+            Block::DefinitionList(i) => {
+                self.out.push_str("\\begin{description}\n");
+                for item in &i.children {
+                    self.out.push_str("\\item[");
+                    self.inlines(&item.term.children);
+                    self.out.push_str("] ");
+                    self.blocks(&item.definition.children);
+                }
+                self.out.push_str("\\end{description}\n\n");
+            }
+            Block::Table(i) => self.table(i),
+        }
+    }
+
+    fn table(&mut self, table: &Table) {
+        let width = table
+            .rows
+            .iter()
+            .map(|r| r.children.len())
+            .max()
+            .unwrap_or(0);
+        let columns: String = (0..width)
+            .map(|idx| {
+                let align = table
+                    .rows
+                    .iter()
+                    .find_map(|r| r.children.get(idx))
+                    .map(|c| c.align)
+                    .unwrap_or_default();
+                match align {
+                    Alignment::Default | Alignment::Left => 'l',
+                    Alignment::Right => 'r',
+                    Alignment::Center => 'c',
+                }
+            })
+            .collect();
+        self.out.push_str("\\begin{table}\n\\centering\n");
+        self.out
+            .push_str(&format!("\\begin{{tabular}}{{{columns}}}\n"));
+        for (idx, row) in table.rows.iter().enumerate() {
+            for (n, cell) in row.children.iter().enumerate() {
+                if n > 0 {
+                    self.out.push_str(" & ");
+                }
+                if cell.head {
+                    self.out.push_str("\\textbf{");
+                    self.inlines(&cell.children);
+                    self.out.push('}');
+                } else {
+                    self.inlines(&cell.children);
+                }
+            }
+            self.out.push_str(" \\\\\n");
+            let next_is_body = table.rows.get(idx + 1).is_some_and(|r| !r.head);
+            if row.head && next_is_body {
+                self.out.push_str("\\hline\n");
+            }
+        }
+        self.out.push_str("\\end{tabular}\n");
+        if !table.caption.children.is_empty() {
+            self.out.push_str("\\caption{");
+            self.inlines(&table.caption.children);
+            self.out.push_str("}\n");
+        }
+        self.out.push_str("\\end{table}\n\n");
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            self.inline(inline);
+        }
+    }
+
+    fn command(&mut self, command: &str, children: &[Inline]) {
+        self.out.push_str(&format!("\\{command}{{"));
+        self.inlines(children);
+        self.out.push('}');
+    }
+
+    fn destination<'b>(
+        &'b self,
+        destination: &'b Option<String>,
+        reference: &Option<String>,
+    ) -> Option<&'b str> {
+        match reference {
+            Some(r) => self
+                .doc
+                .references
+                .get(r)
+                .or_else(|| self.doc.auto_references.get(r))
+                .map(|r| r.destination.as_str()),
+            None => destination.as_deref(),
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Str(i) => self.escaped(&i.text),
+            Inline::SoftBreak(_) => self.out.push('\n'),
+            Inline::HardBreak(_) => self.out.push_str("\\\\\n"),
+            Inline::NonBreakingSpace(_) => self.out.push('~'),
+            Inline::Symb(i) => self.escaped(&format!(":{}:", i.alias)),
+            Inline::Verbatim(i) => {
+                self.out.push_str("\\texttt{");
+                self.escaped(&i.text);
+                self.out.push('}');
+            }
+            Inline::RawInline(i) => {
+                if i.format == "latex" {
+                    self.out.push_str(&i.text);
+                }
+            }
+            Inline::Unknown(i) => match i.inlines() {
+                Some(inlines) => self.inlines(&inlines),
+                None => self.comment(&i.tag),
+            },
+            Inline::InlineMath(i) => self.out.push_str(&format!("\\({}\\)", i.text)),
+            Inline::DisplayMath(i) => self.out.push_str(&format!("\\[{}\\]", i.text)),
+            Inline::Url(i) => {
+                self.out.push_str("\\url{");
+                escape_url(&i.text, &mut self.out);
+                self.out.push('}');
+            }
+            Inline::Email(i) => {
+                self.out.push_str("\\href{mailto:");
+                escape_url(&i.text, &mut self.out);
+                self.out.push_str("}{");
+                self.escaped(&i.text);
+                self.out.push('}');
+            }
+            Inline::FootnoteReference(i) => {
+                let doc = self.doc;
+                match doc.footnotes.get(&i.text) {
+                    Some(footnote) if self.footnote_depth == 0 => {
+                        self.footnote_depth += 1;
+                        self.out.push_str("\\footnote{");
+                        let start = self.out.len();
+                        self.blocks(&footnote.children);
+                        let trimmed = self.out[start..].trim_end().len();
+                        self.out.truncate(start + trimmed);
+                        self.out.push('}');
+                        self.footnote_depth -= 1;
+                    }
+                    _ => {
+                        self.out.push_str("\\textsuperscript{");
+                        self.escaped(&i.text);
+                        self.out.push('}');
+                    }
+                }
+            }
+            Inline::SmartPunctuation(i) => self.out.push_str(match i.punc_type {
+                SmartPunctuationType::LeftSingleQuote => "`",
+                SmartPunctuationType::RightSingleQuote => "'",
+                SmartPunctuationType::LeftDoubleQuote => "``",
+                SmartPunctuationType::RightDoubleQuote => "''",
+                SmartPunctuationType::Ellipses => "\\ldots{}",
+                SmartPunctuationType::EmDash => "---",
+                SmartPunctuationType::EnDash => "--",
+            }),
+            Inline::Emph(i) => self.command("emph", &i.children),
+            Inline::Strong(i) => self.command("textbf", &i.children),
+            Inline::Link(i) => match self.destination(&i.destination, &i.reference) {
+                Some(dest) => {
+                    if let Some(id) = dest.strip_prefix('#') {
+                        self.out.push_str(&format!("\\hyperref[{id}]{{"));
+                    } else {
+                        let mut escaped = String::new();
+                        escape_url(dest, &mut escaped);
+                        self.out.push_str(&format!("\\href{{{escaped}}}{{"));
+                    }
+                    self.inlines(&i.children);
+                    self.out.push('}');
+                }
+                None => self.inlines(&i.children),
+            },
+            Inline::Image(i) => {
+                if let Some(src) = self.destination(&i.destination, &i.reference) {
+                    let src = src.to_string();
+                    self.out.push_str("\\includegraphics{");
+                    self.escaped(&src);
+                    self.out.push('}');
+                }
+            }
+            Inline::Span(i) => self.inlines(&i.children),
+            Inline::Mark(i) => self.command("colorbox{yellow}", &i.children),
+            Inline::Superscript(i) => self.command("textsuperscript", &i.children),
+            Inline::Subscript(i) => self.command("textsubscript", &i.children),
+            Inline::Insert(i) => self.command("uline", &i.children),
+            Inline::Delete(i) => self.command("sout", &i.children),
+            Inline::DoubleQuoted(i) => {
+                self.out.push_str("``");
+                self.inlines(&i.children);
+                self.out.push_str("''");
+            }
+            Inline::SingleQuoted(i) => {
+                self.out.push('`');
+                self.inlines(&i.children);
+                self.out.push('\'');
+            }
+        }
+    }
+}
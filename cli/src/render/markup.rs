@@ -0,0 +1,520 @@
+//! Writing documents back out as djot or Markdown, which share most of their syntax.
+use djot_ast::{
+    attributes::{format_attributes, HasAttributes},
+    block::{Alignment, Block, BulletListStyle, FenceStyle, OrderedList, Table},
+    inline::{Inline, SmartPunctuationType},
+    text::inline_text,
+    Doc,
+};
+
+use super::{format_number, prefix_lines, unknown_comment};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    Djot,
+    /// CommonMark with GitHub's extensions for tables, task lists, strikethrough and footnotes.
+    ///
+    /// Attributes, divs and spans cannot be represented, so are dropped.
+    Markdown,
+}
+
+/// Escape the characters of text that would otherwise be read as markup, including `|` within a table.
+pub fn escape(flavor: Flavor, in_table: bool, text: &str, out: &mut String) {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let special = match (flavor, c) {
+            (_, '\\' | '`' | '*' | '_' | '[' | ']' | '<') => true,
+            (_, '|') => in_table,
+            (_, '!') => next == Some('['),
+            (Flavor::Djot, '{' | '}' | '~' | '^') => true,
+            (Flavor::Djot, '$') => next == Some('`'),
+            _ => false,
+        };
+        if special {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+pub fn render(doc: &Doc, flavor: Flavor) -> String {
+    let mut writer = Writer {
+        doc,
+        flavor,
+        in_table: false,
+    };
+    let mut parts = vec![writer.blocks(&doc.children)];
+
+    let mut references: Vec<_> = doc.references.values().collect();
+    references.sort_by(|a, b| a.label.cmp(&b.label));
+    for reference in references {
+        let definition = format!("[{}]: {}", reference.label, reference.destination);
+        parts.push(writer.with_attributes(reference, definition));
+    }
+
+    let mut footnotes: Vec<_> = doc.footnotes.values().collect();
+    footnotes.sort_by(|a, b| a.label.cmp(&b.label));
+    let indent = match flavor {
+        Flavor::Djot => "  ",
+        Flavor::Markdown => "    ",
+    };
+    for footnote in footnotes {
+        let content = writer.blocks(&footnote.children);
+        let first = format!("[^{}]: ", footnote.label);
+        parts.push(prefix_lines(&content, &first, indent));
+    }
+
+    let mut out = join_blocks(parts);
+    out.push('\n');
+    out
+}
+
+fn join_blocks(parts: impl IntoIterator<Item = String>) -> String {
+    let parts: Vec<_> = parts.into_iter().filter(|p| !p.is_empty()).collect();
+    parts.join("\n\n")
+}
+
+/// Get the length of the longest run of a character in some text.
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+struct Writer<'a> {
+    doc: &'a Doc,
+    flavor: Flavor,
+    /// Whether pipes need escaping.
+    in_table: bool,
+}
+
+impl<'a> Writer<'a> {
+    /// Format explicit attributes as a djot attribute block, e.g. `{#id .class key="value"}`.
+    fn attributes(&self, node: &impl HasAttributes) -> Option<String> {
+        if self.flavor != Flavor::Djot || node.attributes().is_empty() {
+            return None;
+        }
+        Some(format_attributes(node.attributes()))
+    }
+
+    /// Put a block's attributes on the line before it.
+    fn with_attributes(&self, node: &impl HasAttributes, body: String) -> String {
+        match self.attributes(node) {
+            Some(attrs) if body.is_empty() => attrs,
+            Some(attrs) => format!("{attrs}\n{body}"),
+            None => body,
+        }
+    }
+
+    /// A comment in place of a node of an unknown type.
+    fn comment(&self, tag: &str) -> String {
+        match self.flavor {
+            Flavor::Djot => format!("{{% {} %}}", unknown_comment(tag)),
+            Flavor::Markdown => format!("<!-- {} -->", unknown_comment(tag)),
+        }
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) -> String {
+        let parts: Vec<_> = blocks.iter().map(|b| self.block(b)).collect();
+        join_blocks(parts)
+    }
+
+    fn block(&mut self, block: &Block) -> String {
+        let body = match block {
+            Block::Para(i) => self.inlines(&i.children),
+            Block::Heading(i) => {
+                let text = self.inlines(&i.children).replace('\n', " ");
+                format!("{} {text}", "#".repeat(i.level.clamp(1, 6) as usize))
+            }
+            Block::ThematicBreak(_) => "* * *".to_string(),
+            Block::Section(i) => self.blocks(&i.children),
+            Block::Div(i) => {
+                let content = self.blocks(&i.children);
+                if self.flavor == Flavor::Markdown {
+                    return content;
+                }
+                let depth = content
+                    .lines()
+                    .filter(|l| l.starts_with(":::"))
+                    .map(|l| l.len() - l.trim_start_matches(':').len())
+                    .max()
+                    .unwrap_or(0);
+                let fence = ":".repeat(depth.max(2) + 1);
+                format!("{fence}\n{content}\n{fence}")
+            }
+            Block::CodeBlock(i) => {
+                let fence = "`".repeat(longest_run(&i.text, '`').max(2) + 1);
+                let info = i
+                    .lang
+                    .as_deref()
+                    .map(|l| format!(" {l}"))
+                    .unwrap_or_default();
+                let newline = if i.text.ends_with('\n') || i.text.is_empty() {
+                    ""
+                } else {
+                    "\n"
+                };
+                format!("{fence}{info}\n{}{newline}{fence}", i.text)
+            }
+            Block::RawBlock(i) => match self.flavor {
+                Flavor::Djot => {
+                    let fence = "`".repeat(longest_run(&i.text, '`').max(2) + 1);
+                    let newline = if i.text.ends_with('\n') { "" } else { "\n" };
+                    format!("{fence} ={}\n{}{newline}{fence}", i.format, i.text)
+                }
+                Flavor::Markdown if i.format == "html" => i.text.trim_end().to_string(),
+                Flavor::Markdown => return String::new(),
+            },
+            // there is no telling how to render a node of an unknown type, but its children can be
+            Block::Unknown(i) => {
+                if let Some(blocks) = i.blocks() {
+                    return self.blocks(&blocks);
+                }
+                match i.inlines() {
+                    Some(inlines) => self.inlines(&inlines),
+                    None => return self.comment(&i.tag),
+                }
+            }
+            Block::BlockQuote(i) => prefix_lines(&self.blocks(&i.children), "> ", "> "),
+            Block::OrderedList(i) => self.ordered_list(i),
+            Block::BulletList(i) => {
+                let marker = match i.style {
+                    BulletListStyle::Dash => "-",
+                    BulletListStyle::Plus => "+",
+                    BulletListStyle::Asterisk => "*",
+                };
+                let items: Vec<_> = i
+                    .children
+                    .iter()
+                    .map(|item| {
+                        let content = self.blocks(&item.children);
+                        self.with_attributes(item, list_item(marker, &content))
+                    })
+                    .collect();
+                join_items(items, i.tight)
+            }
+            Block::TaskList(i) => {
+                let items: Vec<_> = i
+                    .children
+                    .iter()
+                    .map(|item| {
+                        let marker = if item.checkbox.is_checked() {
+                            "- [x]"
+                        } else {
+                            "- [ ]"
+                        };
+                        let content = self.blocks(&item.children);
+                        let item_text = prefix_lines(&content, &format!("{marker} "), "  ");
+                        self.with_attributes(item, item_text)
+                    })
+                    .collect();
+                join_items(items, i.tight)
+            }
+            Block::DefinitionList(i) => {
+                let items: Vec<_> = i
+                    .children
+                    .iter()
+                    .map(|item| {
+                        let term = self.inlines(&item.term.children);
+                        let definition = self.blocks(&item.definition.children);
+                        match self.flavor {
+                            Flavor::Djot => {
+                                let content = join_blocks([term, definition]);
+                                prefix_lines(&content, ": ", "  ")
+                            }
+                            Flavor::Markdown => join_blocks([format!("**{term}**"), definition]),
+                        }
+                    })
+                    .collect();
+                join_items(items, false)
+            }
+            Block::Table(i) => self.table(i),
+        };
+        self.with_attributes(block, body)
+    }
+
+    fn ordered_list(&mut self, list: &OrderedList) -> String {
+        let start = list.start.unwrap_or(1);
+        let items: Vec<_> = list
+            .children
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let n = start.saturating_add(idx as u64);
+                let marker = match self.flavor {
+                    Flavor::Djot => {
+                        let number = format_number(list.style.number(), n);
+                        match list.style.fence() {
+                            FenceStyle::Dot => format!("{number}."),
+                            FenceStyle::RightParen => format!("{number})"),
+                            FenceStyle::BothParen => format!("({number})"),
+                        }
+                    }
+                    // Markdown only has arabic numbering
+                    Flavor::Markdown => match list.style.fence() {
+                        FenceStyle::Dot => format!("{n}."),
+                        FenceStyle::RightParen | FenceStyle::BothParen => format!("{n})"),
+                    },
+                };
+                let content = self.blocks(&item.children);
+                self.with_attributes(item, list_item(&marker, &content))
+            })
+            .collect();
+        join_items(items, list.tight)
+    }
+
+    fn table(&mut self, table: &Table) -> String {
+        self.in_table = true;
+        let mut lines = Vec::new();
+        let width = table
+            .rows
+            .iter()
+            .map(|r| r.children.len())
+            .max()
+            .unwrap_or(0);
+        let separator = |aligns: Vec<Alignment>| {
+            let cells: Vec<_> = aligns
+                .into_iter()
+                .map(|a| match a {
+                    Alignment::Default => "---",
+                    Alignment::Left => ":--",
+                    Alignment::Right => "--:",
+                    Alignment::Center => ":-:",
+                })
+                .collect();
+            format!("|{}|", cells.join("|"))
+        };
+        let has_head = table.rows.first().is_some_and(|r| r.head);
+        if self.flavor == Flavor::Markdown && !has_head && width > 0 {
+            // GitHub tables must start with a header
+            lines.push(format!("|{}|", vec!["   "; width].join("|")));
+            let aligns = table.rows[0].children.iter().map(|c| c.align).collect();
+            lines.push(separator(aligns));
+        }
+        for (idx, row) in table.rows.iter().enumerate() {
+            let cells: Vec<_> = row
+                .children
+                .iter()
+                .map(|c| format!(" {} ", self.inlines(&c.children)))
+                .collect();
+            lines.push(format!("|{}|", cells.join("|")));
+            let next_is_body = table.rows.get(idx + 1).is_none_or(|r| !r.head);
+            if row.head && next_is_body {
+                lines.push(separator(row.children.iter().map(|c| c.align).collect()));
+            }
+        }
+        self.in_table = false;
+
+        let mut out = lines.join("\n");
+        if !table.caption.children.is_empty() {
+            let caption = self.inlines(&table.caption.children);
+            match self.flavor {
+                Flavor::Djot => out.push_str(&format!("\n\n^ {caption}")),
+                Flavor::Markdown => out.push_str(&format!("\n\n{caption}")),
+            }
+        }
+        out
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) -> String {
+        let mut out = String::new();
+        for inline in inlines {
+            self.inline(inline, &mut out);
+        }
+        out
+    }
+
+    fn escape(&self, text: &str, out: &mut String) {
+        escape(self.flavor, self.in_table, text, out);
+    }
+
+    /// Write emphasis-like markup, using the braced form where the plain delimiters would not parse.
+    fn delimited(&mut self, delim: &str, children: &[Inline], out: &mut String) {
+        let content = self.inlines(children);
+        let needs_braces = content.is_empty()
+            || content.starts_with(char::is_whitespace)
+            || content.ends_with(char::is_whitespace);
+        if self.flavor == Flavor::Djot && needs_braces {
+            out.push_str(&format!("{{{delim}{content}{delim}}}"));
+        } else {
+            out.push_str(&format!("{delim}{content}{delim}"));
+        }
+    }
+
+    fn html(&mut self, tag: &str, children: &[Inline], out: &mut String) {
+        let content = self.inlines(children);
+        out.push_str(&format!("<{tag}>{content}</{tag}>"));
+    }
+
+    /// Write a link or image's destination, as a reference if that can be represented.
+    fn destination(
+        &self,
+        destination: &Option<String>,
+        reference: &Option<String>,
+        text: &str,
+    ) -> String {
+        if let Some(r) = reference {
+            let explicit = self.doc.references.contains_key(r);
+            if self.flavor == Flavor::Djot || explicit {
+                return if r == text {
+                    "[]".to_string()
+                } else {
+                    format!("[{r}]")
+                };
+            }
+            if let Some(auto) = self.doc.auto_references.get(r) {
+                return format!("({})", auto.destination);
+            }
+            return format!("[{r}]");
+        }
+        format!("({})", destination.as_deref().unwrap_or_default())
+    }
+
+    fn inline(&mut self, inline: &Inline, out: &mut String) {
+        let start = out.len();
+        match inline {
+            Inline::Str(i) => self.escape(&i.text, out),
+            Inline::SoftBreak(_) => out.push('\n'),
+            Inline::HardBreak(_) => out.push_str("\\\n"),
+            Inline::NonBreakingSpace(_) => match self.flavor {
+                Flavor::Djot => out.push_str("\\ "),
+                Flavor::Markdown => out.push_str("&nbsp;"),
+            },
+            Inline::Symb(i) => out.push_str(&format!(":{}:", i.alias)),
+            Inline::Verbatim(i) => out.push_str(&verbatim(&i.text)),
+            Inline::RawInline(i) => match self.flavor {
+                Flavor::Djot => out.push_str(&format!("{}{{={}}}", verbatim(&i.text), i.format)),
+                Flavor::Markdown if i.format == "html" => out.push_str(&i.text),
+                Flavor::Markdown => (),
+            },
+            Inline::Unknown(i) => match i.inlines() {
+                Some(inlines) => inlines.iter().for_each(|child| self.inline(child, out)),
+                None => out.push_str(&self.comment(&i.tag)),
+            },
+            Inline::InlineMath(i) => match self.flavor {
+                Flavor::Djot => out.push_str(&format!("${}", verbatim(&i.text))),
+                Flavor::Markdown => out.push_str(&format!("${}$", i.text)),
+            },
+            Inline::DisplayMath(i) => match self.flavor {
+                Flavor::Djot => out.push_str(&format!("$${}", verbatim(&i.text))),
+                Flavor::Markdown => out.push_str(&format!("$${}$$", i.text)),
+            },
+            Inline::Url(i) => out.push_str(&format!("<{}>", i.text)),
+            Inline::Email(i) => out.push_str(&format!("<{}>", i.text)),
+            Inline::FootnoteReference(i) => out.push_str(&format!("[^{}]", i.text)),
+            Inline::SmartPunctuation(i) => match self.flavor {
+                Flavor::Djot => out.push_str(&i.text),
+                Flavor::Markdown => out.push_str(match i.punc_type {
+                    SmartPunctuationType::LeftSingleQuote => "‘",
+                    SmartPunctuationType::RightSingleQuote => "’",
+                    SmartPunctuationType::LeftDoubleQuote => "“",
+                    SmartPunctuationType::RightDoubleQuote => "”",
+                    SmartPunctuationType::Ellipses => "…",
+                    SmartPunctuationType::EmDash => "—",
+                    SmartPunctuationType::EnDash => "–",
+                }),
+            },
+            Inline::Emph(i) => match self.flavor {
+                Flavor::Djot => self.delimited("_", &i.children, out),
+                Flavor::Markdown => self.delimited("*", &i.children, out),
+            },
+            Inline::Strong(i) => match self.flavor {
+                Flavor::Djot => self.delimited("*", &i.children, out),
+                Flavor::Markdown => self.delimited("**", &i.children, out),
+            },
+            Inline::Link(i) => {
+                let text = self.inlines(&i.children);
+                let destination =
+                    self.destination(&i.destination, &i.reference, &inline_text(&i.children));
+                out.push_str(&format!("[{text}]{destination}"));
+            }
+            Inline::Image(i) => {
+                let text = self.inlines(&i.children);
+                let destination =
+                    self.destination(&i.destination, &i.reference, &inline_text(&i.children));
+                out.push_str(&format!("![{text}]{destination}"));
+            }
+            Inline::Span(i) => {
+                let text = self.inlines(&i.children);
+                match self.attributes(i) {
+                    Some(attrs) => out.push_str(&format!("[{text}]{attrs}")),
+                    None => out.push_str(&text),
+                }
+                return;
+            }
+            Inline::Mark(i) => match self.flavor {
+                Flavor::Djot => self.delimited("=", &i.children, out),
+                Flavor::Markdown => self.html("mark", &i.children, out),
+            },
+            Inline::Superscript(i) => match self.flavor {
+                Flavor::Djot => self.delimited("^", &i.children, out),
+                Flavor::Markdown => self.html("sup", &i.children, out),
+            },
+            Inline::Subscript(i) => match self.flavor {
+                Flavor::Djot => self.delimited("~", &i.children, out),
+                Flavor::Markdown => self.html("sub", &i.children, out),
+            },
+            Inline::Insert(i) => match self.flavor {
+                Flavor::Djot => self.delimited("+", &i.children, out),
+                Flavor::Markdown => self.html("ins", &i.children, out),
+            },
+            Inline::Delete(i) => match self.flavor {
+                Flavor::Djot => self.delimited("-", &i.children, out),
+                Flavor::Markdown => self.delimited("~~", &i.children, out),
+            },
+            Inline::DoubleQuoted(i) => {
+                let text = self.inlines(&i.children);
+                match self.flavor {
+                    Flavor::Djot => out.push_str(&format!("\"{text}\"")),
+                    Flavor::Markdown => out.push_str(&format!("“{text}”")),
+                }
+            }
+            Inline::SingleQuoted(i) => {
+                let text = self.inlines(&i.children);
+                match self.flavor {
+                    Flavor::Djot => out.push_str(&format!("'{text}'")),
+                    Flavor::Markdown => out.push_str(&format!("‘{text}’")),
+                }
+            }
+        }
+        if let Some(attrs) = self.attributes(inline) {
+            if matches!(inline, Inline::Str(_)) {
+                // attributes on bare text need a span to attach to
+                let text = out.split_off(start);
+                out.push_str(&format!("[{text}]"));
+            }
+            out.push_str(&attrs);
+        }
+    }
+}
+
+/// Write verbatim text with a backtick fence longer than any run of backticks inside it.
+fn verbatim(text: &str) -> String {
+    let fence = "`".repeat(longest_run(text, '`') + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{fence} {text} {fence}")
+    } else {
+        format!("{fence}{text}{fence}")
+    }
+}
+
+fn list_item(marker: &str, content: &str) -> String {
+    if content.is_empty() {
+        return marker.to_string();
+    }
+    let indent = " ".repeat(marker.chars().count() + 1);
+    prefix_lines(content, &format!("{marker} "), &indent)
+}
+
+fn join_items(items: Vec<String>, tight: bool) -> String {
+    items.join(if tight { "\n" } else { "\n\n" })
+}
@@ -0,0 +1,141 @@
+//! Rendering documents to other formats.
+use clap::ValueEnum;
+use djot_ast::{attributes::HasAttributes, text::blocks_text, Doc};
+
+mod html;
+mod latex;
+mod markup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Html,
+    Plain,
+    Djot,
+    #[value(alias = "markdown")]
+    Md,
+    Latex,
+}
+
+pub fn render(doc: &Doc, format: Format) -> String {
+    match format {
+        Format::Html => html::render(doc),
+        Format::Plain => {
+            let mut text = blocks_text(&doc.children);
+            text.push('\n');
+            text
+        }
+        Format::Djot => markup::render(doc, markup::Flavor::Djot),
+        Format::Md => markup::render(doc, markup::Flavor::Markdown),
+        Format::Latex => latex::render(doc),
+    }
+}
+
+/// Escape text for djot, so that it reads back as the same text.
+pub fn escape_djot(text: &str) -> String {
+    let mut out = String::new();
+    markup::escape(markup::Flavor::Djot, false, text, &mut out);
+    out
+}
+
+/// Get a node's explicit and then automatic attributes in order, the explicit ones taking precedence.
+fn node_attributes(node: &impl HasAttributes, include_auto: bool) -> Vec<(&str, &str)> {
+    let mut attrs: Vec<(&str, &str)> = node
+        .attributes()
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    if include_auto {
+        for (k, v) in node.auto_attributes() {
+            if !node.attributes().contains_key(k) {
+                attrs.push((k, v));
+            }
+        }
+    }
+    attrs
+}
+
+/// The text of a comment standing in for a node of an unknown type whose children cannot be found.
+///
+/// The node's tag is untrusted, so only its ASCII letters, digits and underscores are kept.
+fn unknown_comment(tag: &str) -> String {
+    let tag: String = tag
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    format!("unknown node: {tag}")
+}
+
+/// Prefix the first line of some text with `first` and the rest with `rest`,
+/// leaving blank lines without trailing whitespace.
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    let mut out = String::new();
+    for (idx, line) in text.split('\n').enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        let prefix = if idx == 0 { first } else { rest };
+        if line.is_empty() {
+            out.push_str(prefix.trim_end());
+        } else {
+            out.push_str(prefix);
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+/// The largest number written in roman numerals; larger ones, and 0, are written in arabic numerals.
+const MAX_ROMAN: u64 = 3999;
+
+/// Get the number of an ordered list item in the given numbering style.
+///
+/// There are no letters or roman numerals for 0, which is written in arabic numerals.
+fn format_number(style: djot_ast::block::NumberStyle, n: u64) -> String {
+    use djot_ast::block::NumberStyle;
+    match style {
+        _ if n == 0 => n.to_string(),
+        NumberStyle::LowerRoman | NumberStyle::UpperRoman if n > MAX_ROMAN => n.to_string(),
+        NumberStyle::Arabic => n.to_string(),
+        NumberStyle::LowerAlpha => alpha(n),
+        NumberStyle::UpperAlpha => alpha(n).to_uppercase(),
+        NumberStyle::LowerRoman => roman(n),
+        NumberStyle::UpperRoman => roman(n).to_uppercase(),
+    }
+}
+
+fn alpha(mut n: u64) -> String {
+    let mut out = Vec::new();
+    while n > 0 {
+        n -= 1;
+        out.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    out.reverse();
+    String::from_utf8(out).expect("ascii")
+}
+
+fn roman(mut n: u64) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
@@ -0,0 +1,47 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use djot_ast::{text::blocks_text, traverse::NodeRef, Doc};
+use serde_json::{json, Value};
+
+pub struct Stats {
+    words: usize,
+    characters: usize,
+    /// The number of nodes with each tag, including those in footnotes.
+    nodes: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    pub fn new(doc: &Doc) -> Self {
+        let text = blocks_text(&doc.children);
+        let mut nodes = BTreeMap::new();
+        for (_, node) in doc.nodes() {
+            if !matches!(node, NodeRef::Doc(_)) {
+                *nodes.entry(node.tag().to_string()).or_default() += 1;
+            }
+        }
+        Self {
+            words: text.split_whitespace().count(),
+            characters: text.chars().count(),
+            nodes,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "words": self.words,
+            "characters": self.characters,
+            "nodes": self.nodes,
+        })
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "words: {}", self.words)?;
+        writeln!(f, "characters: {}", self.characters)?;
+        for (tag, count) in &self.nodes {
+            writeln!(f, "{tag}: {count}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use djot_ast::{attributes::HasAttributes, block::Block, text::inline_text, Doc};
+
+use crate::render::escape_djot;
+
+struct Entry {
+    level: u64,
+    text: String,
+    id: Option<String>,
+}
+
+fn collect(blocks: &[Block], out: &mut Vec<Entry>) {
+    for block in blocks {
+        match block {
+            Block::Section(s) => {
+                // a section's id belongs to its heading
+                if let Some(Block::Heading(h)) = s.children.first() {
+                    let id = h.id().or_else(|| s.id());
+                    out.push(Entry {
+                        level: h.level,
+                        text: inline_text(&h.children),
+                        id: id.map(str::to_string),
+                    });
+                    collect(&s.children[1..], out);
+                } else {
+                    collect(&s.children, out);
+                }
+            }
+            Block::Heading(h) => out.push(Entry {
+                level: h.level,
+                text: inline_text(&h.children),
+                id: h.id().map(str::to_string),
+            }),
+            Block::Div(d) => collect(&d.children, out),
+            _ => (),
+        }
+    }
+}
+
+/// Format the document's headings, down to `depth`, as a nested djot list of links.
+pub fn toc(doc: &Doc, depth: u64) -> String {
+    let mut entries = Vec::new();
+    collect(&doc.children, &mut entries);
+    entries.retain(|e| e.level <= depth);
+    let min_level = entries.iter().map(|e| e.level).min().unwrap_or(1);
+
+    let mut out = String::new();
+    let mut previous_depth = 0;
+    for entry in entries {
+        // a list nests at most one deeper than the one before it, however far the levels skip
+        let depth = (entry.level - min_level).min(previous_depth + 1);
+        // sublists need a blank line before them
+        if depth > previous_depth {
+            out.push('\n');
+        }
+        previous_depth = depth;
+        let indent = "  ".repeat(depth as usize);
+        let text = escape_djot(&entry.text);
+        match entry.id {
+            Some(id) => out.push_str(&format!("{indent}- [{text}](#{id})\n")),
+            None => out.push_str(&format!("{indent}- {text}\n")),
+        }
+    }
+    out
+}
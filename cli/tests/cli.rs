@@ -0,0 +1,208 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+const README: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../tests/fixtures/readme.dj.json"
+);
+const PANDOC: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../tests/fixtures/pandoc-manual.json"
+);
+
+fn run(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_djot-ast"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = run(args, b"");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn validate() {
+    assert_eq!(stdout(&["validate", README, PANDOC]), "");
+
+    let broken = r##"{"tag":"doc","references":{},"autoReferences":{},"footnotes":{},"children":[
+        {"tag":"para","children":[{"tag":"footnote_reference","text":"missing"}]}]}"##;
+    let output = run(&["validate"], broken.as_bytes());
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("<stdin>: error at '/children/0/children/0'"));
+}
+
+#[test]
+fn render() {
+    let html = stdout(&["render", "--to", "html", README]);
+    assert!(html.starts_with("<section id=\"djot\">\n<h1>Djot</h1>\n"));
+    assert!(html.contains("<a href=\"#rationale\">Rationale</a>"));
+
+    let plain = stdout(&["render", "--to", "plain", README]);
+    assert!(plain.starts_with("Djot\n\nDjot is a light markup syntax."));
+
+    let djot = stdout(&["render", "--to", "djot", README]);
+    assert!(djot.contains("\n## Rationale\n"));
+    assert!(djot.contains("  ````\n  ``` =html\n"));
+
+    let md = stdout(&["render", "--to", "md", PANDOC]);
+    assert!(md.starts_with("# Synopsis\n\n`pandoc` \\[*options*\\]"));
+
+    let latex = stdout(&["render", "--to", "latex", PANDOC]);
+    assert!(latex.starts_with("\\section{Synopsis}\\label{synopsis}\n"));
+    assert!(latex.contains("\\footnote{"));
+
+    let footnotes = stdout(&["render", "--to", "html", PANDOC]);
+    assert!(footnotes.contains("<section role=\"doc-endnotes\">"));
+}
+
+#[test]
+fn render_untrusted() {
+    let doc = r##"{"tag":"doc","references":{},"autoReferences":{},"footnotes":{},"children":[
+        {"tag":"code_block","lang":"x\"><script>","text":"code"},
+        {"tag":"para","children":[{"tag":"symb","alias":"<b>"},
+            {"tag":"span","attributes":{"onclick=\"alert(1)\" x":"1","class":"mine"},"children":[]}]},
+        {"tag":"task_list","tight":true,"attributes":{"class":"tasks"},"children":[]},
+        {"tag":"para","children":[{"tag":"url","text":"https://a.org","attributes":{"href":"https://b.org"}}]},
+        {"tag":"para","children":[{"tag":"url","text":"https://a.org/a_b%20c#x"}]},
+        {"tag":"ordered_list","style":"i.","tight":true,"start":18446744073709551615,"children":[
+            {"tag":"list_item","children":[]},{"tag":"list_item","children":[]}]}
+    ]}"##;
+    let output = run(&["render", "--to", "html"], doc.as_bytes());
+    assert!(output.status.success());
+    let html = String::from_utf8(output.stdout).unwrap();
+    assert!(html.contains("<code class=\"language-x&quot;&gt;&lt;script&gt;\">"));
+    assert!(html.contains(":&lt;b&gt;:"));
+    assert!(html.contains("<span class=\"mine\">"));
+    assert!(html.contains("<ul class=\"task-list tasks\">"));
+    assert!(html.contains("<a href=\"https://b.org\">"));
+
+    let output = run(&["render", "--to", "djot"], doc.as_bytes());
+    assert!(output.status.success());
+    let djot = String::from_utf8(output.stdout).unwrap();
+    assert!(djot.contains("18446744073709551615."));
+
+    let output = run(&["render", "--to", "latex"], doc.as_bytes());
+    assert!(output.status.success());
+    let latex = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        latex.contains("\\url{https://a.org/a_b\\%20c\\#x}"),
+        "{latex}"
+    );
+}
+
+#[test]
+fn render_unknown() {
+    let doc = r#"{"tag":"doc","references":{},"autoReferences":{},"footnotes":{},"children":[
+        {"tag":"admonition","children":[{"tag":"para","children":[{"tag":"str","text":"inside"}]}]},
+        {"tag":"para","children":[{"tag":"str","text":"a "},
+            {"tag":"highlight","children":[{"tag":"str","text":"marked"}]},
+            {"tag":"widget","name":"x"}]},
+        {"tag":"chart","data":[1]},
+        {"tag":"caption_block","children":[{"tag":"str","text":"inline"}]}
+    ]}"#;
+    let rendered = |format| {
+        let output = run(&["render", "--to", format], doc.as_bytes());
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(
+        rendered("html"),
+        "<p>inside</p>\n<p>a marked<!-- unknown node: widget --></p>\n\
+         <!-- unknown node: chart -->\n<p>inline</p>\n"
+    );
+    assert_eq!(
+        rendered("djot"),
+        "inside\n\na marked{% unknown node: widget %}\n\n{% unknown node: chart %}\n\ninline\n"
+    );
+    let latex = rendered("latex");
+    assert!(
+        latex.contains("inside\n\na marked% unknown node: widget\n"),
+        "{latex}"
+    );
+    assert!(latex.contains("% unknown node: chart\n"), "{latex}");
+}
+
+#[test]
+fn toc() {
+    let toc = stdout(&["toc", README]);
+    assert!(toc.starts_with("- [Djot](#djot)\n\n  - [Rationale](#rationale)\n"));
+    assert_eq!(
+        stdout(&["toc", "--depth", "1", README]),
+        "- [Djot](#djot)\n"
+    );
+
+    let doc = r#"{"tag":"doc","references":{},"autoReferences":{},"footnotes":{},"children":[
+        {"tag":"heading","level":1,"children":[{"tag":"str","text":"a [b] *c*"}],"attributes":{"id":"a"}},
+        {"tag":"heading","level":18446744073709551615,"children":[{"tag":"str","text":"deep"}]},
+        {"tag":"heading","level":2,"children":[{"tag":"str","text":"two"}]}
+    ]}"#;
+    let output = run(&["toc", "--depth", "18446744073709551615"], doc.as_bytes());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "- [a \\[b\\] \\*c\\*](#a)\n\n  - deep\n  - two\n"
+    );
+}
+
+#[test]
+fn stats() {
+    let stats = stdout(&["stats", "--json", README]);
+    let stats: serde_json::Value = serde_json::from_str(&stats).unwrap();
+    assert_eq!(stats["nodes"]["heading"], 6);
+    assert!(stats["words"].as_u64().unwrap() > 1000);
+    assert!(stdout(&["stats", README]).contains("heading: 6\n"));
+}
+
+#[test]
+fn query() {
+    let headings = stdout(&["query", "heading", README]);
+    let lines: Vec<_> = headings.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "/children/0/children/0\tDjot");
+
+    let nested = stdout(&["query", "section section > heading:first-child", README]);
+    assert_eq!(nested.lines().count(), 5);
+    assert!(!run(&["query", "heading[", README], b"").status.success());
+}
+
+#[test]
+fn fmt_and_convert() {
+    let formatted = stdout(&["fmt", README]);
+    let reformatted = run(&["fmt"], formatted.as_bytes());
+    let formatted: serde_json::Value = serde_json::from_str(&formatted).unwrap();
+    let reformatted: serde_json::Value = serde_json::from_slice(&reformatted.stdout).unwrap();
+    assert_eq!(reformatted, formatted);
+
+    let cbor = run(&["convert", "--from", "json", "--to", "cbor", README], b"");
+    assert!(cbor.status.success());
+    let json = run(&["--from", "cbor", "convert", "--to", "json"], &cbor.stdout);
+    let json: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+    let original: serde_json::Value =
+        serde_json::from_str(&stdout(&["convert", "--to", "json", README])).unwrap();
+    assert_eq!(json, original);
+}
+
+#[test]
+fn errors() {
+    let output = run(&["--from", "djot", "toc"], b"# Heading");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not supported yet"));
+
+    let output = run(&["toc"], br#"{"tag":"list_item","children":[]}"#);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("found a 'list_item' node"));
+}
@@ -0,0 +1,142 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use djot_ast::{
+    attributes::{HasAttributes, Pos},
+    block::Block,
+    deserialize::Lenient,
+    locate::PosIndex,
+    path::NodePath,
+    source::{LineIndex, Utf16Position},
+    traverse::NodeRef,
+    Doc,
+};
+use lsp_types::{Position, Range};
+
+/// The command used to convert djot source into djot.js JSON if the client does not configure one.
+pub const DEFAULT_PARSER: &[&str] = &["djot", "--to", "astjson", "--sourcepos"];
+
+/// Run the parser command with the source on its standard input, and read the AST from its standard output.
+pub fn parse(command: &[String], source: &str) -> Result<Doc, String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| "no parser command configured".to_string())?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run parser '{program}': {e}"))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let source = source.to_string();
+    // write on another thread so that a parser which does not read its input cannot deadlock
+    let writer = std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("parser failed: {e}"))?;
+    // the parser may legitimately exit without reading everything
+    let _ = writer.join();
+
+    if !output.status.success() {
+        return Err(format!(
+            "parser exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    // a newer parser may produce types of node this crate does not know yet
+    serde_json::from_slice(&output.stdout)
+        .map(|Lenient(doc)| doc)
+        .map_err(|e| format!("invalid parser output: {e}"))
+}
+
+/// An open text document and its AST, if it could be parsed.
+pub struct Document {
+    pub text: String,
+    pub ast: Result<Doc, String>,
+}
+
+fn to_lsp(p: Utf16Position) -> Position {
+    Position {
+        line: p.line,
+        character: p.character,
+    }
+}
+
+impl Document {
+    pub fn new(text: String, parser: &[String]) -> Self {
+        let ast = parse(parser, &text);
+        Self { text, ast }
+    }
+
+    pub fn line_index(&self) -> LineIndex<'_> {
+        LineIndex::new(&self.text)
+    }
+
+    /// Get the range of a position in the source.
+    pub fn range(&self, index: &LineIndex, pos: &Pos) -> Option<Range> {
+        index.utf16_range(pos).map(|r| Range {
+            start: to_lsp(r.start),
+            end: to_lsp(r.end),
+        })
+    }
+
+    /// Get the range of a byte range in the source.
+    pub fn byte_range(&self, index: &LineIndex, range: std::ops::Range<usize>) -> Option<Range> {
+        index.utf16_range_of_bytes(range).map(|r| Range {
+            start: to_lsp(r.start),
+            end: to_lsp(r.end),
+        })
+    }
+
+    /// Get the range of the node at the path, falling back to its nearest ancestor with a position.
+    ///
+    /// A section's range also covers its heading, which djot.js leaves out of the section's position.
+    pub fn node_range(&self, doc: &Doc, index: &LineIndex, path: &NodePath) -> Option<Range> {
+        let mut path = Some(path.clone());
+        while let Some(p) = path {
+            if let Some(pos) = doc.get(&p).and_then(node_pos) {
+                return self.range(index, &pos);
+            }
+            path = p.parent();
+        }
+        None
+    }
+
+    /// Find the innermost node at the cursor, returning the paths of it and all its ancestors,
+    /// innermost first.
+    pub fn paths_at(&self, doc: &Doc, index: &LineIndex, position: Position) -> Vec<NodePath> {
+        let Some(loc) = index.loc_from_utf16(Utf16Position {
+            line: position.line,
+            character: position.character,
+        }) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        let mut path = PosIndex::new(doc).node_at_loc(&loc).cloned();
+        while let Some(p) = path {
+            path = p.parent();
+            out.push(p);
+        }
+        out
+    }
+}
+
+/// Get the position of a node, extending sections to cover their heading.
+pub fn node_pos(node: NodeRef) -> Option<Pos> {
+    let pos = node.meta().pos().copied();
+    let NodeRef::Block(Block::Section(s)) = node else {
+        return pos;
+    };
+    match (s.children.first().and_then(|h| h.pos()), pos) {
+        (Some(h), Some(p)) => Some(Pos {
+            start: h.start,
+            end: p.end,
+        }),
+        (h, p) => p.or(h.copied()),
+    }
+}
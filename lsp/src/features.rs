@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use djot_ast::{
+    attributes::{HasAttributes, Pos},
+    block::Block,
+    inline::Inline,
+    path::{NodePath, PathRoot},
+    source::LineIndex,
+    text::{blocks_text, inline_text},
+    traverse::NodeRef,
+    validate::{validate, Severity},
+    Doc,
+};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DocumentSymbol, FoldingRange, FoldingRangeKind, Hover,
+    HoverContents, Location, MarkupContent, MarkupKind, Position, Range, SymbolKind, TextEdit, Uri,
+    WorkspaceEdit,
+};
+
+use crate::document::{node_pos, Document};
+
+/// Longest footnote preview shown on hover, in characters.
+const PREVIEW_LENGTH: usize = 500;
+
+pub fn diagnostics(document: &Document) -> Vec<Diagnostic> {
+    let doc = match &document.ast {
+        Ok(doc) => doc,
+        Err(e) => {
+            return vec![Diagnostic {
+                range: Range::default(),
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: e.clone(),
+                ..Default::default()
+            }]
+        }
+    };
+    let index = document.line_index();
+    validate(doc)
+        .into_iter()
+        .map(|d| Diagnostic {
+            range: document
+                .node_range(doc, &index, &d.path)
+                .unwrap_or_default(),
+            severity: Some(match d.severity {
+                Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+            }),
+            source: Some("djot".to_string()),
+            message: d.message,
+            ..Default::default()
+        })
+        .collect()
+}
+
+pub fn document_symbols(document: &Document) -> Vec<DocumentSymbol> {
+    let Ok(doc) = &document.ast else {
+        return Vec::new();
+    };
+    symbols(document, &document.line_index(), &doc.children)
+}
+
+fn symbols(document: &Document, index: &LineIndex, blocks: &[Block]) -> Vec<DocumentSymbol> {
+    let mut out = Vec::new();
+    for block in blocks {
+        let (heading, children) = match block {
+            Block::Section(s) => match s.children.split_first() {
+                Some((Block::Heading(h), rest)) => (h, symbols(document, index, rest)),
+                _ => {
+                    out.extend(symbols(document, index, &s.children));
+                    continue;
+                }
+            },
+            Block::Heading(h) => (h, Vec::new()),
+            Block::Div(d) => {
+                out.extend(symbols(document, index, &d.children));
+                continue;
+            }
+            _ => continue,
+        };
+        let Some(selection_range) = heading.pos().and_then(|p| document.range(index, p)) else {
+            continue;
+        };
+        let range = node_pos(NodeRef::Block(block))
+            .and_then(|p| document.range(index, &p))
+            .unwrap_or(selection_range);
+        #[allow(deprecated)]
+        out.push(DocumentSymbol {
+            name: inline_text(&heading.children),
+            detail: None,
+            kind: SymbolKind::NAMESPACE,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range,
+            children: Some(children),
+        });
+    }
+    out
+}
+
+pub fn folding_ranges(document: &Document) -> Vec<FoldingRange> {
+    let Ok(doc) = &document.ast else {
+        return Vec::new();
+    };
+    let index = document.line_index();
+    doc.nodes()
+        .filter(|(_, node)| {
+            matches!(
+                node,
+                NodeRef::Block(
+                    Block::Section(_)
+                        | Block::OrderedList(_)
+                        | Block::BulletList(_)
+                        | Block::TaskList(_)
+                        | Block::DefinitionList(_)
+                        | Block::CodeBlock(_)
+                )
+            )
+        })
+        .filter_map(|(_, node)| {
+            let range = document.range(&index, &node_pos(node)?)?;
+            // blocks usually end at the start of the following line
+            let end_line = if range.end.character == 0 {
+                range.end.line.saturating_sub(1)
+            } else {
+                range.end.line
+            };
+            (end_line > range.start.line).then(|| FoldingRange {
+                start_line: range.start.line,
+                end_line,
+                kind: Some(FoldingRangeKind::Region),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Something at the cursor which refers to a reference or footnote.
+enum Target<'a> {
+    Link {
+        path: NodePath,
+        reference: Option<&'a str>,
+        destination: Option<&'a str>,
+    },
+    FootnoteReference(NodePath, &'a str),
+    Footnote(&'a str),
+    Reference(&'a str),
+}
+
+fn target_at<'a>(document: &Document, doc: &'a Doc, position: Position) -> Option<Target<'a>> {
+    let index = document.line_index();
+    let paths = document.paths_at(doc, &index, position);
+    if let Some(innermost) = paths.first() {
+        if innermost.children.is_empty() {
+            match &innermost.root {
+                PathRoot::Footnote(l) => {
+                    return Some(Target::Footnote(doc.footnotes.get(l)?.label.as_str()))
+                }
+                PathRoot::Reference(l) => {
+                    return Some(Target::Reference(doc.references.get(l)?.label.as_str()))
+                }
+                _ => (),
+            }
+        }
+    }
+    paths.into_iter().find_map(|path| match doc.get(&path)? {
+        NodeRef::Inline(Inline::Link(l)) => Some(Target::Link {
+            path,
+            reference: l.reference.as_deref(),
+            destination: l.destination.as_deref(),
+        }),
+        NodeRef::Inline(Inline::Image(l)) => Some(Target::Link {
+            path,
+            reference: l.reference.as_deref(),
+            destination: l.destination.as_deref(),
+        }),
+        NodeRef::Inline(Inline::FootnoteReference(f)) => {
+            Some(Target::FootnoteReference(path, f.text.as_str()))
+        }
+        _ => None,
+    })
+}
+
+/// Resolve a link's destination, following its reference if it has one.
+fn resolve_destination<'a>(
+    doc: &'a Doc,
+    reference: Option<&'a str>,
+    destination: Option<&'a str>,
+) -> Option<&'a str> {
+    match reference {
+        Some(r) => doc
+            .references
+            .get(r)
+            .or_else(|| doc.auto_references.get(r))
+            .map(|r| r.destination.as_str()),
+        None => destination,
+    }
+}
+
+fn location(document: &Document, uri: &Uri, pos: Option<Pos>) -> Option<Location> {
+    let range = document.range(&document.line_index(), &pos?)?;
+    Some(Location {
+        uri: uri.clone(),
+        range,
+    })
+}
+
+pub fn definition(document: &Document, uri: &Uri, position: Position) -> Option<Location> {
+    let doc = document.ast.as_ref().ok()?;
+    match target_at(document, doc, position)? {
+        Target::Link {
+            reference,
+            destination,
+            ..
+        } => {
+            if let Some(pos) = reference
+                .and_then(|r| doc.references.get(r))
+                .and_then(|r| r.pos())
+            {
+                return location(document, uri, Some(*pos));
+            }
+            let id = resolve_destination(doc, reference, destination)?.strip_prefix('#')?;
+            let (_, node) = doc
+                .nodes()
+                .find(|(_, n)| n.meta().attributes().get("id").is_some_and(|i| i == id))?;
+            location(document, uri, node_pos(node))
+        }
+        Target::FootnoteReference(_, label) => {
+            location(document, uri, doc.footnotes.get(label)?.pos().copied())
+        }
+        Target::Footnote(_) | Target::Reference(_) => None,
+    }
+}
+
+pub fn hover(document: &Document, position: Position) -> Option<Hover> {
+    let doc = document.ast.as_ref().ok()?;
+    let index = document.line_index();
+    let (path, value) = match target_at(document, doc, position)? {
+        Target::Link {
+            path,
+            reference,
+            destination,
+        } => (
+            path,
+            format!("`{}`", resolve_destination(doc, reference, destination)?),
+        ),
+        Target::FootnoteReference(path, label) => {
+            let mut text = blocks_text(&doc.footnotes.get(label)?.children);
+            if let Some((idx, _)) = text.char_indices().nth(PREVIEW_LENGTH) {
+                text.truncate(idx);
+                text.push('…');
+            }
+            (path, text)
+        }
+        Target::Footnote(_) | Target::Reference(_) => return None,
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: document.node_range(doc, &index, &path),
+    })
+}
+
+pub fn rename(
+    document: &Document,
+    uri: &Uri,
+    position: Position,
+    new_name: &str,
+) -> Result<Option<WorkspaceEdit>, String> {
+    let Ok(doc) = &document.ast else {
+        return Ok(None);
+    };
+    let (label, is_footnote) = match target_at(document, doc, position) {
+        Some(Target::FootnoteReference(_, l)) | Some(Target::Footnote(l)) => (l, true),
+        Some(Target::Link {
+            reference: Some(l), ..
+        })
+        | Some(Target::Reference(l)) => (l, false),
+        _ => return Ok(None),
+    };
+    if !is_footnote && !doc.references.contains_key(label) {
+        return Err(format!(
+            "'{label}' is not an explicit reference, so cannot be renamed"
+        ));
+    }
+    if new_name.trim().is_empty() || new_name.contains([']', '^', '\n', '\r']) {
+        return Err(format!(
+            "'{new_name}' is not a label: it must have text and no ']', '^' or line breaks"
+        ));
+    }
+    let taken = if is_footnote {
+        doc.footnotes.contains_key(new_name)
+    } else {
+        doc.references.contains_key(new_name) || doc.auto_references.contains_key(new_name)
+    };
+    if taken && new_name != label {
+        return Err(format!("'{new_name}' is already a label"));
+    }
+
+    let index = document.line_index();
+    let mut edits = Vec::new();
+    let mut edit = |range: std::ops::Range<usize>, text: String| {
+        if let Some(range) = document.byte_range(&index, range) {
+            edits.push(TextEdit {
+                range,
+                new_text: text,
+            });
+        }
+    };
+    let (old_marker, new_marker) = if is_footnote {
+        (format!("[^{label}]"), format!("[^{new_name}]"))
+    } else {
+        (format!("[{label}]"), format!("[{new_name}]"))
+    };
+
+    for (_, node) in doc.nodes() {
+        let Some(range) = node.meta().pos().and_then(|p| index.byte_range(p)) else {
+            continue;
+        };
+        let source = &document.text[range.clone()];
+        match node {
+            NodeRef::Inline(Inline::FootnoteReference(f))
+                if is_footnote && f.text == label && source == old_marker =>
+            {
+                edit(range, new_marker.clone());
+            }
+            NodeRef::Footnote(f)
+                if is_footnote && f.label == label && source.starts_with(&old_marker) =>
+            {
+                edit(
+                    range.start..range.start + old_marker.len(),
+                    new_marker.clone(),
+                );
+            }
+            NodeRef::Reference(r)
+                if !is_footnote && r.label == label && source.starts_with(&old_marker) =>
+            {
+                edit(
+                    range.start..range.start + old_marker.len(),
+                    new_marker.clone(),
+                );
+            }
+            NodeRef::Inline(Inline::Link(l))
+                if !is_footnote && l.reference.as_deref() == Some(label) =>
+            {
+                rename_link_reference(source, range, &old_marker, &new_marker, &mut edit);
+            }
+            NodeRef::Inline(Inline::Image(l))
+                if !is_footnote && l.reference.as_deref() == Some(label) =>
+            {
+                rename_link_reference(source, range, &old_marker, &new_marker, &mut edit);
+            }
+            _ => (),
+        }
+    }
+
+    Ok(Some(WorkspaceEdit {
+        changes: Some(HashMap::from([(uri.clone(), edits)])),
+        ..Default::default()
+    }))
+}
+
+/// Edit the `[label]` or collapsed `[]` at the end of a reference link's source.
+fn rename_link_reference(
+    source: &str,
+    range: std::ops::Range<usize>,
+    old_marker: &str,
+    new_marker: &str,
+    edit: &mut impl FnMut(std::ops::Range<usize>, String),
+) {
+    for marker in [old_marker, "[]"] {
+        if source.ends_with(marker) {
+            edit(range.end - marker.len()..range.end, new_marker.to_string());
+            return;
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! A language server for djot documents, built on [djot_ast].
+//!
+//! There is no djot parser in Rust yet, so the server runs an external command
+//! which reads djot from its standard input and writes djot.js JSON to its standard output.
+//! The command is [DEFAULT_PARSER] unless the client sets the `parserCommand` initialization option
+//! to an array of the program and its arguments.
+//! Source positions are required for most features.
+use std::collections::HashMap;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition, HoverRequest, Rename,
+        Request as _,
+    },
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentSymbolParams, DocumentSymbolResponse, FoldingRangeParams,
+    FoldingRangeProviderCapability, GotoDefinitionParams, GotoDefinitionResponse, HoverParams,
+    HoverProviderCapability, InitializeParams, OneOf, PublishDiagnosticsParams, RenameParams,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use serde_json::Value;
+
+mod document;
+use document::Document;
+pub use document::{parse, DEFAULT_PARSER};
+mod features;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    }
+}
+
+/// Run the server until the client shuts it down.
+pub fn run(connection: Connection) -> Result<(), Error> {
+    let (id, params) = connection.initialize_start()?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    let parser = params
+        .initialization_options
+        .as_ref()
+        .and_then(|o| o.get("parserCommand"))
+        .and_then(|c| serde_json::from_value(c.clone()).ok())
+        .unwrap_or_else(|| DEFAULT_PARSER.iter().map(|s| s.to_string()).collect());
+    connection.initialize_finish(
+        id,
+        serde_json::json!({
+            "capabilities": capabilities(),
+            "serverInfo": {"name": "djot-ast-lsp", "version": env!("CARGO_PKG_VERSION")},
+        }),
+    )?;
+
+    let mut server = Server {
+        parser,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                connection.sender.send(server.request(request).into())?;
+            }
+            Message::Notification(notification) => {
+                if let Some(out) = server.notify(notification)? {
+                    connection.sender.send(out.into())?;
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+    Ok(())
+}
+
+struct Server {
+    parser: Vec<String>,
+    documents: HashMap<Uri, Document>,
+}
+
+impl Server {
+    /// Handle a notification, returning a diagnostics notification if a document changed.
+    fn notify(&mut self, notification: Notification) -> Result<Option<Notification>, Error> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let document = Document::new(params.text_document.text, &self.parser);
+                self.documents
+                    .insert(params.text_document.uri.clone(), document);
+                params.text_document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // full synchronisation means the last change holds the whole text
+                let Some(change) = params.content_changes.into_iter().last() else {
+                    return Ok(None);
+                };
+                let document = Document::new(change.text, &self.parser);
+                self.documents
+                    .insert(params.text_document.uri.clone(), document);
+                params.text_document.uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+        let diagnostics = features::diagnostics(&self.documents[&uri]);
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        Ok(Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))
+    }
+
+    fn request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        match self.dispatch(request) {
+            Ok(value) => Response::new_ok(id, value),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        }
+    }
+
+    fn document(&self, uri: &Uri) -> Result<&Document, (ErrorCode, String)> {
+        self.documents.get(uri).ok_or_else(|| {
+            (
+                ErrorCode::InvalidParams,
+                format!("unknown document '{}'", uri.as_str()),
+            )
+        })
+    }
+
+    fn dispatch(&self, request: Request) -> Result<Value, (ErrorCode, String)> {
+        fn params<P: serde::de::DeserializeOwned>(
+            request: Request,
+        ) -> Result<P, (ErrorCode, String)> {
+            serde_json::from_value(request.params)
+                .map_err(|e| (ErrorCode::InvalidParams, e.to_string()))
+        }
+        fn to_value(value: impl serde::Serialize) -> Result<Value, (ErrorCode, String)> {
+            serde_json::to_value(value).map_err(|e| (ErrorCode::InternalError, e.to_string()))
+        }
+
+        match request.method.as_str() {
+            DocumentSymbolRequest::METHOD => {
+                let p: DocumentSymbolParams = params(request)?;
+                let symbols = features::document_symbols(self.document(&p.text_document.uri)?);
+                to_value(DocumentSymbolResponse::Nested(symbols))
+            }
+            FoldingRangeRequest::METHOD => {
+                let p: FoldingRangeParams = params(request)?;
+                to_value(features::folding_ranges(
+                    self.document(&p.text_document.uri)?,
+                ))
+            }
+            GotoDefinition::METHOD => {
+                let p: GotoDefinitionParams = params(request)?;
+                let td = p.text_document_position_params;
+                let location = features::definition(
+                    self.document(&td.text_document.uri)?,
+                    &td.text_document.uri,
+                    td.position,
+                );
+                to_value(location.map(GotoDefinitionResponse::Scalar))
+            }
+            HoverRequest::METHOD => {
+                let p: HoverParams = params(request)?;
+                let td = p.text_document_position_params;
+                to_value(features::hover(
+                    self.document(&td.text_document.uri)?,
+                    td.position,
+                ))
+            }
+            Rename::METHOD => {
+                let p: RenameParams = params(request)?;
+                let td = p.text_document_position;
+                let edit = features::rename(
+                    self.document(&td.text_document.uri)?,
+                    &td.text_document.uri,
+                    td.position,
+                    &p.new_name,
+                )
+                .map_err(|e| (ErrorCode::RequestFailed, e))?;
+                to_value(edit)
+            }
+            method => Err((
+                ErrorCode::MethodNotFound,
+                format!("unsupported method '{method}'"),
+            )),
+        }
+    }
+}
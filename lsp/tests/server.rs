@@ -0,0 +1,257 @@
+use std::{str::FromStr, thread};
+
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{DidOpenTextDocument, Exit, Initialized, Notification as _, PublishDiagnostics},
+    request::{
+        DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition, HoverRequest, Initialize,
+        Rename, Request as _, Shutdown,
+    },
+    DocumentSymbolResponse, FoldingRange, GotoDefinitionResponse, Hover, HoverContents, Range,
+    TextEdit, Uri, WorkspaceEdit,
+};
+use serde_json::{json, Value};
+
+const README: &str = include_str!("../../tests/fixtures/readme.dj");
+const LABELS: &str = include_str!("fixtures/labels.dj");
+
+struct Client {
+    connection: Connection,
+    next_id: i32,
+}
+
+impl Client {
+    fn request(&mut self, method: &str, params: Value) -> Response {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        self.connection
+            .sender
+            .send(Request::new(id.clone(), method.to_string(), params).into())
+            .unwrap();
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(r) if r.id == id => return r,
+                _ => continue,
+            }
+        }
+    }
+
+    fn result<T: serde::de::DeserializeOwned>(&mut self, method: &str, params: Value) -> T {
+        let response = self.request(method, params);
+        assert!(response.error.is_none(), "{:?}", response.error);
+        serde_json::from_value(response.result.unwrap()).unwrap()
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.connection
+            .sender
+            .send(Notification::new(method.to_string(), params).into())
+            .unwrap();
+    }
+
+    fn diagnostics(&self) -> Value {
+        loop {
+            if let Message::Notification(n) = self.connection.receiver.recv().unwrap() {
+                if n.method == PublishDiagnostics::METHOD {
+                    return n.params;
+                }
+            }
+        }
+    }
+}
+
+fn start(parser: Value) -> (Client, thread::JoinHandle<()>) {
+    let (server, connection) = Connection::memory();
+    let handle = thread::spawn(move || djot_ast_lsp::run(server).unwrap());
+    let mut client = Client {
+        connection,
+        next_id: 0,
+    };
+    client.request(
+        Initialize::METHOD,
+        json!({"capabilities": {}, "initializationOptions": {"parserCommand": parser}}),
+    );
+    client.notify(Initialized::METHOD, json!({}));
+    (client, handle)
+}
+
+fn stop(mut client: Client, handle: thread::JoinHandle<()>) {
+    client.request(Shutdown::METHOD, Value::Null);
+    client.notify(Exit::METHOD, Value::Null);
+    handle.join().unwrap();
+}
+
+fn open(client: &Client, uri: &str, text: &str) -> Value {
+    client.notify(
+        DidOpenTextDocument::METHOD,
+        json!({"textDocument": {"uri": uri, "languageId": "djot", "version": 1, "text": text}}),
+    );
+    client.diagnostics()
+}
+
+#[test]
+fn readme() {
+    let fixture = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../tests/fixtures/readme.dj.json"
+    );
+    let (mut client, handle) = start(json!(["cat", fixture]));
+    let uri = "file:///readme.dj";
+    let diagnostics = open(&client, uri, README);
+    assert_eq!(diagnostics["diagnostics"], json!([]));
+    let document = json!({"uri": uri});
+
+    let symbols: DocumentSymbolResponse = client.result(
+        DocumentSymbolRequest::METHOD,
+        json!({"textDocument": document}),
+    );
+    let DocumentSymbolResponse::Nested(symbols) = symbols else {
+        panic!("expected nested symbols");
+    };
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "Djot");
+    let names: Vec<_> = symbols[0]
+        .children
+        .iter()
+        .flatten()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "Rationale",
+            "Syntax",
+            "Implementations",
+            "File extension",
+            "License"
+        ]
+    );
+
+    let folds: Vec<FoldingRange> = client.result(
+        FoldingRangeRequest::METHOD,
+        json!({"textDocument": document}),
+    );
+    assert!(folds.iter().any(|f| f.start_line == 27));
+
+    // the `[Rationale](#rationale)` link
+    let line = README
+        .lines()
+        .position(|l| l.contains("[Rationale]"))
+        .unwrap();
+    let character = README
+        .lines()
+        .nth(line)
+        .unwrap()
+        .find("[Rationale]")
+        .unwrap()
+        + 2;
+    let position =
+        json!({"textDocument": document, "position": {"line": line, "character": character}});
+
+    let definition: GotoDefinitionResponse =
+        client.result(GotoDefinition::METHOD, position.clone());
+    let GotoDefinitionResponse::Scalar(location) = definition else {
+        panic!("expected a single location");
+    };
+    assert_eq!(location.uri, Uri::from_str(uri).unwrap());
+    assert_eq!(location.range.start.line, 27);
+
+    let hover: Hover = client.result(HoverRequest::METHOD, position);
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("expected markup");
+    };
+    assert_eq!(markup.value, "`#rationale`");
+
+    let unknown = client.request(
+        DocumentSymbolRequest::METHOD,
+        json!({"textDocument": {"uri": "file:///unknown.dj"}}),
+    );
+    assert!(unknown.error.is_some());
+
+    stop(client, handle);
+}
+
+fn edit(line: u32, start: u32, end: u32, new_text: &str) -> TextEdit {
+    let range: Range = serde_json::from_value(json!({
+        "start": {"line": line, "character": start},
+        "end": {"line": line, "character": end},
+    }))
+    .unwrap();
+    TextEdit {
+        range,
+        new_text: new_text.to_string(),
+    }
+}
+
+#[test]
+fn labels() {
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/labels.dj.json");
+    let (mut client, handle) = start(json!(["cat", fixture]));
+    let uri = "file:///labels.dj";
+    let diagnostics = open(&client, uri, LABELS);
+    assert_eq!(diagnostics["diagnostics"], json!([]));
+    let at = |character: u32| json!({"textDocument": {"uri": uri}, "position": {"line": 0, "character": character}});
+    let rename = |position: Value, new_name: &str| {
+        let mut params = position;
+        params["newName"] = json!(new_name);
+        params
+    };
+
+    let definition: GotoDefinitionResponse = client.result(GotoDefinition::METHOD, at(5));
+    let GotoDefinitionResponse::Scalar(location) = definition else {
+        panic!("expected a single location");
+    };
+    assert_eq!(location.range.start.line, 2);
+    let definition: GotoDefinitionResponse = client.result(GotoDefinition::METHOD, at(16));
+    let GotoDefinitionResponse::Scalar(location) = definition else {
+        panic!("expected a single location");
+    };
+    assert_eq!(location.range.start.line, 4);
+
+    let hover: Hover = client.result(HoverRequest::METHOD, at(16));
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("expected markup");
+    };
+    assert_eq!(markup.value, "Note.");
+
+    let uri = Uri::from_str(uri).unwrap();
+    let workspace: WorkspaceEdit = client.result(Rename::METHOD, rename(at(16), "note"));
+    let mut edits = workspace.changes.unwrap().remove(&uri).unwrap();
+    edits.sort_by_key(|e| e.range.start);
+    assert_eq!(
+        edits,
+        [edit(0, 15, 19, "[^note]"), edit(4, 0, 4, "[^note]")]
+    );
+
+    let workspace: WorkspaceEdit = client.result(Rename::METHOD, rename(at(5), "y"));
+    let mut edits = workspace.changes.unwrap().remove(&uri).unwrap();
+    edits.sort_by_key(|e| e.range.start);
+    assert_eq!(edits, [edit(0, 7, 10, "[y]"), edit(2, 0, 3, "[y]")]);
+
+    for (position, new_name) in [
+        (at(5), ""),
+        (at(5), " "),
+        (at(5), "a]b"),
+        (at(16), "^m"),
+        (at(5), "a\nb"),
+        (at(5), "z"),
+    ] {
+        let response = client.request(Rename::METHOD, rename(position, new_name));
+        assert!(response.error.is_some(), "renamed to {new_name:?}");
+    }
+
+    stop(client, handle);
+}
+
+#[test]
+fn parser_failure() {
+    let (client, handle) = start(json!(["false"]));
+    let diagnostics = open(&client, "file:///broken.dj", "text");
+    let diagnostics = diagnostics["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("parser exited"));
+    stop(client, handle);
+}
@@ -0,0 +1,238 @@
+//! Archiving documents with [rkyv], so they can be read straight from bytes without deserializing.
+//!
+//! With the `rkyv` feature, [Doc] and every node have an archived counterpart,
+//! e.g. [ArchivedDoc] and [ArchivedBlock](crate::block::ArchivedBlock),
+//! whose fields can be read in place from a buffer or a memory-mapped file.
+//! Archived nodes have a `meta()` method to read their attributes and position.
+//!
+//! ```
+//! # use djot_ast::{archive, block::ArchivedBlock, Doc};
+//! let doc: Doc = serde_json::from_str(r#"{
+//!     "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+//!     "children": [{"tag": "para", "attributes": {"id": "p"}, "children": [{"tag": "str", "text": "hello"}]}]
+//! }"#).unwrap();
+//! let bytes = archive::to_bytes(&doc).unwrap();
+//! let archived = archive::access(&bytes).unwrap();
+//! assert!(matches!(&archived.children[0], ArchivedBlock::Para(_)));
+//! assert_eq!(archived.children[0].meta().get_attribute("id"), Some("p"));
+//! assert_eq!(archive::from_bytes(&bytes).unwrap(), doc);
+//! ```
+pub use rkyv;
+use rkyv::{rancor, util::AlignedVec};
+
+use crate::{
+    attributes::ArchivedMeta,
+    block::{self, ArchivedBlock},
+    inline::{self, ArchivedInline},
+    unknown, ArchivedAstNode, ArchivedDoc, ArchivedFootnote, ArchivedReference, Doc, Error, Result,
+};
+
+/// Archive a document.
+pub fn to_bytes(doc: &Doc) -> Result<AlignedVec> {
+    rkyv::to_bytes::<rancor::Error>(doc).map_err(|e| Error::Wrapper(Box::new(e)))
+}
+
+/// Check that the bytes hold a valid archived document, and get it.
+///
+/// The bytes must be aligned as they were written, as by [AlignedVec] or a memory map.
+pub fn access(bytes: &[u8]) -> Result<&ArchivedDoc> {
+    rkyv::access::<ArchivedDoc, rancor::Error>(bytes).map_err(|e| Error::Wrapper(Box::new(e)))
+}
+
+/// Deserialize an archived document into an owned [Doc].
+pub fn from_bytes(bytes: &[u8]) -> Result<Doc> {
+    rkyv::from_bytes::<Doc, rancor::Error>(bytes).map_err(|e| Error::Wrapper(Box::new(e)))
+}
+
+macro_rules! archived_meta {
+    ($($name:ty),+ $(,)?) => {
+        $(impl $name {
+            /// Get the node's attributes, position and id.
+            pub fn meta(&self) -> &ArchivedMeta {
+                &self.meta
+            }
+        })+
+    };
+}
+
+macro_rules! archived_enum_meta {
+    ($name:ty { $($variant:ident),+ $(,)? }) => {
+        impl $name {
+            /// Get the node's attributes, position and id.
+            pub fn meta(&self) -> &ArchivedMeta {
+                match self {
+                    $(Self::$variant(node) => node.meta(),)+
+                }
+            }
+        }
+    };
+}
+
+archived_meta!(
+    ArchivedDoc,
+    ArchivedReference,
+    ArchivedFootnote,
+    block::ArchivedPara,
+    block::ArchivedHeading,
+    block::ArchivedThematicBreak,
+    block::ArchivedSection,
+    block::ArchivedDiv,
+    block::ArchivedCodeBlock,
+    block::ArchivedRawBlock,
+    block::ArchivedBlockQuote,
+    block::ArchivedOrderedList,
+    block::ArchivedBulletList,
+    block::ArchivedListItem,
+    block::ArchivedTaskList,
+    block::ArchivedTaskListItem,
+    block::ArchivedDefinitionList,
+    block::ArchivedDefinitionListItem,
+    block::ArchivedTerm,
+    block::ArchivedDefinition,
+    block::ArchivedTable,
+    block::ArchivedCaption,
+    block::ArchivedRow,
+    block::ArchivedCell,
+    inline::ArchivedStr,
+    inline::ArchivedSoftBreak,
+    inline::ArchivedHardBreak,
+    inline::ArchivedNonBreakingSpace,
+    inline::ArchivedSymb,
+    inline::ArchivedVerbatim,
+    inline::ArchivedRawInline,
+    inline::ArchivedInlineMath,
+    inline::ArchivedDisplayMath,
+    inline::ArchivedUrl,
+    inline::ArchivedEmail,
+    inline::ArchivedFootnoteReference,
+    inline::ArchivedSmartPunctuation,
+    inline::ArchivedEmph,
+    inline::ArchivedStrong,
+    inline::ArchivedLink,
+    inline::ArchivedImage,
+    inline::ArchivedSpan,
+    inline::ArchivedMark,
+    inline::ArchivedSuperscript,
+    inline::ArchivedSubscript,
+    inline::ArchivedInsert,
+    inline::ArchivedDelete,
+    inline::ArchivedDoubleQuoted,
+    inline::ArchivedSingleQuoted,
+    unknown::ArchivedUnknown,
+);
+
+archived_enum_meta!(ArchivedAstNode {
+    Doc,
+    Block,
+    Inline,
+    ListItem,
+    TaskListItem,
+    DefinitionListItem,
+    Term,
+    Definition,
+    Row,
+    Cell,
+    Caption,
+    Footnote,
+    Reference,
+});
+
+archived_enum_meta!(ArchivedBlock {
+    Para,
+    Heading,
+    ThematicBreak,
+    Section,
+    Div,
+    CodeBlock,
+    RawBlock,
+    BlockQuote,
+    OrderedList,
+    BulletList,
+    TaskList,
+    DefinitionList,
+    Table,
+    Unknown,
+});
+
+archived_enum_meta!(ArchivedInline {
+    Str,
+    SoftBreak,
+    HardBreak,
+    NonBreakingSpace,
+    Symb,
+    Verbatim,
+    RawInline,
+    InlineMath,
+    DisplayMath,
+    Url,
+    Email,
+    FootnoteReference,
+    SmartPunctuation,
+    Emph,
+    Strong,
+    Link,
+    Image,
+    Span,
+    Mark,
+    Superscript,
+    Subscript,
+    Insert,
+    Delete,
+    DoubleQuoted,
+    SingleQuoted,
+    Unknown,
+});
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use crate::attributes::HasAttributes;
+
+    #[test]
+    fn test_table_and_definitions() {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+            "children": [
+                {"tag": "definition_list", "children": [{"tag": "definition_list_item", "children": [
+                    {"tag": "term", "children": [{"tag": "str", "text": "t"}]},
+                    {"tag": "definition", "children": [{"tag": "thematic_break"}]}
+                ]}]},
+                {"tag": "table", "attributes": {"id": "tbl"}, "children": [
+                    {"tag": "caption", "children": [{"tag": "str", "text": "c"}]},
+                    {"tag": "row", "head": true, "children": [{"tag": "cell", "head": true, "align": "left", "children": []}]}
+                ]}
+            ]
+        }"#;
+        let doc: Doc = serde_json::from_str(s).unwrap();
+        let bytes = to_bytes(&doc).unwrap();
+        let archived = access(&bytes).unwrap();
+
+        let ArchivedBlock::DefinitionList(list) = &archived.children[0] else {
+            panic!("expected a definition list");
+        };
+        let item = &list.children[0];
+        assert!(matches!(&item.term.children[0], ArchivedInline::Str(s) if s.text == "t"));
+        assert!(matches!(
+            item.definition.children[0],
+            ArchivedBlock::ThematicBreak(_)
+        ));
+
+        let ArchivedBlock::Table(table) = &archived.children[1] else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.meta().get_attribute("id"), Some("tbl"));
+        assert!(matches!(&table.caption.children[0], ArchivedInline::Str(s) if s.text == "c"));
+        assert!(table.rows[0].head);
+        assert_eq!(
+            table.rows[0].children[0].align,
+            block::ArchivedAlignment::Left
+        );
+
+        assert_eq!(from_bytes(&bytes).unwrap(), doc);
+        assert!(access(&bytes[1..]).is_err());
+        assert_eq!(
+            archived.children[1].meta().pos(),
+            doc.children[1].pos().copied()
+        );
+    }
+}
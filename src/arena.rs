@@ -0,0 +1,633 @@
+//! An arena representation of a document, with links to parents and siblings.
+//!
+//! The owned [Doc] tree only links nodes to their children.
+//! An [Arena] stores every node in one `Vec`, each with links to its parent, first and last children,
+//! and previous and next siblings, so that moving around the tree in any direction is O(1),
+//! and nodes can be detached and inserted elsewhere without rebuilding their ancestors.
+//!
+//! ```
+//! # use djot_ast::{arena::Arena, AstNode, Doc, block::Block};
+//! let doc: Doc = serde_json::from_str(r#"{
+//!     "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+//!     "children": [
+//!         {"tag": "para", "children": [{"tag": "str", "text": "a"}]},
+//!         {"tag": "thematic_break"}
+//!     ]
+//! }"#).unwrap();
+//! let mut arena = Arena::from(doc);
+//! let para = arena.first_child(arena.root()).unwrap();
+//! let text = arena.first_child(para).unwrap();
+//! assert_eq!(arena.ancestors(text).collect::<Vec<_>>(), [para, arena.root()]);
+//!
+//! let hr = arena.next_sibling(para).unwrap();
+//! arena.insert_before(para, hr).unwrap();
+//! let doc = arena.into_doc().unwrap();
+//! assert!(matches!(doc.children[0], Block::ThematicBreak(_)));
+//! ```
+use indexmap::IndexMap;
+
+use crate::{
+    block::{Block, Caption, Definition, Term},
+    inline::Inline,
+    AstNode, Doc, Error, Meta, Result,
+};
+
+/// The index of a node in an [Arena].
+///
+/// Indices stay valid for the life of the arena: detached nodes are kept, not freed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeIndex(usize);
+
+impl NodeIndex {
+    /// The position of the node in the arena's storage.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// The node, with its children moved out into the arena.
+    node: AstNode,
+    parent: Option<NodeIndex>,
+    first_child: Option<NodeIndex>,
+    last_child: Option<NodeIndex>,
+    prev_sibling: Option<NodeIndex>,
+    next_sibling: Option<NodeIndex>,
+}
+
+/// A document whose nodes are stored in a flat list with parent and sibling links.
+///
+/// The document's footnotes are stored as further roots, with no parent,
+/// and its references stay in the [Doc] node at the [root](Arena::root).
+///
+/// Each node is stored without its children,
+/// e.g. a [Para](crate::block::Para)'s `children` are empty, and its inlines are separate nodes.
+/// Nodes are only linked under parents which can hold them,
+/// so that the arena can always be converted back into a [Doc].
+#[derive(Debug, Clone)]
+pub struct Arena {
+    entries: Vec<Entry>,
+    footnotes: IndexMap<String, NodeIndex>,
+}
+
+impl From<Doc> for Arena {
+    fn from(value: Doc) -> Self {
+        Self::from_doc(value)
+    }
+}
+
+impl Arena {
+    /// Move all of a document's nodes into an arena.
+    pub fn from_doc(mut doc: Doc) -> Self {
+        let footnotes = std::mem::take(&mut doc.footnotes);
+        let mut arena = Self {
+            entries: Vec::new(),
+            footnotes: IndexMap::new(),
+        };
+        arena.new_node(doc);
+        for (label, footnote) in footnotes {
+            let idx = arena.new_node(footnote);
+            arena.footnotes.insert(label, idx);
+        }
+        arena
+    }
+
+    /// Rebuild the document from the nodes reachable from the root and the footnotes.
+    ///
+    /// Detached nodes are dropped.
+    /// This fails if a node was changed through [Arena::get_mut] so that its children no longer fit,
+    /// e.g. by replacing a [Para](crate::block::Para) with a [Section](crate::block::Section).
+    pub fn into_doc(mut self) -> Result<Doc> {
+        let footnotes = std::mem::take(&mut self.footnotes);
+        let AstNode::Doc(mut doc) = self.take_tree(self.root())? else {
+            return Err(Error::general("the arena root is not a document"));
+        };
+        for (label, idx) in footnotes {
+            doc.footnotes
+                .insert(label, self.take_tree(idx)?.try_into()?);
+        }
+        Ok(doc)
+    }
+
+    /// The document node.
+    pub fn root(&self) -> NodeIndex {
+        NodeIndex(0)
+    }
+
+    /// The footnote with the given label.
+    pub fn footnote(&self, label: &str) -> Option<NodeIndex> {
+        self.footnotes.get(label).copied()
+    }
+
+    /// Iterate over the labels and nodes of the document's footnotes.
+    pub fn footnotes(&self) -> impl Iterator<Item = (&str, NodeIndex)> + '_ {
+        self.footnotes.iter().map(|(l, i)| (l.as_str(), *i))
+    }
+
+    /// The number of nodes in the arena, including detached ones.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the arena has no nodes, which is never the case for one made from a [Doc].
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get a node, without its children.
+    ///
+    /// # Panics
+    /// If the index is from a different arena.
+    pub fn get(&self, idx: NodeIndex) -> &AstNode {
+        &self.entries[idx.0].node
+    }
+
+    /// Get a node mutably, without its children.
+    ///
+    /// Changing the kind of the node so that it can no longer hold its children
+    /// makes [Arena::into_doc] fail.
+    pub fn get_mut(&mut self, idx: NodeIndex) -> &mut AstNode {
+        &mut self.entries[idx.0].node
+    }
+
+    /// The node's parent, or `None` for the root, footnotes and detached nodes.
+    pub fn parent(&self, idx: NodeIndex) -> Option<NodeIndex> {
+        self.entries[idx.0].parent
+    }
+
+    /// The node's first child.
+    pub fn first_child(&self, idx: NodeIndex) -> Option<NodeIndex> {
+        self.entries[idx.0].first_child
+    }
+
+    /// The node's last child.
+    pub fn last_child(&self, idx: NodeIndex) -> Option<NodeIndex> {
+        self.entries[idx.0].last_child
+    }
+
+    /// The node after this one in its parent.
+    pub fn next_sibling(&self, idx: NodeIndex) -> Option<NodeIndex> {
+        self.entries[idx.0].next_sibling
+    }
+
+    /// The node before this one in its parent.
+    pub fn previous_sibling(&self, idx: NodeIndex) -> Option<NodeIndex> {
+        self.entries[idx.0].prev_sibling
+    }
+
+    /// Iterate over the node's direct children.
+    pub fn children(&self, idx: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        std::iter::successors(self.first_child(idx), |i| self.next_sibling(*i))
+    }
+
+    /// Iterate over the node's parent, its parent's parent, and so on up to the root.
+    pub fn ancestors(&self, idx: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        std::iter::successors(self.parent(idx), |i| self.parent(*i))
+    }
+
+    /// Iterate over this node and all of its descendants, depth-first in document order.
+    pub fn descendants(&self, idx: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        std::iter::successors(Some(idx), move |i| {
+            if let Some(child) = self.first_child(*i) {
+                return Some(child);
+            }
+            let mut node = *i;
+            while node != idx {
+                if let Some(next) = self.next_sibling(node) {
+                    return Some(next);
+                }
+                node = self.parent(node)?;
+            }
+            None
+        })
+    }
+
+    /// Add a node to the arena, along with all of its descendants, without attaching it anywhere.
+    pub fn new_node(&mut self, node: impl Into<AstNode>) -> NodeIndex {
+        let root = self.push(node.into());
+        let mut stack = vec![root];
+        while let Some(parent) = stack.pop() {
+            for child in take_children(&mut self.entries[parent.0].node) {
+                let idx = self.push(child);
+                self.link(idx, parent, None);
+                stack.push(idx);
+            }
+        }
+        root
+    }
+
+    /// Unlink a node, and so its descendants, from its parent and siblings.
+    ///
+    /// Table captions and the terms and definitions of definition list items cannot be detached.
+    /// Detaching a node without a parent does nothing.
+    pub fn detach(&mut self, idx: NodeIndex) -> Result<()> {
+        let Some(parent) = self.parent(idx) else {
+            return Ok(());
+        };
+        let fixed = match self.get(parent) {
+            AstNode::Block(Block::Table(_)) => matches!(self.get(idx), AstNode::Caption(_)),
+            AstNode::DefinitionListItem(_) => true,
+            _ => false,
+        };
+        if fixed {
+            return Err(Error::general("cannot detach this node"));
+        }
+        let Entry {
+            prev_sibling,
+            next_sibling,
+            ..
+        } = self.entries[idx.0];
+        match prev_sibling {
+            Some(p) => self.entries[p.0].next_sibling = next_sibling,
+            None => self.entries[parent.0].first_child = next_sibling,
+        }
+        match next_sibling {
+            Some(n) => self.entries[n.0].prev_sibling = prev_sibling,
+            None => self.entries[parent.0].last_child = prev_sibling,
+        }
+        let entry = &mut self.entries[idx.0];
+        entry.parent = None;
+        entry.prev_sibling = None;
+        entry.next_sibling = None;
+        Ok(())
+    }
+
+    /// Move a node to be the last child of `parent`, detaching it from where it was.
+    ///
+    /// The node must be of the right type for its parent,
+    /// e.g. a [Block] in a [Section](crate::block::Section) or a [Row](crate::block::Row) in a [Table](crate::block::Table),
+    /// and must not be an ancestor of `parent`.
+    pub fn append(&mut self, parent: NodeIndex, child: NodeIndex) -> Result<()> {
+        self.check_insert(parent, child, None)?;
+        self.detach(child)?;
+        self.link(child, parent, None);
+        Ok(())
+    }
+
+    /// Move a node to be the first child of `parent`, detaching it from where it was.
+    pub fn prepend(&mut self, parent: NodeIndex, child: NodeIndex) -> Result<()> {
+        match self.first_child(parent) {
+            Some(first) => self.insert_before(first, child),
+            None => self.append(parent, child),
+        }
+    }
+
+    /// Move a node to be the previous sibling of `sibling`, detaching it from where it was.
+    pub fn insert_before(&mut self, sibling: NodeIndex, node: NodeIndex) -> Result<()> {
+        let parent = self.sibling_parent(sibling)?;
+        self.check_insert(parent, node, Some(sibling))?;
+        self.detach(node)?;
+        self.link(node, parent, Some(sibling));
+        Ok(())
+    }
+
+    /// Move a node to be the next sibling of `sibling`, detaching it from where it was.
+    pub fn insert_after(&mut self, sibling: NodeIndex, node: NodeIndex) -> Result<()> {
+        if sibling == node {
+            return Err(Error::general("cannot insert a node next to itself"));
+        }
+        match self.next_sibling(sibling) {
+            Some(next) if next == node => Ok(()),
+            Some(next) => self.insert_before(next, node),
+            None => self.append(self.sibling_parent(sibling)?, node),
+        }
+    }
+
+    fn sibling_parent(&self, sibling: NodeIndex) -> Result<NodeIndex> {
+        self.parent(sibling)
+            .ok_or_else(|| Error::general("cannot insert a sibling of a node without a parent"))
+    }
+
+    fn check_insert(
+        &self,
+        parent: NodeIndex,
+        child: NodeIndex,
+        before: Option<NodeIndex>,
+    ) -> Result<()> {
+        if parent == child || self.ancestors(parent).any(|i| i == child) {
+            return Err(Error::general("cannot insert a node inside itself"));
+        }
+        if before == Some(child) {
+            return Err(Error::general("cannot insert a node next to itself"));
+        }
+        let child = self.get(child);
+        let fits = match self.get(parent) {
+            AstNode::Doc(_)
+            | AstNode::ListItem(_)
+            | AstNode::TaskListItem(_)
+            | AstNode::Definition(_)
+            | AstNode::Footnote(_) => matches!(child, AstNode::Block(_)),
+            AstNode::Block(b) => match b {
+                Block::Para(_) | Block::Heading(_) => matches!(child, AstNode::Inline(_)),
+                Block::Section(_) | Block::Div(_) | Block::BlockQuote(_) => {
+                    matches!(child, AstNode::Block(_))
+                }
+                Block::OrderedList(_) | Block::BulletList(_) => {
+                    matches!(child, AstNode::ListItem(_))
+                }
+                Block::TaskList(_) => matches!(child, AstNode::TaskListItem(_)),
+                Block::DefinitionList(_) => matches!(child, AstNode::DefinitionListItem(_)),
+                // rows go after the caption
+                Block::Table(_) => {
+                    matches!(child, AstNode::Row(_)) && before != self.first_child(parent)
+                }
+                Block::ThematicBreak(_)
+                | Block::CodeBlock(_)
+                | Block::RawBlock(_)
+                | Block::Unknown(_) => false,
+            },
+            AstNode::Inline(i) => i.children().is_some() && matches!(child, AstNode::Inline(_)),
+            AstNode::Term(_) | AstNode::Cell(_) | AstNode::Caption(_) => {
+                matches!(child, AstNode::Inline(_))
+            }
+            AstNode::Row(_) => matches!(child, AstNode::Cell(_)),
+            AstNode::DefinitionListItem(_) | AstNode::Reference(_) => false,
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(Error::general("cannot insert a node here"))
+        }
+    }
+
+    fn push(&mut self, node: AstNode) -> NodeIndex {
+        self.entries.push(Entry {
+            node,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        });
+        NodeIndex(self.entries.len() - 1)
+    }
+
+    /// Link a detached node under `parent`, before `before` or otherwise last.
+    fn link(&mut self, idx: NodeIndex, parent: NodeIndex, before: Option<NodeIndex>) {
+        let prev = match before {
+            Some(b) => self.entries[b.0].prev_sibling,
+            None => self.entries[parent.0].last_child,
+        };
+        match prev {
+            Some(p) => self.entries[p.0].next_sibling = Some(idx),
+            None => self.entries[parent.0].first_child = Some(idx),
+        }
+        match before {
+            Some(b) => self.entries[b.0].prev_sibling = Some(idx),
+            None => self.entries[parent.0].last_child = Some(idx),
+        }
+        let entry = &mut self.entries[idx.0];
+        entry.parent = Some(parent);
+        entry.prev_sibling = prev;
+        entry.next_sibling = before;
+    }
+
+    /// Move a subtree out of the arena, putting the children back into their parents.
+    fn take_tree(&mut self, idx: NodeIndex) -> Result<AstNode> {
+        let order: Vec<_> = self.descendants(idx).collect();
+        // children come after their parents, so are rebuilt first
+        for &node in order.iter().rev() {
+            let children = self
+                .children(node)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|c| self.take(c))
+                .collect();
+            set_children(&mut self.entries[node.0].node, children)?;
+        }
+        Ok(self.take(idx))
+    }
+
+    fn take(&mut self, idx: NodeIndex) -> AstNode {
+        std::mem::replace(
+            &mut self.entries[idx.0].node,
+            AstNode::Caption(Caption::default()),
+        )
+    }
+}
+
+/// Move a node's children out, leaving it empty.
+fn take_children(node: &mut AstNode) -> Vec<AstNode> {
+    fn all<T: Into<AstNode>>(children: &mut Vec<T>) -> Vec<AstNode> {
+        std::mem::take(children)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    match node {
+        AstNode::Doc(i) => all(&mut i.children),
+        AstNode::Block(b) => match b {
+            Block::Para(i) => all(&mut i.children),
+            Block::Heading(i) => all(&mut i.children),
+            Block::Section(i) => all(&mut i.children),
+            Block::Div(i) => all(&mut i.children),
+            Block::BlockQuote(i) => all(&mut i.children),
+            Block::OrderedList(i) => all(&mut i.children),
+            Block::BulletList(i) => all(&mut i.children),
+            Block::TaskList(i) => all(&mut i.children),
+            Block::DefinitionList(i) => all(&mut i.children),
+            Block::Table(i) => std::iter::once(std::mem::take(&mut i.caption).into())
+                .chain(all(&mut i.rows))
+                .collect(),
+            Block::ThematicBreak(_)
+            | Block::CodeBlock(_)
+            | Block::RawBlock(_)
+            | Block::Unknown(_) => Vec::new(),
+        },
+        AstNode::Inline(i) => i.children_mut().map(all).unwrap_or_default(),
+        AstNode::ListItem(i) => all(&mut i.children),
+        AstNode::TaskListItem(i) => all(&mut i.children),
+        AstNode::DefinitionListItem(i) => {
+            let term = Term {
+                children: Vec::new(),
+                meta: Meta::default(),
+            };
+            let definition = Definition {
+                children: Vec::new(),
+                meta: Meta::default(),
+            };
+            vec![
+                std::mem::replace(&mut i.term, term).into(),
+                std::mem::replace(&mut i.definition, definition).into(),
+            ]
+        }
+        AstNode::Term(i) => all(&mut i.children),
+        AstNode::Definition(i) => all(&mut i.children),
+        AstNode::Row(i) => all(&mut i.children),
+        AstNode::Cell(i) => all(&mut i.children),
+        AstNode::Caption(i) => all(&mut i.children),
+        AstNode::Footnote(i) => all(&mut i.children),
+        AstNode::Reference(_) => Vec::new(),
+    }
+}
+
+/// Put children back into a node emptied by [take_children].
+fn set_children(node: &mut AstNode, children: Vec<AstNode>) -> Result<()> {
+    fn all<T: TryFrom<AstNode, Error = Error>>(children: Vec<AstNode>) -> Result<Vec<T>> {
+        children.into_iter().map(T::try_from).collect()
+    }
+    fn leaf(children: Vec<AstNode>) -> Result<()> {
+        match children.is_empty() {
+            true => Ok(()),
+            false => Err(Error::general("a leaf node cannot have children")),
+        }
+    }
+
+    match node {
+        AstNode::Doc(i) => i.children = all(children)?,
+        AstNode::Block(b) => match b {
+            Block::Para(i) => i.children = all(children)?,
+            Block::Heading(i) => i.children = all(children)?,
+            Block::Section(i) => i.children = all(children)?,
+            Block::Div(i) => i.children = all(children)?,
+            Block::BlockQuote(i) => i.children = all(children)?,
+            Block::OrderedList(i) => i.children = all(children)?,
+            Block::BulletList(i) => i.children = all(children)?,
+            Block::TaskList(i) => i.children = all(children)?,
+            Block::DefinitionList(i) => i.children = all(children)?,
+            Block::Table(i) => {
+                let mut children = children.into_iter();
+                i.caption = children
+                    .next()
+                    .ok_or_else(|| Error::general("a table needs a caption"))?
+                    .try_into()?;
+                i.rows = all(children.collect())?;
+            }
+            Block::ThematicBreak(_)
+            | Block::CodeBlock(_)
+            | Block::RawBlock(_)
+            | Block::Unknown(_) => leaf(children)?,
+        },
+        AstNode::Inline(i) => match i.children_mut() {
+            Some(c) => *c = all::<Inline>(children)?,
+            None => leaf(children)?,
+        },
+        AstNode::ListItem(i) => i.children = all(children)?,
+        AstNode::TaskListItem(i) => i.children = all(children)?,
+        AstNode::DefinitionListItem(i) => {
+            let [term, definition] = <[AstNode; 2]>::try_from(children).map_err(|_| {
+                Error::general("a definition list item needs a term and a definition")
+            })?;
+            i.term = term.try_into()?;
+            i.definition = definition.try_into()?;
+        }
+        AstNode::Term(i) => i.children = all(children)?,
+        AstNode::Definition(i) => i.children = all(children)?,
+        AstNode::Row(i) => i.children = all(children)?,
+        AstNode::Cell(i) => i.children = all(children)?,
+        AstNode::Caption(i) => i.children = all(children)?,
+        AstNode::Footnote(i) => i.children = all(children)?,
+        AstNode::Reference(_) => leaf(children)?,
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn doc() -> Doc {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {},
+            "footnotes": {"n": {"tag": "footnote", "label": "n", "children": [{"tag": "thematic_break"}]}},
+            "children": [
+                {"tag": "para", "children": [{"tag": "str", "text": "a"}, {"tag": "emph", "children": [{"tag": "str", "text": "b"}]}]},
+                {"tag": "thematic_break"},
+                {"tag": "table", "children": [
+                    {"tag": "caption", "children": []},
+                    {"tag": "row", "head": false, "children": []}
+                ]}
+            ]
+        }"#;
+        serde_json::from_str(s).unwrap()
+    }
+
+    fn text(arena: &Arena, idx: NodeIndex) -> &str {
+        match arena.get(idx) {
+            AstNode::Inline(Inline::Str(s)) => &s.text,
+            node => panic!("not a str: {node:?}"),
+        }
+    }
+
+    #[test]
+    fn test_navigation() {
+        let d = doc();
+        let arena = Arena::from(d.clone());
+        assert_eq!(arena.len(), d.nodes().count());
+
+        let root = arena.root();
+        let [para, hr, table] = arena.children(root).collect::<Vec<_>>()[..] else {
+            panic!("expected three blocks");
+        };
+        assert_eq!(arena.previous_sibling(hr), Some(para));
+        assert_eq!(arena.next_sibling(hr), Some(table));
+        assert_eq!(arena.next_sibling(table), None);
+        assert_eq!(arena.last_child(root), Some(table));
+
+        let emph = arena.last_child(para).unwrap();
+        let b = arena.first_child(emph).unwrap();
+        assert_eq!(text(&arena, b), "b");
+        assert_eq!(arena.ancestors(b).collect::<Vec<_>>(), [emph, para, root]);
+        let texts: Vec<_> = arena
+            .descendants(para)
+            .filter(|i| matches!(arena.get(*i), AstNode::Inline(Inline::Str(_))))
+            .map(|i| text(&arena, i))
+            .collect();
+        assert_eq!(texts, ["a", "b"]);
+        assert_eq!(arena.descendants(emph).count(), 2);
+
+        let footnote = arena.footnote("n").unwrap();
+        assert_eq!(arena.parent(footnote), None);
+        assert_eq!(arena.children(footnote).count(), 1);
+
+        assert_eq!(arena.into_doc().unwrap(), d);
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut arena = Arena::from(doc());
+        let root = arena.root();
+        let para = arena.first_child(root).unwrap();
+        let hr = arena.next_sibling(para).unwrap();
+        let table = arena.last_child(root).unwrap();
+        let emph = arena.last_child(para).unwrap();
+        let a = arena.first_child(para).unwrap();
+
+        // move "a" into the emphasis, after "b"
+        arena.append(emph, a).unwrap();
+        assert_eq!(arena.children(para).collect::<Vec<_>>(), [emph]);
+        assert_eq!(arena.parent(a), Some(emph));
+
+        // wrong type for the parent, cycles and fixed children
+        assert!(arena.append(root, a).is_err());
+        assert!(arena.append(a, emph).is_err());
+        assert!(arena.append(emph, para).is_err());
+        let caption = arena.first_child(table).unwrap();
+        assert!(arena.detach(caption).is_err());
+        let row = arena.last_child(table).unwrap();
+        assert!(arena.prepend(table, row).is_err());
+        assert!(arena.insert_after(caption, row).is_ok());
+
+        arena.detach(hr).unwrap();
+        arena.insert_after(table, hr).unwrap();
+        let c = arena.new_node(match arena.get(a).clone() {
+            AstNode::Inline(Inline::Str(mut s)) => {
+                s.text = "c".to_string();
+                Inline::Str(s)
+            }
+            _ => unreachable!(),
+        });
+        arena.insert_before(emph, c).unwrap();
+
+        let d = arena.into_doc().unwrap();
+        let Block::Para(p) = &d.children[0] else {
+            panic!("expected a para");
+        };
+        assert!(matches!(&p.children[0], Inline::Str(s) if s.text == "c"));
+        assert!(matches!(&p.children[1], Inline::Emph(e) if e.children.len() == 2));
+        assert!(matches!(d.children[2], Block::ThematicBreak(_)));
+        assert_eq!(d.footnotes.len(), 1);
+    }
+}
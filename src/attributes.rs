@@ -1,12 +1,21 @@
-use std::collections::HashMap;
-
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-pub type Attributes = HashMap<String, String>;
+use std::{num::NonZeroU64, sync::LazyLock};
+
+use indexmap::IndexMap;
+
+use crate::{Error, Result};
+
+/// Attributes, in the order they were written.
+pub type Attributes = IndexMap<String, String>;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct SourceLoc {
     pub line: u64,
     pub col: u64,
@@ -15,35 +24,340 @@ pub struct SourceLoc {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Pos {
     pub start: SourceLoc,
     pub end: SourceLoc,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// An identifier for a node which is kept as the document is edited, assigned by [Doc::assign_ids](crate::Doc::assign_ids).
+///
+/// Node ids are only serialized and deserialized, as `nodeId`, within [WithNodeIds].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[cfg_attr(
-    feature = "serde",
-    derive(Serialize, Deserialize),
-    serde(rename_all = "camelCase")
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
 )]
-pub struct Meta {
-    #[cfg_attr(
-        feature = "serde",
-        serde(skip_serializing_if = "Attributes::is_empty", default)
-    )]
+pub struct NodeId(NonZeroU64);
+
+impl NodeId {
+    /// Make an id from a number, which must not be zero.
+    pub fn new(id: u64) -> Option<Self> {
+        NonZeroU64::new(id).map(Self)
+    }
+
+    /// The id after this one, unless it is the largest.
+    pub(crate) fn after(id: u64) -> Option<Self> {
+        id.checked_add(1).and_then(Self::new)
+    }
+
+    pub fn get(self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+thread_local! {
+    static NODE_IDS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Whether node ids are being serialized and deserialized.
+#[cfg(feature = "serde")]
+pub(crate) fn with_node_ids() -> bool {
+    NODE_IDS.get()
+}
+
+/// Serializes and deserializes a node with the [NodeId]s of it and its descendants, as `nodeId`,
+/// which djot.js does not have.
+///
+/// Elsewhere, ids are neither written nor read, so that the JSON is that of djot.js.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithNodeIds<T>(pub T);
+
+/// Restores the previous mode, even if (de)serializing panics.
+#[cfg(feature = "serde")]
+struct RestoreNodeIds(bool);
+
+#[cfg(feature = "serde")]
+impl Drop for RestoreNodeIds {
+    fn drop(&mut self) {
+        NODE_IDS.set(self.0);
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for WithNodeIds<T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let _restore = RestoreNodeIds(NODE_IDS.replace(true));
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for WithNodeIds<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let _restore = RestoreNodeIds(NODE_IDS.replace(true));
+        T::deserialize(deserializer).map(WithNodeIds)
+    }
+}
+
+/// A node's attributes, position and id.
+///
+/// Most nodes have no attributes and some have no position,
+/// so they are boxed on demand to keep `Meta` the size of a pointer.
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct Meta(Option<Box<MetaData>>);
+
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+struct MetaData {
+    pos: Option<Pos>,
+    attributes: Option<Box<AttributeMaps>>,
+    node_id: Option<NodeId>,
+    #[cfg(feature = "serde")]
+    layout: Layout,
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+struct AttributeMaps {
     attributes: Attributes,
-    #[cfg_attr(
-        feature = "serde",
-        serde(skip_serializing_if = "Attributes::is_empty", default)
-    )]
     auto_attributes: Attributes,
-    #[cfg_attr(
-        feature = "serde",
-        serde(skip_serializing_if = "Option::is_none", default)
-    )]
-    pos: Option<Pos>,
 }
 
+/// Returned for nodes without attributes.
+static NO_ATTRIBUTES: LazyLock<Attributes> = LazyLock::new(Attributes::new);
+
+impl Meta {
+    /// Build metadata, only allocating for whatever is present.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_parts(
+        attributes: Attributes,
+        auto_attributes: Attributes,
+        pos: Option<Pos>,
+        node_id: Option<NodeId>,
+        layout: Layout,
+    ) -> Self {
+        let attributes = (!attributes.is_empty() || !auto_attributes.is_empty()).then(|| {
+            Box::new(AttributeMaps {
+                attributes,
+                auto_attributes,
+            })
+        });
+        if pos.is_none() && attributes.is_none() && node_id.is_none() && layout == Layout::default()
+        {
+            return Meta(None);
+        }
+        Meta(Some(Box::new(MetaData {
+            pos,
+            attributes,
+            node_id,
+            layout,
+        })))
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn layout(&self) -> Layout {
+        self.0.as_ref().map(|d| d.layout).unwrap_or_default()
+    }
+
+    fn data_mut(&mut self) -> &mut MetaData {
+        self.0.get_or_insert_with(Default::default)
+    }
+
+    fn maps(&self) -> Option<&AttributeMaps> {
+        self.0.as_ref().and_then(|d| d.attributes.as_deref())
+    }
+
+    fn maps_mut(&mut self) -> &mut AttributeMaps {
+        self.data_mut()
+            .attributes
+            .get_or_insert_with(Default::default)
+    }
+}
+
+impl std::fmt::Debug for Meta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Meta")
+            .field("attributes", self.attributes())
+            .field("auto_attributes", self.auto_attributes())
+            .field("pos", &self.pos())
+            .field("node_id", &self.node_id())
+            .finish()
+    }
+}
+
+/// Where the fields of [Meta] appeared in djot.js JSON, so they can be written back out the same way.
+///
+/// djot.js writes `"autoAttributes": {}` on every heading,
+/// and adds the attributes of an inline after its position.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub(crate) struct Layout {
+    pub(crate) empty_attributes: bool,
+    pub(crate) empty_auto_attributes: bool,
+    pub(crate) attributes_after_pos: bool,
+}
+
+/// Whether the data is boxed and the layout are only representation details, so are ignored when comparing.
+/// Node ids identify nodes rather than describe them, so are ignored too.
+impl PartialEq for Meta {
+    fn eq(&self, other: &Self) -> bool {
+        self.attributes() == other.attributes()
+            && self.auto_attributes() == other.auto_attributes()
+            && self.pos() == other.pos()
+    }
+}
+
+impl Eq for Meta {}
+
+#[cfg(feature = "serde")]
+impl Serialize for Meta {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let layout = self.layout();
+        let attributes = !self.attributes().is_empty() || layout.empty_attributes;
+        let mut map = serializer.serialize_map(None)?;
+        if attributes && !layout.attributes_after_pos {
+            map.serialize_entry("attributes", self.attributes())?;
+        }
+        if !self.auto_attributes().is_empty() || layout.empty_auto_attributes {
+            map.serialize_entry("autoAttributes", self.auto_attributes())?;
+        }
+        if let Some(pos) = self.pos() {
+            map.serialize_entry("pos", pos)?;
+        }
+        if attributes && layout.attributes_after_pos {
+            map.serialize_entry("attributes", self.attributes())?;
+        }
+        if let Some(id) = self.node_id().filter(|_| with_node_ids()) {
+            map.serialize_entry("nodeId", &id)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Meta {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        crate::borrowed::Meta::deserialize(deserializer).map(crate::borrowed::Meta::into_owned)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl From<&ArchivedSourceLoc> for SourceLoc {
+    fn from(value: &ArchivedSourceLoc) -> Self {
+        Self {
+            line: value.line.to_native(),
+            col: value.col.to_native(),
+            offset: value.offset.to_native(),
+        }
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl From<&ArchivedPos> for Pos {
+    fn from(value: &ArchivedPos) -> Self {
+        Self {
+            start: (&value.start).into(),
+            end: (&value.end).into(),
+        }
+    }
+}
+
+/// Read access to archived metadata, mirroring [HasAttributes].
+#[cfg(feature = "rkyv")]
+impl ArchivedMeta {
+    fn data(&self) -> Option<&ArchivedMetaData> {
+        self.0.as_ref().map(|d| d.get())
+    }
+
+    fn maps(&self) -> Option<&ArchivedAttributeMaps> {
+        self.data()
+            .and_then(|d| d.attributes.as_ref())
+            .map(|m| m.get())
+    }
+
+    /// Iterate over the explicit attributes.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.maps()
+            .into_iter()
+            .flat_map(|m| m.attributes.iter())
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Iterate over the automatic attributes.
+    pub fn auto_attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.maps()
+            .into_iter()
+            .flat_map(|m| m.auto_attributes.iter())
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Look up an key in the explicit attributes, then the automatic attributes if not found.
+    pub fn get_attribute(&self, key: &str) -> Option<&str> {
+        let maps = self.maps()?;
+        maps.attributes
+            .get(key)
+            .or_else(|| maps.auto_attributes.get(key))
+            .map(|s| s.as_str())
+    }
+
+    /// Get the position.
+    pub fn pos(&self) -> Option<Pos> {
+        self.data().and_then(|d| d.pos.as_ref()).map(Pos::from)
+    }
+
+    /// Get the node's id, if one has been assigned.
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.data()
+            .and_then(|d| d.node_id.as_ref())
+            .and_then(|id| NodeId::new(id.0.get()))
+    }
+}
+
+/// Access to a node's attributes.
+///
+/// Nodes have explicit attributes, written in the source, and automatic attributes, added by the parser
+/// (e.g. the generated `id` of a section).
+/// When both have the same key, the explicit attribute takes precedence and the automatic one is ignored.
+/// The convenience methods below read with that precedence and only ever write explicit attributes.
 pub trait HasAttributes {
     /// Get a reference to the attributes.
     fn attributes(&self) -> &Attributes;
@@ -60,6 +374,18 @@ pub trait HasAttributes {
     /// Get a mutable reference to the optional position (so the position can be removed entirely).
     fn pos_mut(&mut self) -> &mut Option<Pos>;
 
+    /// Get the node's id, if one has been assigned.
+    ///
+    /// By default there is none.
+    fn node_id(&self) -> Option<NodeId> {
+        None
+    }
+
+    /// Set or remove the node's id.
+    ///
+    /// By default the id is not kept.
+    fn set_node_id(&mut self, _id: Option<NodeId>) {}
+
     /// Look up an key in the explicit attributes, then the automatic attributes if not found.
     fn get_attribute(&self, key: &str) -> Option<&str> {
         self.attributes()
@@ -67,6 +393,200 @@ pub trait HasAttributes {
             .or_else(|| self.auto_attributes().get(key))
             .map(|s| s.as_str())
     }
+
+    /// Get the `id`, explicit or automatic.
+    fn id(&self) -> Option<&str> {
+        self.get_attribute("id")
+    }
+
+    /// Set an explicit `id`, which overrides any automatic one.
+    fn set_id(&mut self, id: &str) {
+        self.attributes_mut()
+            .insert("id".to_string(), id.to_string());
+    }
+
+    /// Get the whitespace-separated classes of the `class` attribute, explicit or automatic.
+    fn classes(&self) -> Vec<&str> {
+        self.get_attribute("class")
+            .map(|c| c.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Check whether the node has a class.
+    fn has_class(&self, class: &str) -> bool {
+        self.classes().contains(&class)
+    }
+
+    /// Add a class if not already present, returning whether it was added.
+    ///
+    /// If the classes only come from the automatic attributes, they are copied to the explicit ones first.
+    fn add_class(&mut self, class: &str) -> bool {
+        if self.has_class(class) {
+            return false;
+        }
+        let mut classes: Vec<String> = self.classes().into_iter().map(str::to_string).collect();
+        classes.push(class.to_string());
+        set_classes(self, classes);
+        true
+    }
+
+    /// Remove a class, returning whether it was present.
+    ///
+    /// If the classes only come from the automatic attributes, the rest are copied to the explicit ones.
+    fn remove_class(&mut self, class: &str) -> bool {
+        if !self.has_class(class) {
+            return false;
+        }
+        let classes = self
+            .classes()
+            .into_iter()
+            .filter(|c| *c != class)
+            .map(str::to_string)
+            .collect();
+        set_classes(self, classes);
+        true
+    }
+
+    /// Add a class if absent or remove it if present, returning whether it is now present.
+    fn toggle_class(&mut self, class: &str) -> bool {
+        !self.remove_class(class) && self.add_class(class)
+    }
+}
+
+/// Set the explicit `class` attribute, removing it if there are no classes and nothing to override.
+fn set_classes<T: HasAttributes + ?Sized>(node: &mut T, classes: Vec<String>) {
+    if classes.is_empty() && !node.auto_attributes().contains_key("class") {
+        node.attributes_mut().shift_remove("class");
+    } else {
+        node.attributes_mut()
+            .insert("class".to_string(), classes.join(" "));
+    }
+}
+
+/// Whether a character can appear in an id, class or key without quoting.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | ':')
+}
+
+/// Parse a djot attribute block such as `{#id .a .b key="v" %comment%}`.
+///
+/// Classes accumulate into the `class` attribute; for any other repeated key, the last value wins.
+pub fn parse_attributes(s: &str) -> Result<Attributes> {
+    let invalid = |message: &str| Error::general(format!("invalid attributes '{s}': {message}"));
+    let inner = s
+        .trim()
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(|| invalid("expected braces"))?;
+    let mut attributes = Attributes::new();
+    let mut chars = inner.chars().peekable();
+    let name = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        let mut out = String::new();
+        while let Some(c) = chars.next_if(|c| is_name_char(*c)) {
+            out.push(c);
+        }
+        out
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '%' => {
+                if !chars.any(|c| c == '%') {
+                    return Err(invalid("unterminated comment"));
+                }
+            }
+            '#' | '.' => {
+                let value = name(&mut chars);
+                if value.is_empty() {
+                    return Err(invalid(&format!("expected a name after '{c}'")));
+                }
+                if c == '#' {
+                    attributes.insert("id".to_string(), value);
+                } else {
+                    attributes
+                        .entry("class".to_string())
+                        .and_modify(|classes| {
+                            classes.push(' ');
+                            classes.push_str(&value);
+                        })
+                        .or_insert(value);
+                }
+            }
+            c if is_name_char(c) => {
+                let key = c.to_string() + &name(&mut chars);
+                if chars.next() != Some('=') {
+                    return Err(invalid(&format!("expected '=' after '{key}'")));
+                }
+                let value = if chars.next_if_eq(&'"').is_some() {
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            None => return Err(invalid("unterminated string")),
+                            Some('"') => break,
+                            Some('\\') => value.extend(chars.next()),
+                            Some(c) => value.push(c),
+                        }
+                    }
+                    value
+                } else {
+                    let value = name(&mut chars);
+                    if value.is_empty() {
+                        return Err(invalid(&format!("expected a value for '{key}'")));
+                    }
+                    value
+                };
+                if key == "class" {
+                    attributes
+                        .entry(key)
+                        .and_modify(|classes| {
+                            classes.push(' ');
+                            classes.push_str(&value);
+                        })
+                        .or_insert(value);
+                } else {
+                    attributes.insert(key, value);
+                }
+            }
+            c => return Err(invalid(&format!("unexpected '{c}'"))),
+        }
+    }
+    Ok(attributes)
+}
+
+/// Format attributes as a djot attribute block, e.g. `{#id .a .b key="v"}`.
+///
+/// The id comes first, then the classes, then the other keys in order.
+/// Ids and classes that can't be written with `#` or `.` are written as quoted values instead.
+pub fn format_attributes(attributes: &Attributes) -> String {
+    let quoted = |key: &str, value: &str| {
+        format!(
+            "{key}=\"{}\"",
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        )
+    };
+    let is_name = |s: &str| !s.is_empty() && s.chars().all(is_name_char);
+    let mut parts = Vec::new();
+    if let Some(id) = attributes.get("id") {
+        parts.push(if is_name(id) {
+            format!("#{id}")
+        } else {
+            quoted("id", id)
+        });
+    }
+    if let Some(classes) = attributes.get("class") {
+        if classes.split_whitespace().all(is_name) && !classes.trim().is_empty() {
+            parts.extend(classes.split_whitespace().map(|c| format!(".{c}")));
+        } else {
+            parts.push(quoted("class", classes));
+        }
+    }
+    parts.extend(
+        attributes
+            .iter()
+            .filter(|(k, _)| *k != "id" && *k != "class")
+            .map(|(k, v)| quoted(k, v)),
+    );
+    format!("{{{}}}", parts.join(" "))
 }
 
 pub(crate) trait HasMeta {
@@ -94,26 +614,164 @@ impl<T: HasMeta> HasAttributes for T {
     fn pos_mut(&mut self) -> &mut Option<Pos> {
         self.meta_mut().pos_mut()
     }
+
+    fn node_id(&self) -> Option<NodeId> {
+        self.meta().node_id()
+    }
+
+    fn set_node_id(&mut self, id: Option<NodeId>) {
+        self.meta_mut().set_node_id(id)
+    }
 }
 
 impl HasAttributes for Meta {
     fn attributes(&self) -> &Attributes {
-        &self.attributes
+        self.maps().map_or(&NO_ATTRIBUTES, |m| &m.attributes)
     }
 
     fn attributes_mut(&mut self) -> &mut Attributes {
-        &mut self.attributes
+        &mut self.maps_mut().attributes
     }
 
     fn auto_attributes(&self) -> &Attributes {
-        &self.auto_attributes
+        self.maps().map_or(&NO_ATTRIBUTES, |m| &m.auto_attributes)
     }
 
     fn pos(&self) -> Option<&Pos> {
-        self.pos.as_ref()
+        self.0.as_ref().and_then(|d| d.pos.as_ref())
     }
 
     fn pos_mut(&mut self) -> &mut Option<Pos> {
-        &mut self.pos
+        &mut self.data_mut().pos
+    }
+
+    fn node_id(&self) -> Option<NodeId> {
+        self.0.as_ref().and_then(|d| d.node_id)
+    }
+
+    fn set_node_id(&mut self, id: Option<NodeId>) {
+        if id.is_some() || self.0.is_some() {
+            self.data_mut().node_id = id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(pairs: &[(&str, &str)]) -> Attributes {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_meta_size() {
+        assert_eq!(std::mem::size_of::<Meta>(), std::mem::size_of::<usize>());
+        let mut meta = Meta::default();
+        meta.set_node_id(None);
+        assert!(meta.0.is_none());
+        assert_eq!(meta, Meta::default());
+        *meta.pos_mut() = None;
+        assert!(meta.0.is_some());
+        assert_eq!(meta, Meta::default());
+        assert!(meta.0.as_ref().unwrap().attributes.is_none());
+    }
+
+    #[test]
+    fn test_id_precedence() {
+        let mut meta = Meta::default();
+        meta.maps_mut().auto_attributes = attrs(&[("id", "auto")]);
+        assert_eq!(meta.id(), Some("auto"));
+        meta.set_id("explicit");
+        assert_eq!(meta.id(), Some("explicit"));
+        assert_eq!(meta.auto_attributes().get("id").unwrap(), "auto");
+    }
+
+    #[test]
+    fn test_classes() {
+        let mut meta = Meta::default();
+        assert!(meta.classes().is_empty());
+        assert!(meta.add_class("a"));
+        assert!(!meta.add_class("a"));
+        assert!(meta.add_class("b"));
+        assert_eq!(meta.attributes().get("class").unwrap(), "a b");
+        assert!(meta.has_class("b"));
+        assert!(meta.remove_class("a"));
+        assert!(!meta.remove_class("a"));
+        assert!(!meta.toggle_class("b"));
+        assert!(!meta.attributes().contains_key("class"));
+        assert!(meta.toggle_class("c"));
+        assert_eq!(meta.classes(), vec!["c"]);
+
+        let mut meta = Meta::default();
+        meta.maps_mut().auto_attributes = attrs(&[("class", "x y")]);
+        assert!(meta.has_class("x"));
+        assert!(meta.add_class("z"));
+        assert_eq!(meta.attributes().get("class").unwrap(), "x y z");
+        assert!(meta.remove_class("x") && meta.remove_class("y") && meta.remove_class("z"));
+        // an empty explicit class still overrides the automatic one
+        assert!(meta.classes().is_empty());
+        assert_eq!(meta.attributes().get("class").unwrap(), "");
+    }
+
+    #[test]
+    fn test_parse_attributes() {
+        assert_eq!(
+            parse_attributes(r#"{#id .a key="v \"q\"" .b %a comment% n=1 class=c}"#).unwrap(),
+            attrs(&[
+                ("id", "id"),
+                ("class", "a b c"),
+                ("key", "v \"q\""),
+                ("n", "1")
+            ])
+        );
+        assert_eq!(parse_attributes("{}").unwrap(), Attributes::new());
+        assert_eq!(parse_attributes("{#a #b}").unwrap(), attrs(&[("id", "b")]));
+        for s in ["#a", "{#}", "{key}", "{key=}", r#"{k="v}"#, "{%x}", "{!}"] {
+            let err = parse_attributes(s).unwrap_err().to_string();
+            assert!(err.contains("invalid attributes"), "{s}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_format_attributes() {
+        let a = attrs(&[
+            ("z", "1"),
+            ("class", "a b"),
+            ("id", "x"),
+            ("key", r#"say "hi" \o/"#),
+        ]);
+        let s = format_attributes(&a);
+        assert_eq!(s, r#"{#x .a .b z="1" key="say \"hi\" \\o/"}"#);
+        assert_eq!(parse_attributes(&s).unwrap(), a);
+
+        let a = attrs(&[("id", "a b"), ("class", "c.d")]);
+        let s = format_attributes(&a);
+        assert_eq!(s, r#"{id="a b" class="c.d"}"#);
+        assert_eq!(parse_attributes(&s).unwrap(), a);
+        assert_eq!(format_attributes(&Attributes::new()), "{}");
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        for s in [
+            r#"{"attributes":{"b":"1","a":"2"},"pos":{"start":{"line":1,"col":1,"offset":0},"end":{"line":1,"col":1,"offset":0}}}"#,
+            r#"{"pos":{"start":{"line":1,"col":1,"offset":0},"end":{"line":1,"col":1,"offset":0}},"attributes":{"b":"1","a":"2"}}"#,
+            r#"{"attributes":{},"autoAttributes":{}}"#,
+            r#"{}"#,
+        ] {
+            let meta: Meta = serde_json::from_str(s).unwrap();
+            assert_eq!(serde_json::to_string(&meta).unwrap(), s);
+        }
+        let a: Meta = serde_json::from_str(r#"{"attributes":{}}"#).unwrap();
+        assert_eq!(a, Meta::default());
     }
 }
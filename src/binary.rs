@@ -0,0 +1,565 @@
+//! A compact representation of the AST for binary formats such as CBOR, MessagePack, postcard and bincode.
+//!
+//! The usual serde implementations write the djot.js JSON representation,
+//! which uses internally tagged enums and flattened attributes and positions.
+//! Deserializing those relies on the format describing its own structure,
+//! so fails in formats like postcard and bincode, and repeats every field name in formats like CBOR.
+//!
+//! Wrapping a node in [Compact] serializes it instead as a tuple of its fields in a fixed order,
+//! with enums tagged by their variant,
+//! which works with any serde format.
+//! This representation is meant for caches: it may change between versions of this crate.
+//!
+//! ```
+//! # use djot_ast::{binary::Compact, Doc};
+//! let doc: Doc = serde_json::from_str(r#"{
+//!     "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+//!     "children": [{"tag": "para", "children": [{"tag": "str", "text": "hello"}]}]
+//! }"#).unwrap();
+//! let bytes = postcard::to_allocvec(&Compact(&doc)).unwrap();
+//! let Compact(decoded): Compact<Doc> = postcard::from_bytes(&bytes).unwrap();
+//! assert_eq!(decoded, doc);
+//! ```
+//!
+//! The `cbor`, `msgpack` and `postcard` features add functions to write and read each format.
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::{
+    de::{self, Expected, SeqAccess, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+#[cfg(any(feature = "cbor", feature = "msgpack", feature = "postcard"))]
+use crate::Error;
+use crate::{
+    attributes::{HasAttributes, Layout, Meta, Pos},
+    block::{
+        Alignment, Block, BlockQuote, BulletList, BulletListStyle, Caption, Cell, CheckboxStatus,
+        CodeBlock, Definition, DefinitionList, DefinitionListItem, Div, Heading, ListItem,
+        OrderedList, OrderedListStyle, Para, RawBlock, Row, Section, Table, TaskList, TaskListItem,
+        Term, ThematicBreak,
+    },
+    inline::{
+        Delete, DisplayMath, DoubleQuoted, Email, Emph, FootnoteReference, HardBreak, Image,
+        Inline, InlineMath, Insert, Link, Mark, NonBreakingSpace, RawInline, SingleQuoted,
+        SmartPunctuation, SmartPunctuationType, SoftBreak, Span, Str, Strong, Subscript,
+        Superscript, Symb, Url, Verbatim,
+    },
+    unknown::{Unknown, Value},
+    AstNode, Doc, Footnote, Reference,
+};
+
+/// Serializes a reference to a node, or deserializes a node, in the compact representation.
+///
+/// Implemented for [Doc], [AstNode] and each kind of node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compact<T>(pub T);
+
+mod sealed {
+    /// A value with a compact representation.
+    pub trait Repr: Sized {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+        fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D)
+            -> Result<Self, D::Error>;
+    }
+}
+use sealed::Repr;
+
+impl<T: Repr> Serialize for Compact<&T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Repr> Deserialize<'de> for Compact<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Compact)
+    }
+}
+
+/// Values which already serialize the same way in every format.
+macro_rules! plain {
+    ($($ty:ty),+ $(,)?) => {
+        $(impl Repr for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                Serialize::serialize(self, serializer)
+            }
+
+            fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Deserialize::deserialize(deserializer)
+            }
+        })+
+    };
+}
+
+plain!(
+    String,
+    u64,
+    bool,
+    Option<String>,
+    Option<u64>,
+    Alignment,
+    BulletListStyle,
+    CheckboxStatus,
+    OrderedListStyle,
+    SmartPunctuationType,
+);
+
+impl<T: Repr> Repr for Vec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(Compact))
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Compact<T>>::deserialize(deserializer)?;
+        Ok(items.into_iter().map(|c| c.0).collect())
+    }
+}
+
+impl<T: Repr> Repr for IndexMap<String, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter().map(|(k, v)| (k, Compact(v))))
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = IndexMap::<String, Compact<T>>::deserialize(deserializer)?;
+        Ok(items.into_iter().map(|(k, v)| (k, v.0)).collect())
+    }
+}
+
+/// Written as an enum, as formats which do not describe themselves cannot tell the types of values apart.
+impl Repr for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        enum Ser<'a> {
+            Null,
+            Bool(bool),
+            U64(u64),
+            I64(i64),
+            F64(f64),
+            String(&'a str),
+            Array(Compact<&'a Vec<Value>>),
+            Object(Compact<&'a IndexMap<String, Value>>),
+        }
+
+        match self {
+            Value::Null => Ser::Null,
+            Value::Bool(v) => Ser::Bool(*v),
+            Value::U64(v) => Ser::U64(*v),
+            Value::I64(v) => Ser::I64(*v),
+            Value::F64(v) => Ser::F64(*v),
+            Value::String(v) => Ser::String(v),
+            Value::Array(items) => Ser::Array(Compact(items)),
+            Value::Object(fields) => Ser::Object(Compact(fields)),
+        }
+        .serialize(serializer)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        enum De {
+            Null,
+            Bool(bool),
+            U64(u64),
+            I64(i64),
+            F64(f64),
+            String(String),
+            Array(Compact<Vec<Value>>),
+            Object(Compact<IndexMap<String, Value>>),
+        }
+
+        let _nested = crate::deserialize::nest_value()?;
+        Ok(match De::deserialize(deserializer)? {
+            De::Null => Value::Null,
+            De::Bool(v) => Value::Bool(v),
+            De::U64(v) => Value::U64(v),
+            De::I64(v) => Value::I64(v),
+            De::F64(v) => Value::F64(v),
+            De::String(v) => Value::String(v),
+            De::Array(Compact(items)) => Value::Array(items),
+            De::Object(Compact(fields)) => Value::Object(fields),
+        })
+    }
+}
+
+/// Read the next element of a tuple, counting the elements read so far.
+fn element<'de, T: Deserialize<'de>, A: SeqAccess<'de>>(
+    seq: &mut A,
+    idx: &mut usize,
+    expected: &dyn Expected,
+) -> Result<T, A::Error> {
+    let value = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(*idx, expected))?;
+    *idx += 1;
+    Ok(value)
+}
+
+/// The layout flags of [Meta], as bits.
+const EMPTY_ATTRIBUTES: u8 = 1;
+const EMPTY_AUTO_ATTRIBUTES: u8 = 2;
+const ATTRIBUTES_AFTER_POS: u8 = 4;
+
+/// Written as `(attributes, auto attributes, position, layout, node id)`.
+impl Repr for Meta {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let layout = self.layout();
+        let bits = (layout.empty_attributes as u8 * EMPTY_ATTRIBUTES)
+            | (layout.empty_auto_attributes as u8 * EMPTY_AUTO_ATTRIBUTES)
+            | (layout.attributes_after_pos as u8 * ATTRIBUTES_AFTER_POS);
+        let mut tuple = serializer.serialize_tuple(META_LEN)?;
+        tuple.serialize_element(self.attributes())?;
+        tuple.serialize_element(self.auto_attributes())?;
+        tuple.serialize_element(&self.pos())?;
+        tuple.serialize_element(&bits)?;
+        tuple.serialize_element(&self.node_id())?;
+        tuple.end()
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MetaVisitor;
+
+        impl<'de> Visitor<'de> for MetaVisitor {
+            type Value = Meta;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("compact attributes and position")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Meta, A::Error> {
+                let mut idx = 0;
+                let attributes = element(&mut seq, &mut idx, &self)?;
+                let auto_attributes = element(&mut seq, &mut idx, &self)?;
+                let pos: Option<Pos> = element(&mut seq, &mut idx, &self)?;
+                let bits: u8 = element(&mut seq, &mut idx, &self)?;
+                let node_id: Option<crate::attributes::NodeId> =
+                    element(&mut seq, &mut idx, &self)?;
+                let layout = Layout {
+                    empty_attributes: bits & EMPTY_ATTRIBUTES != 0,
+                    empty_auto_attributes: bits & EMPTY_AUTO_ATTRIBUTES != 0,
+                    attributes_after_pos: bits & ATTRIBUTES_AFTER_POS != 0,
+                };
+                Ok(Meta::from_parts(
+                    attributes,
+                    auto_attributes,
+                    pos,
+                    node_id,
+                    layout,
+                ))
+            }
+        }
+
+        deserializer.deserialize_tuple(META_LEN, MetaVisitor)
+    }
+}
+
+const META_LEN: usize = 5;
+
+/// Write a node as a tuple of the given fields followed by its [Meta].
+macro_rules! compact_node {
+    ($($name:ident { $($field:ident: $ty:ty),* $(,)? });+ $(;)?) => {
+        $(impl Repr for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut tuple = serializer.serialize_tuple(<[&str]>::len(&[$(stringify!($field)),*]) + 1)?;
+                $(tuple.serialize_element(&Compact(&self.$field))?;)*
+                tuple.serialize_element(&Compact(&self.meta))?;
+                tuple.end()
+            }
+
+            fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct NodeVisitor;
+
+                impl<'de> Visitor<'de> for NodeVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str(concat!("a compact ", stringify!($name)))
+                    }
+
+                    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<$name, A::Error> {
+                        let mut idx = 0;
+                        $(let Compact::<$ty>($field) = element(&mut seq, &mut idx, &self)?;)*
+                        let Compact(meta) = element(&mut seq, &mut idx, &self)?;
+                        Ok($name { $($field,)* meta })
+                    }
+                }
+
+                deserializer.deserialize_tuple(<[&str]>::len(&[$(stringify!($field)),*]) + 1, NodeVisitor)
+            }
+        })+
+    };
+}
+
+/// Write an enum of nodes as its variant and that variant's node.
+macro_rules! compact_enum {
+    ($name:ident { $($variant:ident),+ $(,)? }) => {
+        impl Repr for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                #[derive(Serialize)]
+                enum Ser<'a> {
+                    $($variant(Compact<&'a $variant>),)+
+                }
+
+                match self {
+                    $(Self::$variant(node) => Ser::$variant(Compact(node)),)+
+                }
+                .serialize(serializer)
+            }
+
+            fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                #[derive(Deserialize)]
+                enum De {
+                    $($variant(Compact<$variant>),)+
+                }
+
+                let _nested = crate::deserialize::nest_node()?;
+                Ok(match De::deserialize(deserializer)? {
+                    $(De::$variant(Compact(node)) => Self::$variant(node),)+
+                })
+            }
+        }
+    };
+}
+
+compact_enum!(AstNode {
+    Doc,
+    Block,
+    Inline,
+    ListItem,
+    TaskListItem,
+    DefinitionListItem,
+    Term,
+    Definition,
+    Row,
+    Cell,
+    Caption,
+    Footnote,
+    Reference,
+});
+
+compact_enum!(Block {
+    Para,
+    Heading,
+    ThematicBreak,
+    Section,
+    Div,
+    CodeBlock,
+    RawBlock,
+    BlockQuote,
+    OrderedList,
+    BulletList,
+    TaskList,
+    DefinitionList,
+    Table,
+    Unknown,
+});
+
+compact_enum!(Inline {
+    Str,
+    SoftBreak,
+    HardBreak,
+    NonBreakingSpace,
+    Symb,
+    Verbatim,
+    RawInline,
+    InlineMath,
+    DisplayMath,
+    Url,
+    Email,
+    FootnoteReference,
+    SmartPunctuation,
+    Emph,
+    Strong,
+    Link,
+    Image,
+    Span,
+    Mark,
+    Superscript,
+    Subscript,
+    Insert,
+    Delete,
+    DoubleQuoted,
+    SingleQuoted,
+    Unknown,
+});
+
+compact_node!(
+    Doc {
+        references: IndexMap<String, Reference>,
+        auto_references: IndexMap<String, Reference>,
+        footnotes: IndexMap<String, Footnote>,
+        children: Vec<Block>,
+    };
+    Reference { label: String, destination: String };
+    Footnote { label: String, children: Vec<Block> };
+
+    Para { children: Vec<Inline> };
+    Heading { level: u64, children: Vec<Inline> };
+    ThematicBreak {};
+    Section { children: Vec<Block> };
+    Div { children: Vec<Block> };
+    CodeBlock { text: String, lang: Option<String> };
+    RawBlock { format: String, text: String };
+    BlockQuote { children: Vec<Block> };
+    OrderedList { style: OrderedListStyle, children: Vec<ListItem>, start: Option<u64>, tight: bool };
+    BulletList { tight: bool, style: BulletListStyle, children: Vec<ListItem> };
+    ListItem { children: Vec<Block> };
+    TaskList { tight: bool, children: Vec<TaskListItem> };
+    TaskListItem { checkbox: CheckboxStatus, children: Vec<Block> };
+    DefinitionList { children: Vec<DefinitionListItem> };
+    DefinitionListItem { term: Term, definition: Definition };
+    Term { children: Vec<Inline> };
+    Definition { children: Vec<Block> };
+    Table { caption: Caption, rows: Vec<Row> };
+    Caption { children: Vec<Inline> };
+    Row { children: Vec<Cell>, head: bool };
+    Cell { children: Vec<Inline>, head: bool, align: Alignment };
+
+    Str { text: String };
+    SoftBreak {};
+    HardBreak {};
+    NonBreakingSpace {};
+    Symb { alias: String };
+    Verbatim { text: String };
+    RawInline { format: String, text: String };
+    InlineMath { text: String };
+    DisplayMath { text: String };
+    Url { text: String };
+    Email { text: String };
+    FootnoteReference { text: String };
+    SmartPunctuation { punc_type: SmartPunctuationType, text: String };
+    Emph { children: Vec<Inline> };
+    Strong { children: Vec<Inline> };
+    Link { destination: Option<String>, reference: Option<String>, children: Vec<Inline> };
+    Image { destination: Option<String>, reference: Option<String>, children: Vec<Inline> };
+    Span { children: Vec<Inline> };
+    Mark { children: Vec<Inline> };
+    Superscript { children: Vec<Inline> };
+    Subscript { children: Vec<Inline> };
+    Insert { children: Vec<Inline> };
+    Delete { children: Vec<Inline> };
+    DoubleQuoted { children: Vec<Inline> };
+    SingleQuoted { children: Vec<Inline> };
+
+    Unknown { tag: String, fields: IndexMap<String, Value> };
+);
+
+#[cfg(any(feature = "cbor", feature = "msgpack", feature = "postcard"))]
+fn wrap(e: impl std::error::Error + 'static) -> Error {
+    Error::Wrapper(Box::new(e))
+}
+
+/// Write a node as CBOR.
+#[cfg(feature = "cbor")]
+pub fn to_cbor<T: Repr>(node: &T) -> crate::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ciborium::into_writer(&Compact(node), &mut out).map_err(wrap)?;
+    Ok(out)
+}
+
+/// Read a node written by [to_cbor].
+#[cfg(feature = "cbor")]
+pub fn from_cbor<T: Repr>(bytes: &[u8]) -> crate::Result<T> {
+    ciborium::from_reader::<Compact<T>, _>(bytes)
+        .map(|c| c.0)
+        .map_err(wrap)
+}
+
+/// Write a node as MessagePack.
+#[cfg(feature = "msgpack")]
+pub fn to_msgpack<T: Repr>(node: &T) -> crate::Result<Vec<u8>> {
+    rmp_serde::to_vec(&Compact(node)).map_err(wrap)
+}
+
+/// Read a node written by [to_msgpack].
+#[cfg(feature = "msgpack")]
+pub fn from_msgpack<T: Repr>(bytes: &[u8]) -> crate::Result<T> {
+    rmp_serde::from_slice::<Compact<T>>(bytes)
+        .map(|c| c.0)
+        .map_err(wrap)
+}
+
+/// Write a node as postcard.
+#[cfg(feature = "postcard")]
+pub fn to_postcard<T: Repr>(node: &T) -> crate::Result<Vec<u8>> {
+    postcard::to_allocvec(&Compact(node)).map_err(wrap)
+}
+
+/// Read a node written by [to_postcard].
+#[cfg(feature = "postcard")]
+pub fn from_postcard<T: Repr>(bytes: &[u8]) -> crate::Result<T> {
+    postcard::from_bytes::<Compact<T>>(bytes)
+        .map(|c| c.0)
+        .map_err(wrap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> Doc {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+            "children": [
+                {"tag": "heading", "level": 1, "autoAttributes": {},
+                    "children": [{"tag": "str", "text": "a", "pos": {
+                        "start": {"line": 1, "col": 3, "offset": 2}, "end": {"line": 1, "col": 3, "offset": 2}
+                    }, "attributes": {"class": "x"}}]},
+                {"tag": "definition_list", "children": [{"tag": "definition_list_item", "children": [
+                    {"tag": "term", "children": []},
+                    {"tag": "definition", "children": [{"tag": "thematic_break"}]}
+                ]}]},
+                {"tag": "table", "children": [
+                    {"tag": "caption", "children": []},
+                    {"tag": "row", "head": true, "children": [{"tag": "cell", "head": true, "align": "left", "children": []}]}
+                ]}
+            ]
+        }"#;
+        serde_json::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_postcard() {
+        let d = doc();
+        // the djot.js representation needs a self-describing format
+        assert!(postcard::to_allocvec(&d).is_err());
+
+        let bytes = postcard::to_allocvec(&Compact(&d)).unwrap();
+        let Compact(decoded): Compact<Doc> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, d);
+        assert_eq!(
+            serde_json::to_string(&decoded).unwrap(),
+            serde_json::to_string(&d).unwrap()
+        );
+
+        let node = AstNode::Block(d.children[2].clone());
+        let bytes = postcard::to_allocvec(&Compact(&node)).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<Compact<AstNode>>(&bytes).unwrap().0,
+            node
+        );
+        assert!(postcard::from_bytes::<Compact<AstNode>>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_postcard_depth() {
+        let max = crate::deserialize::Limits::DEFAULT.max_depth;
+        let mut block = Block::ThematicBreak(ThematicBreak {
+            meta: Meta::default(),
+        });
+        for _ in 0..max {
+            block = Block::BlockQuote(BlockQuote {
+                children: vec![block],
+                meta: Meta::default(),
+            });
+        }
+        let mut d = Doc::default();
+        d.children.push(block);
+        let bytes = postcard::to_allocvec(&Compact(&d)).unwrap();
+        let err = postcard::from_bytes::<Compact<Doc>>(&bytes).unwrap_err();
+        assert_eq!(err, postcard::Error::SerdeDeCustom);
+    }
+}
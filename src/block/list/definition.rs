@@ -1,7 +1,13 @@
 #[cfg(feature = "serde")]
-use serde::{ser::SerializeStruct, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 
-use crate::{macros::impl_hasmeta, Block, Meta, Node};
+#[cfg(feature = "serde")]
+use crate::macros::deserialize_variants;
+
+use crate::{
+    macros::{drop_children, impl_hasmeta},
+    Block, Meta, Node,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -9,10 +15,14 @@ use crate::{macros::impl_hasmeta, Block, Meta, Node};
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "definition_list")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct DefinitionList {
     pub children: Vec<DefinitionListItem>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for DefinitionList {
     fn node_type(&self) -> crate::NodeType {
@@ -22,10 +32,14 @@ impl Node for DefinitionList {
 impl_hasmeta!(DefinitionList);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct DefinitionListItem {
     pub term: Term,
     pub definition: Definition,
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for DefinitionListItem {
     fn node_type(&self) -> crate::NodeType {
@@ -40,13 +54,23 @@ impl Serialize for DefinitionListItem {
     where
         S: serde::Serializer,
     {
-        let mut s = serializer.serialize_struct("DefinitionListItem", 2)?;
-        s.serialize_field("tag", "definition_list_item")?;
-        s.serialize_field("children", &(&self.term, &self.definition))?;
-        s.end()
+        SerDefinitionListItem {
+            children: (&self.term, &self.definition),
+            meta: &self.meta,
+        }
+        .serialize(serializer)
     }
 }
 
+#[derive(Debug, Serialize)]
+#[cfg(feature = "serde")]
+#[serde(tag = "tag", rename = "definition_list_item")]
+struct SerDefinitionListItem<'a> {
+    children: (&'a Term, &'a Definition),
+    #[serde(flatten)]
+    meta: &'a Meta,
+}
+
 #[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for DefinitionListItem {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -54,27 +78,52 @@ impl<'de> Deserialize<'de> for DefinitionListItem {
         D: serde::Deserializer<'de>,
     {
         let deser = DeserDefinitionListItem::deserialize(deserializer)?;
-        Ok(deser.into())
+        deser.try_into().map_err(serde::de::Error::custom)
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Deserialize)]
 #[cfg(feature = "serde")]
 #[serde(tag = "tag", rename = "definition_list_item")]
 struct DeserDefinitionListItem {
-    children: (Term, Definition),
+    children: Vec<DeserTermOrDefinition>,
     #[cfg_attr(feature = "serde", serde(flatten))]
     meta: Meta,
 }
 
 #[cfg(feature = "serde")]
-impl From<DeserDefinitionListItem> for DefinitionListItem {
-    fn from(value: DeserDefinitionListItem) -> Self {
-        Self {
-            term: value.children.0,
-            definition: value.children.1,
-            meta: value.meta,
+enum DeserTermOrDefinition {
+    Term(Term),
+    Definition(Definition),
+}
+#[cfg(feature = "serde")]
+deserialize_variants!(strict DeserTermOrDefinition, Term, Definition);
+
+#[cfg(feature = "serde")]
+impl TryFrom<DeserDefinitionListItem> for DefinitionListItem {
+    type Error = crate::Error;
+
+    fn try_from(value: DeserDefinitionListItem) -> Result<Self, Self::Error> {
+        let child = |child, expected| crate::Error::Child {
+            tag: "definition_list_item",
+            child,
+            expected,
+        };
+        let mut children = value.children.into_iter();
+        let Some(DeserTermOrDefinition::Term(term)) = children.next() else {
+            return Err(child(0, "a term"));
+        };
+        let Some(DeserTermOrDefinition::Definition(definition)) = children.next() else {
+            return Err(child(1, "a definition"));
+        };
+        if children.next().is_some() {
+            return Err(child(2, "absent"));
         }
+        Ok(Self {
+            term,
+            definition,
+            meta: value.meta,
+        })
     }
 }
 
@@ -86,10 +135,14 @@ crate::macros::inline_container!(Term, "term");
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "definition")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Definition {
     pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Definition {
     fn node_type(&self) -> crate::NodeType {
@@ -97,6 +150,7 @@ impl Node for Definition {
     }
 }
 impl_hasmeta!(Definition);
+drop_children!(Definition, drop_blocks);
 
 #[cfg(all(test, feature = "serde"))]
 mod serde_tests {
@@ -109,4 +163,15 @@ mod serde_tests {
         let s2 = serde_json::to_string(&def).unwrap();
         assert_eq!(s, s2);
     }
+
+    #[test]
+    fn test_definition_list_item_pos() {
+        let s = concat!(
+            r#"{"tag":"definition_list_item","children":["#,
+            r#"{"tag":"term","children":[]},{"tag":"definition","children":[]}],"#,
+            r#""pos":{"start":{"line":1,"col":1,"offset":0},"end":{"line":1,"col":2,"offset":1}}}"#
+        );
+        let item: DefinitionListItem = serde_json::from_str(s).unwrap();
+        assert_eq!(serde_json::to_string(&item).unwrap(), s);
+    }
 }
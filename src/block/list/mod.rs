@@ -1,26 +1,41 @@
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{macros::impl_hasmeta, Block, Meta, Node};
+use crate::{
+    macros::{drop_children, impl_hasmeta},
+    Block, Meta, Node,
+};
 
 mod ordered;
+#[cfg(feature = "rkyv")]
+pub use ordered::{
+    ArchivedFenceStyle, ArchivedNumberStyle, ArchivedOrderedList, ArchivedOrderedListStyle,
+};
 pub use ordered::{FenceStyle, NumberStyle, OrderedList, OrderedListStyle};
 
 mod definition;
+#[cfg(feature = "rkyv")]
+pub use definition::{
+    ArchivedDefinition, ArchivedDefinitionList, ArchivedDefinitionListItem, ArchivedTerm,
+};
 pub use definition::{Definition, DefinitionList, DefinitionListItem, Term};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
-    serde(tag = "bullet_list", rename = "bullet_list")
+    serde(tag = "tag", rename = "bullet_list")
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
 )]
 pub struct BulletList {
-    tight: bool,
-    style: BulletListStyle,
-    children: Vec<ListItem>,
+    pub tight: bool,
+    pub style: BulletListStyle,
+    pub children: Vec<ListItem>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for BulletList {
     fn node_type(&self) -> crate::NodeType {
@@ -31,6 +46,11 @@ impl_hasmeta!(BulletList);
 
 #[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub enum BulletListStyle {
     #[cfg_attr(feature = "serde", serde(rename = "+"))]
     Plus,
@@ -46,10 +66,14 @@ pub enum BulletListStyle {
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "list_item")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct ListItem {
-    children: Vec<Block>,
+    pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for ListItem {
     fn node_type(&self) -> crate::NodeType {
@@ -57,6 +81,7 @@ impl Node for ListItem {
     }
 }
 impl_hasmeta!(ListItem);
+drop_children!(ListItem, drop_blocks);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -64,11 +89,15 @@ impl_hasmeta!(ListItem);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "task_list")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct TaskList {
-    tight: bool,
-    children: Vec<TaskListItem>,
+    pub tight: bool,
+    pub children: Vec<TaskListItem>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for TaskList {
     fn node_type(&self) -> crate::NodeType {
@@ -83,11 +112,15 @@ impl_hasmeta!(TaskList);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "task_list_item")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct TaskListItem {
-    checkbox: CheckboxStatus,
-    children: Vec<Block>,
+    pub checkbox: CheckboxStatus,
+    pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for TaskListItem {
     fn node_type(&self) -> crate::NodeType {
@@ -95,6 +128,7 @@ impl Node for TaskListItem {
     }
 }
 impl_hasmeta!(TaskListItem);
+drop_children!(TaskListItem, drop_blocks);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -102,6 +136,11 @@ impl_hasmeta!(TaskListItem);
     derive(Serialize, Deserialize),
     serde(rename_all = "camelCase")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub enum CheckboxStatus {
     Checked,
     Unchecked,
@@ -16,13 +16,18 @@ use super::ListItem;
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "ordered_list")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct OrderedList {
     pub style: OrderedListStyle,
-    pub tight: bool,
-    pub start: Option<u64>,
     pub children: Vec<ListItem>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub start: Option<u64>,
+    pub tight: bool,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for OrderedList {
     fn node_type(&self) -> crate::NodeType {
@@ -32,11 +37,30 @@ impl Node for OrderedList {
 impl_hasmeta!(OrderedList);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub struct OrderedListStyle {
     number: NumberStyle,
     fence: FenceStyle,
 }
 
+impl OrderedListStyle {
+    pub fn new(number: NumberStyle, fence: FenceStyle) -> Self {
+        Self { number, fence }
+    }
+
+    pub fn number(&self) -> NumberStyle {
+        self.number
+    }
+
+    pub fn fence(&self) -> FenceStyle {
+        self.fence
+    }
+}
+
 #[cfg(feature = "serde")]
 impl Serialize for OrderedListStyle {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
@@ -84,7 +108,7 @@ impl FromStr for OrderedListStyle {
     type Err = crate::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let invalid = || Error::General(format!("invalid ordered list style '{s}'"));
+        let invalid = || Error::InvalidListStyle(s.to_string());
         let mut chars = s.chars();
         let mut c = chars.next().ok_or_else(invalid)?;
         let starts_paren = if c == '(' {
@@ -110,14 +134,22 @@ impl FromStr for OrderedListStyle {
                     FenceStyle::RightParen
                 }
             }
-            '.' => FenceStyle::Dot,
+            '.' if !starts_paren => FenceStyle::Dot,
             _ => return Err(invalid()),
         };
+        if chars.next().is_some() {
+            return Err(invalid());
+        }
         Ok(Self { number, fence })
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub enum NumberStyle {
     Arabic,
     LowerAlpha,
@@ -127,8 +159,28 @@ pub enum NumberStyle {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub enum FenceStyle {
     Dot,
     RightParen,
     BothParen,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_from_str() {
+        for s in ["1.", "a)", "(A)", "i.", "(I)"] {
+            assert_eq!(OrderedListStyle::from_str(s).unwrap().to_string(), s);
+        }
+        for s in ["", "1", "(1", "(1.", "1.)", "1.x", "b.", "(1))"] {
+            assert!(OrderedListStyle::from_str(s).is_err(), "{s}");
+        }
+    }
+}
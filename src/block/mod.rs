@@ -4,9 +4,12 @@ use serde::{Deserialize, Serialize};
 use crate::attributes::{HasMeta, Meta};
 use crate::{
     inline::Inline,
-    macros::{atom, from_into_variants, impl_hasmeta},
+    macros::{
+        atom, deserialize_variants, drop_children, from_into_variants, impl_hasmeta,
+        serialize_variants,
+    },
 };
-use crate::{Node, NodeType};
+use crate::{unknown::Unknown, Node, NodeType};
 
 mod list;
 pub use list::{
@@ -14,31 +17,87 @@ pub use list::{
     FenceStyle, ListItem, NumberStyle, OrderedList, OrderedListStyle, TaskList, TaskListItem, Term,
 };
 mod table;
-pub use table::{Caption, Cell, Row, Table};
+#[cfg(feature = "rkyv")]
+pub use list::{
+    ArchivedBulletList, ArchivedBulletListStyle, ArchivedCheckboxStatus, ArchivedDefinition,
+    ArchivedDefinitionList, ArchivedDefinitionListItem, ArchivedFenceStyle, ArchivedListItem,
+    ArchivedNumberStyle, ArchivedOrderedList, ArchivedOrderedListStyle, ArchivedTaskList,
+    ArchivedTaskListItem, ArchivedTerm,
+};
+pub use table::{Alignment, Caption, Cell, Row, Table};
+#[cfg(feature = "rkyv")]
+pub use table::{ArchivedAlignment, ArchivedCaption, ArchivedCell, ArchivedRow, ArchivedTable};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+// The variants contain this enum again, so their bounds are omitted and the ones they need are given here.
 #[cfg_attr(
-    feature = "serde",
-    derive(Serialize, Deserialize),
-    serde(tag = "tag", rename_all = "snake_case")
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(
+        serialize_bounds(
+            __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+            __S::Error: rkyv::rancor::Source,
+        ),
+        deserialize_bounds(__D::Error: rkyv::rancor::Source),
+        bytecheck(bounds(
+            __C: rkyv::validation::ArchiveContext,
+            __C::Error: rkyv::rancor::Source,
+        )),
+    )
 )]
 pub enum Block {
-    Para(Para),
-    Heading(Heading),
-    ThematicBreak(ThematicBreak),
-    Section(Section),
-    Div(Div),
-    CodeBlock(CodeBlock),
-    RawBlock(RawBlock),
-    BlockQuote(BlockQuote),
-    OrderedList(OrderedList),
-    BulletList(BulletList),
-    TaskList(TaskList),
-    DefinitionList(DefinitionList),
-    Table(Table),
+    Para(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Para),
+    Heading(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Heading),
+    ThematicBreak(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] ThematicBreak),
+    Section(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Section),
+    Div(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Div),
+    CodeBlock(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] CodeBlock),
+    RawBlock(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] RawBlock),
+    BlockQuote(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] BlockQuote),
+    OrderedList(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] OrderedList),
+    BulletList(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] BulletList),
+    TaskList(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] TaskList),
+    DefinitionList(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] DefinitionList),
+    Table(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Table),
+    /// A node with a tag this crate does not know, read when deserializing [leniently](crate::deserialize::Lenient).
+    Unknown(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Unknown),
 }
 
 from_into_variants!(
+    Block,
+    Para,
+    Heading,
+    ThematicBreak,
+    Section,
+    Div,
+    CodeBlock,
+    RawBlock,
+    BlockQuote,
+    OrderedList,
+    BulletList,
+    TaskList,
+    DefinitionList,
+    Table,
+    Unknown
+);
+serialize_variants!(
+    Block,
+    Para,
+    Heading,
+    ThematicBreak,
+    Section,
+    Div,
+    CodeBlock,
+    RawBlock,
+    BlockQuote,
+    OrderedList,
+    BulletList,
+    TaskList,
+    DefinitionList,
+    Table,
+    Unknown
+);
+deserialize_variants!(
     Block,
     Para,
     Heading,
@@ -72,6 +131,7 @@ impl HasMeta for Block {
             TaskList(i) => i.meta(),
             DefinitionList(i) => i.meta(),
             Table(i) => i.meta(),
+            Unknown(i) => i.meta(),
         }
     }
 
@@ -91,6 +151,7 @@ impl HasMeta for Block {
             TaskList(i) => i.meta_mut(),
             DefinitionList(i) => i.meta_mut(),
             Table(i) => i.meta_mut(),
+            Unknown(i) => i.meta_mut(),
         }
     }
 }
@@ -112,6 +173,7 @@ impl Node for Block {
             TaskList(i) => i.node_type(),
             DefinitionList(i) => i.node_type(),
             Table(i) => i.node_type(),
+            Unknown(i) => i.node_type(),
         }
     }
 }
@@ -122,10 +184,14 @@ impl Node for Block {
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "para")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Para {
     pub children: Vec<Inline>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Para {
     fn node_type(&self) -> crate::NodeType {
@@ -140,11 +206,15 @@ impl_hasmeta!(Para);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "heading")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Heading {
     pub level: u64,
     pub children: Vec<Inline>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Heading {
     fn node_type(&self) -> crate::NodeType {
@@ -155,16 +225,20 @@ impl_hasmeta!(Heading);
 
 atom!(ThematicBreak, "thematic_break");
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "section")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Section {
     pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Section {
     fn node_type(&self) -> crate::NodeType {
@@ -172,6 +246,7 @@ impl Node for Section {
     }
 }
 impl_hasmeta!(Section);
+drop_children!(Section, drop_blocks);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -179,10 +254,14 @@ impl_hasmeta!(Section);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "div")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Div {
     pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Div {
     fn node_type(&self) -> crate::NodeType {
@@ -190,6 +269,7 @@ impl Node for Div {
     }
 }
 impl_hasmeta!(Div);
+drop_children!(Div, drop_blocks);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -197,10 +277,14 @@ impl_hasmeta!(Div);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "block_quote")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct BlockQuote {
     pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for BlockQuote {
     fn node_type(&self) -> crate::NodeType {
@@ -208,6 +292,7 @@ impl Node for BlockQuote {
     }
 }
 impl_hasmeta!(BlockQuote);
+drop_children!(BlockQuote, drop_blocks);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -215,11 +300,16 @@ impl_hasmeta!(BlockQuote);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "code_block")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct CodeBlock {
-    lang: Option<String>,
-    text: String,
+    pub text: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub lang: Option<String>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for CodeBlock {
     fn node_type(&self) -> crate::NodeType {
@@ -234,11 +324,15 @@ impl_hasmeta!(CodeBlock);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "raw_block")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct RawBlock {
-    format: String,
-    text: String,
+    pub format: String,
+    pub text: String,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for RawBlock {
     fn node_type(&self) -> crate::NodeType {
@@ -2,16 +2,23 @@ use crate::{macros::impl_hasmeta, Error, Inline, Meta, Node};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "serde")]
+use crate::macros::deserialize_variants;
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "caption")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Caption {
     pub children: Vec<Inline>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Caption {
     fn node_type(&self) -> crate::NodeType {
@@ -26,11 +33,15 @@ impl_hasmeta!(Caption);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "row")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Row {
-    pub head: bool,
     pub children: Vec<Cell>,
+    pub head: bool,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Row {
     fn node_type(&self) -> crate::NodeType {
@@ -45,6 +56,11 @@ impl_hasmeta!(Row);
     derive(Serialize, Deserialize),
     serde(rename_all = "snake_case")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub enum Alignment {
     #[default]
     Default,
@@ -59,12 +75,16 @@ pub enum Alignment {
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "cell")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Cell {
+    pub children: Vec<Inline>,
     pub head: bool,
     pub align: Alignment,
-    pub children: Vec<Inline>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Cell {
     fn node_type(&self) -> crate::NodeType {
@@ -74,10 +94,14 @@ impl Node for Cell {
 impl_hasmeta!(Cell);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Table {
-    caption: Caption,
-    rows: Vec<Row>,
-    meta: Meta,
+    pub caption: Caption,
+    pub rows: Vec<Row>,
+    pub(crate) meta: Meta,
 }
 impl Node for Table {
     fn node_type(&self) -> crate::NodeType {
@@ -87,7 +111,7 @@ impl Node for Table {
 impl_hasmeta!(Table);
 
 #[cfg(feature = "serde")]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Deserialize)]
 #[serde(tag = "tag", rename = "table")]
 struct DeserTable {
     children: Vec<DeserCapOrRow>,
@@ -96,12 +120,12 @@ struct DeserTable {
 }
 
 #[cfg(feature = "serde")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "tag", rename_all = "snake_case")]
 enum DeserCapOrRow {
     Caption(Caption),
     Row(Row),
 }
+#[cfg(feature = "serde")]
+deserialize_variants!(strict DeserCapOrRow, Caption, Row);
 
 #[cfg(feature = "serde")]
 impl TryFrom<DeserTable> for Table {
@@ -111,12 +135,23 @@ impl TryFrom<DeserTable> for Table {
         let mut children = value.children.into_iter();
         let caption = match children.next() {
             Some(DeserCapOrRow::Caption(c)) => c,
-            _ => return Err(Error::general("children must start with a caption")),
+            _ => {
+                return Err(Error::Child {
+                    tag: "table",
+                    child: 0,
+                    expected: "a caption",
+                })
+            }
         };
         let r_rows = children
-            .map(|c| match c {
+            .enumerate()
+            .map(|(i, c)| match c {
                 DeserCapOrRow::Row(row) => Ok(row),
-                _ => Err(Error::general("children other than first must be rows")),
+                _ => Err(Error::Child {
+                    tag: "table",
+                    child: i + 1,
+                    expected: "a row",
+                }),
             })
             .collect::<crate::Result<Vec<Row>>>();
         Ok(Self {
@@ -149,7 +184,7 @@ struct SerTable<'a> {
 
 #[cfg(feature = "serde")]
 #[derive(Debug, Serialize)]
-#[serde(tag = "tag", rename_all = "snake_case")]
+#[serde(untagged)]
 enum SerCapOrRow<'a> {
     Caption(&'a Caption),
     Row(&'a Row),
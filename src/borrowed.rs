@@ -0,0 +1,782 @@
+//! A borrowed form of the AST, for reading large documents without copying their text.
+//!
+//! Each type mirrors the owned type of the same name, but its strings are [Cow]s that borrow from the input
+//! wherever the deserializer allows: `serde_json::from_str`, for example, borrows every string without escapes.
+//! `into_owned` converts to the owned types.
+//!
+//! Deserializing counts the nodes within the [Limits](crate::deserialize::Limits), as for the owned types,
+//! but reads each node in place rather than into memory when its tag is its first field.
+//! Like deserializing, `into_owned` and dropping recurse through the nesting,
+//! so they are only as deep as the limits allowed.
+//!
+//! ```
+//! use std::borrow::Cow;
+//! use djot_ast::borrowed::{Block, Doc, Inline};
+//!
+//! let json = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [
+//!     {"tag": "para", "children": [{"tag": "str", "text": "plain"}, {"tag": "str", "text": "\"escaped\""}]}
+//! ]}"#;
+//! let doc: Doc = serde_json::from_str(json).unwrap();
+//! let Block::Para(para) = &doc.children[0] else { panic!() };
+//! let [Inline::Str(plain), Inline::Str(escaped)] = &para.children[..] else { panic!() };
+//! assert!(matches!(plain.text, Cow::Borrowed("plain")));
+//! assert!(matches!(escaped.text, Cow::Owned(_)));
+//!
+//! let owned: djot_ast::Doc = doc.into_owned();
+//! assert_eq!(owned, serde_json::from_str(json).unwrap());
+//! ```
+use std::{borrow::Cow, hash::Hash, marker::PhantomData, sync::LazyLock};
+
+use indexmap::IndexMap;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{
+    attributes::{Layout, NodeId, Pos},
+    block::{self, Alignment, BulletListStyle, CheckboxStatus, OrderedListStyle},
+    inline::{self, SmartPunctuationType},
+    Error,
+};
+
+/// Attributes borrowing from the input, in the order they were written.
+pub type Attributes<'a> = IndexMap<Cow<'a, str>, Cow<'a, str>>;
+
+/// Conversion from a borrowed value to its owned counterpart.
+trait IntoOwned {
+    type Owned;
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl IntoOwned for Cow<'_, str> {
+    type Owned = String;
+
+    fn into_owned(self) -> String {
+        Cow::into_owned(self)
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(T::into_owned)
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Vec<T> {
+    type Owned = Vec<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(T::into_owned).collect()
+    }
+}
+
+impl<K: IntoOwned, V: IntoOwned> IntoOwned for IndexMap<K, V>
+where
+    K::Owned: Hash + Eq,
+{
+    type Owned = IndexMap<K::Owned, V::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter()
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect()
+    }
+}
+
+macro_rules! already_owned {
+    ($($ty:ty),+) => {
+        $(
+        impl IntoOwned for $ty {
+            type Owned = Self;
+
+            fn into_owned(self) -> Self {
+                self
+            }
+        }
+        )+
+    };
+}
+
+already_owned!(
+    bool,
+    u64,
+    Alignment,
+    BulletListStyle,
+    CheckboxStatus,
+    OrderedListStyle,
+    SmartPunctuationType
+);
+
+/// A string which borrows from the input if it can.
+///
+/// Serde only borrows into a bare `Cow<str>` field marked `#[serde(borrow)]`,
+/// so this is used for strings inside options and maps.
+#[derive(PartialEq, Eq, Hash)]
+struct CowStr<'a>(Cow<'a, str>);
+
+impl<'de: 'a, 'a> Deserialize<'de> for CowStr<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CowStrVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> de::Visitor<'de> for CowStrVisitor<'a> {
+            type Value = CowStr<'a>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(CowStr(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(CowStr(Cow::Owned(v.to_string())))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(CowStr(Cow::Owned(v)))
+            }
+        }
+
+        deserializer.deserialize_str(CowStrVisitor(PhantomData))
+    }
+}
+
+fn optional<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error> {
+    Ok(Option::<CowStr>::deserialize(deserializer)?.map(|s| s.0))
+}
+
+fn labelled<'de: 'a, 'a, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<IndexMap<Cow<'a, str>, T>, D::Error> {
+    Ok(IndexMap::<CowStr, T>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(k, v)| (k.0, v))
+        .collect())
+}
+
+/// Mirrors [crate::attributes::Meta], including its pointer-sized layout.
+#[derive(Debug, Clone, Default)]
+pub struct Meta<'a>(Option<Box<MetaData<'a>>>);
+
+#[derive(Debug, Clone, Default)]
+struct MetaData<'a> {
+    pos: Option<Pos>,
+    attributes: Option<Box<[Attributes<'a>; 2]>>,
+    node_id: Option<NodeId>,
+    layout: Layout,
+}
+
+/// Returned for nodes without attributes.
+static NO_ATTRIBUTES: LazyLock<Attributes<'static>> = LazyLock::new(Attributes::new);
+
+impl<'a> Meta<'a> {
+    /// Get the explicit attributes.
+    pub fn attributes(&self) -> &Attributes<'a> {
+        self.maps().map_or(&NO_ATTRIBUTES, |[a, _]| a)
+    }
+
+    /// Get the automatic attributes.
+    pub fn auto_attributes(&self) -> &Attributes<'a> {
+        self.maps().map_or(&NO_ATTRIBUTES, |[_, a]| a)
+    }
+
+    /// Get the position.
+    pub fn pos(&self) -> Option<&Pos> {
+        self.0.as_ref().and_then(|d| d.pos.as_ref())
+    }
+
+    /// Get the node id, which is only deserialized within [WithNodeIds](crate::attributes::WithNodeIds).
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.0.as_ref().and_then(|d| d.node_id)
+    }
+
+    fn maps(&self) -> Option<&[Attributes<'a>; 2]> {
+        self.0.as_ref().and_then(|d| d.attributes.as_deref())
+    }
+
+    pub fn into_owned(self) -> crate::Meta {
+        let Some(data) = self.0 else {
+            return crate::Meta::default();
+        };
+        let [attributes, auto_attributes] = data.attributes.map_or_else(Default::default, |a| *a);
+        crate::Meta::from_parts(
+            attributes.into_owned(),
+            auto_attributes.into_owned(),
+            data.pos,
+            data.node_id,
+            data.layout,
+        )
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Meta<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MetaVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> de::Visitor<'de> for MetaVisitor<'a> {
+            type Value = Meta<'a>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map of attributes and position")
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Meta<'a>, A::Error> {
+                let attributes = |m: IndexMap<CowStr<'a>, CowStr<'a>>| -> Attributes<'a> {
+                    m.into_iter().map(|(k, v)| (k.0, v.0)).collect()
+                };
+                let mut maps: [Attributes; 2] = Default::default();
+                let mut pos = None;
+                let mut node_id = None;
+                let mut layout = Layout::default();
+                while let Some(key) = map.next_key::<CowStr>()? {
+                    match key.0.as_ref() {
+                        "attributes" => {
+                            maps[0] = attributes(map.next_value()?);
+                            layout.empty_attributes = maps[0].is_empty();
+                            layout.attributes_after_pos = pos.is_some();
+                        }
+                        "autoAttributes" => {
+                            maps[1] = attributes(map.next_value()?);
+                            layout.empty_auto_attributes = maps[1].is_empty();
+                        }
+                        "pos" => pos = map.next_value()?,
+                        "nodeId" if crate::attributes::with_node_ids() => {
+                            node_id = map.next_value()?
+                        }
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                let attributes = maps.iter().any(|m| !m.is_empty()).then(|| Box::new(maps));
+                if pos.is_none()
+                    && attributes.is_none()
+                    && node_id.is_none()
+                    && layout == Layout::default()
+                {
+                    return Ok(Meta(None));
+                }
+                Ok(Meta(Some(Box::new(MetaData {
+                    pos,
+                    attributes,
+                    node_id,
+                    layout,
+                }))))
+            }
+        }
+
+        deserializer.deserialize_map(MetaVisitor(PhantomData))
+    }
+}
+
+/// Define a node mirroring an owned node, with its fields in the same order.
+macro_rules! node {
+    ($(#[$attr:meta])* $name:ident, $tag:literal, $owned:path {
+        $($(#[$field_attr:meta])* $field:ident: $ty:ty),* $(,)?
+    }) => {
+        #[doc = concat!("Mirrors [", stringify!($owned), "].")]
+        #[derive(Debug, Clone, Deserialize)]
+        #[serde(tag = "tag", rename = $tag)]
+        $(#[$attr])*
+        pub struct $name<'a> {
+            $($(#[$field_attr])* pub $field: $ty,)*
+            #[serde(flatten, borrow)]
+            pub meta: Meta<'a>,
+        }
+
+        impl $name<'_> {
+            pub fn into_owned(self) -> $owned {
+                $owned {
+                    $($field: IntoOwned::into_owned(self.$field),)*
+                    meta: self.meta.into_owned(),
+                }
+            }
+        }
+
+        impl IntoOwned for $name<'_> {
+            type Owned = $owned;
+
+            fn into_owned(self) -> $owned {
+                Self::into_owned(self)
+            }
+        }
+    };
+}
+
+/// Deserialize an enum of nodes by the tag of each, counting each node within the [Limits](crate::deserialize::Limits).
+///
+/// Serde's internally tagged enums read each node into memory to find its tag, without counting the depth and
+/// without borrowing, so this reads the tag as the first field, as djot.js and this crate write it,
+/// and deserializes the node from the rest of the map.
+/// A node with its tag elsewhere is read into memory, and its strings are owned.
+macro_rules! deserialize_tagged {
+    ($name:ident, $($variant:ident),+) => {
+        impl<'de: 'a, 'a> Deserialize<'de> for $name<'a> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use serde::de::{
+                    value::{MapAccessDeserializer, StrDeserializer},
+                    Error as _, IntoDeserializer, MapAccess,
+                };
+
+                use crate::unknown::Value;
+
+                #[derive(Deserialize)]
+                #[serde(rename_all = "snake_case")]
+                enum Tag {
+                    $($variant,)+
+                }
+
+                fn tag<E: de::Error>(tag: &str) -> Result<Tag, E> {
+                    let tag: StrDeserializer<E> = tag.into_deserializer();
+                    Tag::deserialize(tag)
+                }
+
+                struct NodeVisitor<'a>(PhantomData<&'a ()>);
+
+                impl<'de: 'a, 'a> de::Visitor<'de> for NodeVisitor<'a> {
+                    type Value = $name<'a>;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        formatter.write_str("a node")
+                    }
+
+                    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<$name<'a>, A::Error> {
+                        let _nested = crate::deserialize::nest_node()?;
+                        let first = map.next_key::<CowStr>()?;
+                        if first.as_ref().is_some_and(|key| key.0 == "tag") {
+                            let tag = tag(&map.next_value::<CowStr>()?.0)?;
+                            // a function per variant keeps their results out of this frame, which is on the stack for each level
+                            let read: fn(MapAccessDeserializer<A>) -> Result<$name<'a>, A::Error> = match tag {
+                                $(Tag::$variant => |node| $variant::deserialize(node).map($name::$variant),)+
+                            };
+                            return read(MapAccessDeserializer::new(map));
+                        }
+                        let fields = crate::unknown::read_fields(first.map(|key| key.0.into_owned()), map)?;
+                        let tag = match fields.get("tag") {
+                            Some(Value::String(t)) => tag(t)?,
+                            Some(other) => return Err(A::Error::invalid_type(other.unexpected(), &"a tag")),
+                            None => return Err(A::Error::missing_field("tag")),
+                        };
+                        let read: fn(Value) -> Result<$name<'a>, de::value::Error> = match tag {
+                            $(Tag::$variant => |node| $variant::deserialize(node).map($name::$variant),)+
+                        };
+                        read(Value::Object(fields)).map_err(A::Error::custom)
+                    }
+                }
+
+                deserializer.deserialize_map(NodeVisitor(PhantomData))
+            }
+        }
+    };
+}
+
+/// Define an enum of nodes mirroring an owned enum.
+macro_rules! node_enum {
+    ($name:ident, $owned:path, $($variant:ident),+ $(,)?) => {
+        #[doc = concat!("Mirrors [", stringify!($owned), "].")]
+        #[derive(Debug, Clone)]
+        pub enum $name<'a> {
+            $($variant($variant<'a>),)+
+        }
+
+        deserialize_tagged!($name, $($variant),+);
+
+        impl $name<'_> {
+            pub fn into_owned(self) -> $owned {
+                match self {
+                    $($name::$variant(node) => node.into_owned().into(),)+
+                }
+            }
+        }
+
+        impl IntoOwned for $name<'_> {
+            type Owned = $owned;
+
+            fn into_owned(self) -> $owned {
+                Self::into_owned(self)
+            }
+        }
+    };
+}
+
+node!(
+    #[serde(rename_all = "camelCase")]
+    Doc, "doc", crate::Doc {
+        #[serde(borrow, deserialize_with = "labelled")]
+        references: IndexMap<Cow<'a, str>, Reference<'a>>,
+        #[serde(borrow, deserialize_with = "labelled")]
+        auto_references: IndexMap<Cow<'a, str>, Reference<'a>>,
+        #[serde(borrow, deserialize_with = "labelled")]
+        footnotes: IndexMap<Cow<'a, str>, Footnote<'a>>,
+        #[serde(borrow)]
+        children: Vec<Block<'a>>,
+    }
+);
+node!(Reference, "reference", crate::Reference {
+    #[serde(borrow)]
+    label: Cow<'a, str>,
+    #[serde(borrow)]
+    destination: Cow<'a, str>,
+});
+node!(Footnote, "footnote", crate::Footnote {
+    #[serde(borrow)]
+    label: Cow<'a, str>,
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+
+node_enum!(
+    Block,
+    block::Block,
+    Para,
+    Heading,
+    ThematicBreak,
+    Section,
+    Div,
+    CodeBlock,
+    RawBlock,
+    BlockQuote,
+    OrderedList,
+    BulletList,
+    TaskList,
+    DefinitionList,
+    Table,
+);
+
+node!(Para, "para", block::Para {
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+});
+node!(Heading, "heading", block::Heading {
+    level: u64,
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+});
+node!(ThematicBreak, "thematic_break", block::ThematicBreak {});
+node!(Section, "section", block::Section {
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+node!(Div, "div", block::Div {
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+node!(CodeBlock, "code_block", block::CodeBlock {
+    #[serde(borrow)]
+    text: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with = "optional")]
+    lang: Option<Cow<'a, str>>,
+});
+node!(RawBlock, "raw_block", block::RawBlock {
+    #[serde(borrow)]
+    format: Cow<'a, str>,
+    #[serde(borrow)]
+    text: Cow<'a, str>,
+});
+node!(BlockQuote, "block_quote", block::BlockQuote {
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+node!(OrderedList, "ordered_list", block::OrderedList {
+    style: OrderedListStyle,
+    #[serde(borrow)]
+    children: Vec<ListItem<'a>>,
+    start: Option<u64>,
+    tight: bool,
+});
+node!(BulletList, "bullet_list", block::BulletList {
+    tight: bool,
+    style: BulletListStyle,
+    #[serde(borrow)]
+    children: Vec<ListItem<'a>>,
+});
+node!(ListItem, "list_item", block::ListItem {
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+node!(TaskList, "task_list", block::TaskList {
+    tight: bool,
+    #[serde(borrow)]
+    children: Vec<TaskListItem<'a>>,
+});
+node!(TaskListItem, "task_list_item", block::TaskListItem {
+    checkbox: CheckboxStatus,
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+node!(DefinitionList, "definition_list", block::DefinitionList {
+    #[serde(borrow)]
+    children: Vec<DefinitionListItem<'a>>,
+});
+node!(Term, "term", block::Term {
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+});
+node!(Definition, "definition", block::Definition {
+    #[serde(borrow)]
+    children: Vec<Block<'a>>,
+});
+node!(Caption, "caption", block::Caption {
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+});
+node!(Row, "row", block::Row {
+    #[serde(borrow)]
+    children: Vec<Cell<'a>>,
+    head: bool,
+});
+node!(Cell, "cell", block::Cell {
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+    head: bool,
+    align: Alignment,
+});
+
+/// Mirrors [block::DefinitionListItem].
+#[derive(Debug, Clone)]
+pub struct DefinitionListItem<'a> {
+    pub term: Term<'a>,
+    pub definition: Definition<'a>,
+    pub meta: Meta<'a>,
+}
+
+impl DefinitionListItem<'_> {
+    pub fn into_owned(self) -> block::DefinitionListItem {
+        block::DefinitionListItem {
+            term: self.term.into_owned(),
+            definition: self.definition.into_owned(),
+            meta: self.meta.into_owned(),
+        }
+    }
+}
+
+impl IntoOwned for DefinitionListItem<'_> {
+    type Owned = block::DefinitionListItem;
+
+    fn into_owned(self) -> Self::Owned {
+        Self::into_owned(self)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for DefinitionListItem<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(tag = "tag", rename = "definition_list_item")]
+        struct DeserDefinitionListItem<'a> {
+            #[serde(borrow)]
+            children: Vec<TermOrDefinition<'a>>,
+            #[serde(flatten, borrow)]
+            meta: Meta<'a>,
+        }
+
+        enum TermOrDefinition<'a> {
+            Term(Term<'a>),
+            Definition(Definition<'a>),
+        }
+
+        deserialize_tagged!(TermOrDefinition, Term, Definition);
+
+        let child = |child, expected| {
+            de::Error::custom(Error::Child {
+                tag: "definition_list_item",
+                child,
+                expected,
+            })
+        };
+        let item = DeserDefinitionListItem::deserialize(deserializer)?;
+        let mut children = item.children.into_iter();
+        let Some(TermOrDefinition::Term(term)) = children.next() else {
+            return Err(child(0, "a term"));
+        };
+        let Some(TermOrDefinition::Definition(definition)) = children.next() else {
+            return Err(child(1, "a definition"));
+        };
+        if children.next().is_some() {
+            return Err(child(2, "absent"));
+        }
+        Ok(Self {
+            term,
+            definition,
+            meta: item.meta,
+        })
+    }
+}
+
+/// Mirrors [block::Table].
+#[derive(Debug, Clone)]
+pub struct Table<'a> {
+    pub caption: Caption<'a>,
+    pub rows: Vec<Row<'a>>,
+    pub meta: Meta<'a>,
+}
+
+impl Table<'_> {
+    pub fn into_owned(self) -> block::Table {
+        block::Table {
+            caption: self.caption.into_owned(),
+            rows: self.rows.into_owned(),
+            meta: self.meta.into_owned(),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Table<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(tag = "tag", rename = "table")]
+        struct DeserTable<'a> {
+            #[serde(borrow)]
+            children: Vec<CapOrRow<'a>>,
+            #[serde(flatten, borrow)]
+            meta: Meta<'a>,
+        }
+
+        enum CapOrRow<'a> {
+            Caption(Caption<'a>),
+            Row(Row<'a>),
+        }
+
+        deserialize_tagged!(CapOrRow, Caption, Row);
+
+        let table = DeserTable::deserialize(deserializer)?;
+        let mut children = table.children.into_iter();
+        let Some(CapOrRow::Caption(caption)) = children.next() else {
+            return Err(de::Error::custom(Error::Child {
+                tag: "table",
+                child: 0,
+                expected: "a caption",
+            }));
+        };
+        let rows = children
+            .enumerate()
+            .map(|(i, c)| match c {
+                CapOrRow::Row(row) => Ok(row),
+                CapOrRow::Caption(_) => Err(de::Error::custom(Error::Child {
+                    tag: "table",
+                    child: i + 1,
+                    expected: "a row",
+                })),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            caption,
+            rows,
+            meta: table.meta,
+        })
+    }
+}
+
+node_enum!(
+    Inline,
+    inline::Inline,
+    Str,
+    SoftBreak,
+    HardBreak,
+    NonBreakingSpace,
+    Symb,
+    Verbatim,
+    RawInline,
+    InlineMath,
+    DisplayMath,
+    Url,
+    Email,
+    FootnoteReference,
+    SmartPunctuation,
+    Emph,
+    Strong,
+    Link,
+    Image,
+    Span,
+    Mark,
+    Superscript,
+    Subscript,
+    Insert,
+    Delete,
+    DoubleQuoted,
+    SingleQuoted,
+);
+
+macro_rules! text_node {
+    ($($name:ident, $tag:literal);+ $(;)?) => {
+        $(node!($name, $tag, inline::$name {
+            #[serde(borrow)]
+            text: Cow<'a, str>,
+        });)+
+    };
+}
+
+macro_rules! container_node {
+    ($($name:ident, $tag:literal);+ $(;)?) => {
+        $(node!($name, $tag, inline::$name {
+            #[serde(borrow)]
+            children: Vec<Inline<'a>>,
+        });)+
+    };
+}
+
+text_node!(
+    Str, "str";
+    Verbatim, "verbatim";
+    InlineMath, "inline_math";
+    DisplayMath, "display_math";
+    Url, "url";
+    Email, "email";
+    FootnoteReference, "footnote_reference";
+);
+
+container_node!(
+    Emph, "emph";
+    Strong, "strong";
+    Span, "span";
+    Mark, "mark";
+    Superscript, "superscript";
+    Subscript, "subscript";
+    Insert, "insert";
+    Delete, "delete";
+    DoubleQuoted, "double_quoted";
+    SingleQuoted, "single_quoted";
+);
+
+node!(SoftBreak, "soft_break", inline::SoftBreak {});
+node!(HardBreak, "hard_break", inline::HardBreak {});
+node!(
+    NonBreakingSpace,
+    "non_breaking_space",
+    inline::NonBreakingSpace {}
+);
+node!(Symb, "symb", inline::Symb {
+    #[serde(borrow)]
+    alias: Cow<'a, str>,
+});
+node!(RawInline, "raw_inline", inline::RawInline {
+    #[serde(borrow)]
+    format: Cow<'a, str>,
+    #[serde(borrow)]
+    text: Cow<'a, str>,
+});
+node!(SmartPunctuation, "smart_punctuation", inline::SmartPunctuation {
+    #[serde(rename = "type")]
+    punc_type: SmartPunctuationType,
+    #[serde(borrow)]
+    text: Cow<'a, str>,
+});
+node!(Link, "link", inline::Link {
+    #[serde(borrow, default, deserialize_with = "optional")]
+    destination: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with = "optional")]
+    reference: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+});
+node!(Image, "image", inline::Image {
+    #[serde(borrow, default, deserialize_with = "optional")]
+    destination: Option<Cow<'a, str>>,
+    #[serde(borrow, default, deserialize_with = "optional")]
+    reference: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    children: Vec<Inline<'a>>,
+});
@@ -0,0 +1,258 @@
+//! Splitting a document's text into size-limited chunks, e.g. for retrieval indexes.
+use crate::{
+    attributes::{HasAttributes, Pos},
+    block::{Block, ListItem, TaskListItem},
+    text::{
+        block_text, definition_list_item_text, inline_text, list_item_text, row_text,
+        task_list_item_text,
+    },
+    Doc,
+};
+
+/// A piece of a document's plain text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Plain text of the chunk.
+    pub text: String,
+    /// Text of the headings enclosing the chunk, outermost first.
+    pub headings: Vec<String>,
+    /// Source range from the start of the chunk's first block to the end of its last,
+    /// if the blocks have positions.
+    pub pos: Option<Pos>,
+}
+
+/// Splits documents into chunks whose size does not exceed a budget.
+///
+/// Chunks never span a heading.
+/// Whole blocks are packed into chunks where possible;
+/// blocks which do not fit in the budget on their own are split into their list items,
+/// table rows or child blocks, and only then into lines, words and characters.
+/// Code blocks and tables are therefore kept whole whenever they fit.
+pub struct Chunker<'a> {
+    budget: usize,
+    measure: Box<dyn Fn(&str) -> usize + 'a>,
+}
+
+impl Chunker<'static> {
+    /// Create a chunker whose budget is a number of characters.
+    pub fn with_char_budget(budget: usize) -> Self {
+        Self::with_measure(budget, |s| s.chars().count())
+    }
+}
+
+impl<'a> Chunker<'a> {
+    /// Create a chunker which measures text with the given function, e.g. a tokenizer's token count.
+    ///
+    /// Text too large for one chunk is measured in pieces, so the measure of some text should be
+    /// no more than the sum of the measures of its lines or words.
+    pub fn with_measure(budget: usize, measure: impl Fn(&str) -> usize + 'a) -> Self {
+        Self {
+            budget,
+            measure: Box::new(measure),
+        }
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        (self.measure)(text) <= self.budget
+    }
+
+    /// Split the document's text into chunks.
+    pub fn chunk(&self, doc: &Doc) -> Vec<Chunk> {
+        let mut state = State::default();
+        self.walk(&doc.children, &mut state);
+        state.flush();
+        state.chunks
+    }
+
+    fn walk(&self, blocks: &[Block], state: &mut State) {
+        for block in blocks {
+            match block {
+                Block::Section(s) => self.walk(&s.children, state),
+                Block::Heading(h) => {
+                    state.flush();
+                    let text = inline_text(&h.children);
+                    state.headings.retain(|(l, _)| *l < h.level);
+                    state.headings.push((h.level, text.clone()));
+                    self.push(state, text, h.pos());
+                }
+                _ => self.push_block(state, block),
+            }
+        }
+    }
+
+    fn push_block(&self, state: &mut State, block: &Block) {
+        let text = block_text(block);
+        if text.is_empty() {
+            return;
+        }
+        if self.fits(&text) {
+            return self.push(state, text, block.pos());
+        }
+        match block {
+            Block::Div(i) => self.walk(&i.children, state),
+            Block::BlockQuote(i) => self.walk(&i.children, state),
+            Block::OrderedList(i) => self.push_list_items(state, &i.children),
+            Block::BulletList(i) => self.push_list_items(state, &i.children),
+            Block::TaskList(i) => {
+                for item in i.children.iter() {
+                    self.push_task_list_item(state, item);
+                }
+            }
+            Block::DefinitionList(i) => {
+                for item in i.children.iter() {
+                    let text = definition_list_item_text(item);
+                    if self.fits(&text) {
+                        self.push(state, text, item.pos().or(block.pos()));
+                    } else {
+                        let pos = item.term.pos().or(item.pos()).or(block.pos());
+                        self.push(state, inline_text(&item.term.children), pos);
+                        self.walk(&item.definition.children, state);
+                    }
+                }
+            }
+            Block::Table(i) => {
+                let pos = i.caption.pos().or(block.pos());
+                self.push(state, inline_text(&i.caption.children), pos);
+                for row in i.rows.iter() {
+                    self.push(state, row_text(row), row.pos().or(block.pos()));
+                }
+            }
+            _ => self.push(state, text, block.pos()),
+        }
+    }
+
+    fn push_list_items(&self, state: &mut State, items: &[ListItem]) {
+        for item in items {
+            let text = list_item_text(item);
+            if self.fits(&text) {
+                self.push(state, text, item.pos());
+            } else {
+                self.walk(&item.children, state);
+            }
+        }
+    }
+
+    fn push_task_list_item(&self, state: &mut State, item: &TaskListItem) {
+        let text = task_list_item_text(item);
+        if self.fits(&text) {
+            self.push(state, text, item.pos());
+        } else {
+            self.walk(&item.children, state);
+        }
+    }
+
+    /// Add a piece of text to the current chunk, starting a new chunk or splitting the text if necessary.
+    fn push(&self, state: &mut State, text: String, pos: Option<&Pos>) {
+        if text.is_empty() {
+            return;
+        }
+        if !state.text.is_empty() {
+            let candidate = format!("{}{SEPARATOR}{text}", state.text);
+            if self.fits(&candidate) {
+                state.text = candidate;
+                state.extend_pos(pos);
+                return;
+            }
+            state.flush();
+        }
+        if self.fits(&text) {
+            state.text = text;
+            state.extend_pos(pos);
+            return;
+        }
+        let mut pieces = Vec::new();
+        self.split_text(&text, SPLITTERS, &mut pieces);
+        for piece in pieces {
+            state.flush();
+            state.text = piece;
+            state.extend_pos(pos);
+        }
+    }
+
+    /// Greedily pack pieces of the text into strings which fit the budget,
+    /// using progressively finer splitters for pieces which are too large.
+    ///
+    /// Each piece is measured once, and the size of a string taken to be the sum of its pieces'.
+    fn split_text(&self, text: &str, splitters: &[Splitter], out: &mut Vec<String>) {
+        let Some((split, finer)) = splitters.split_first() else {
+            out.push(text.to_string());
+            return;
+        };
+        let mut current = String::new();
+        let mut current_len = 0;
+        for piece in split(text) {
+            let len = (self.measure)(piece);
+            if current_len + len <= self.budget {
+                current.push_str(piece);
+                current_len += len;
+                continue;
+            }
+            if !current.is_empty() {
+                out.push(std::mem::take(&mut current));
+            }
+            current_len = 0;
+            if len <= self.budget {
+                current.push_str(piece);
+                current_len = len;
+            } else {
+                self.split_text(piece, finer, out);
+            }
+        }
+        if !current.is_empty() {
+            out.push(current);
+        }
+    }
+}
+
+const SEPARATOR: &str = "\n\n";
+
+type Splitter = fn(&str) -> Vec<&str>;
+
+const SPLITTERS: &[Splitter] = &[
+    |s| s.split_inclusive('\n').collect(),
+    |s| s.split_inclusive(' ').collect(),
+    |s| {
+        s.char_indices()
+            .map(|(idx, c)| &s[idx..idx + c.len_utf8()])
+            .collect()
+    },
+];
+
+#[derive(Debug, Default)]
+struct State {
+    headings: Vec<(u64, String)>,
+    text: String,
+    pos: Option<Pos>,
+    chunks: Vec<Chunk>,
+}
+
+impl State {
+    fn extend_pos(&mut self, pos: Option<&Pos>) {
+        let Some(pos) = pos else {
+            return;
+        };
+        // blocks are not always in source order, as a table's caption follows its rows
+        match self.pos.as_mut() {
+            Some(p) => {
+                if pos.start.offset < p.start.offset {
+                    p.start = pos.start;
+                }
+                if pos.end.offset > p.end.offset {
+                    p.end = pos.end;
+                }
+            }
+            None => self.pos = Some(*pos),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        self.chunks.push(Chunk {
+            text: std::mem::take(&mut self.text),
+            headings: self.headings.iter().map(|(_, h)| h.clone()).collect(),
+            pos: self.pos.take(),
+        });
+    }
+}
@@ -0,0 +1,719 @@
+//! Deserializing with every error found, and where each one is.
+//!
+//! Deserializing [AstNode] stops at the first error, which serde reports without saying where in the tree it is.
+//! [from_deserializer] reads the input into memory, checks it against the [schema](crate::schema)
+//! to collect every error with the JSON pointer and tag of the node where it occurs,
+//! and only deserializes it if there are none.
+//!
+//! ```
+//! let json = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [
+//!     {"tag": "para", "children": [{"tag": "strr", "text": "typo"}]},
+//!     {"tag": "ordered_list", "style": "1]", "tight": true, "children": []}
+//! ]}"#;
+//! let mut de = serde_json::Deserializer::from_str(json);
+//! let errors = djot_ast::deserialize::from_deserializer(&mut de).unwrap_err();
+//! let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+//! assert_eq!(
+//!     errors,
+//!     [
+//!         "/children/0/children/0 (strr): unknown tag 'strr'",
+//!         "/children/1/style (ordered_list): invalid ordered list style '1]'",
+//!     ]
+//! );
+//! ```
+use std::{cell::Cell, fmt::Display};
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{
+    schema::{fields, find_def, union_nodes, Def, Shape, Type},
+    unknown::Value,
+    AstNode, Error,
+};
+
+/// An error found in deserializing, with where it is.
+#[derive(Debug)]
+pub struct DeserializeError {
+    /// A JSON pointer to the value with the error, e.g. `/children/3/style`.
+    pub pointer: String,
+    /// The tag of the node the value is, or is in, if any.
+    pub tag: Option<String>,
+    pub error: Error,
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.pointer)?;
+        if let Some(tag) = &self.tag {
+            if !self.pointer.is_empty() {
+                f.write_str(" ")?;
+            }
+            write!(f, "({tag})")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Deserialize a node, or collect every error in the input.
+///
+/// The errors of a node come before those of its fields and children.
+/// An input which cannot be read at all, such as malformed JSON, gives a single error at the root.
+pub fn from_deserializer<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<AstNode, Vec<DeserializeError>> {
+    let at_root = |error| {
+        vec![DeserializeError {
+            pointer: String::new(),
+            tag: None,
+            error,
+        }]
+    };
+    let value =
+        Value::deserialize(deserializer).map_err(|e| at_root(Error::general(e.to_string())))?;
+    let errors = check(&value);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    AstNode::deserialize(value).map_err(|e| at_root(Error::general(e.to_string())))
+}
+
+/// Collect every error which deserializing a node from the input would have.
+pub fn check_deserializer<'de, D: Deserializer<'de>>(deserializer: D) -> Vec<DeserializeError> {
+    match Value::deserialize(deserializer) {
+        Ok(value) => check(&value),
+        Err(e) => vec![DeserializeError {
+            pointer: String::new(),
+            tag: None,
+            error: Error::general(e.to_string()),
+        }],
+    }
+}
+
+fn check(value: &Value) -> Vec<DeserializeError> {
+    let mut checker = Checker {
+        pointer: String::new(),
+        tag: None,
+        errors: Vec::new(),
+    };
+    checker.check_def(value, find_def("AstNode"));
+    checker.errors
+}
+
+thread_local! {
+    static LENIENT: Cell<bool> = const { Cell::new(false) };
+    static LIMITS: Cell<Limits> = const { Cell::new(Limits::DEFAULT) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static NODES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Whether blocks and inlines with unknown tags are being deserialized as [Unknown](crate::unknown::Unknown) nodes.
+pub(crate) fn is_lenient() -> bool {
+    LENIENT.get()
+}
+
+/// Deserializes a node leniently,
+/// reading blocks and inlines with unknown tags as [Unknown](crate::unknown::Unknown) nodes rather than failing.
+///
+/// Like deserializing the node itself, this needs a self-describing format such as JSON,
+/// as a block or inline whose tag is not its first field is read into memory to find it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lenient<T>(pub T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Restores the previous mode, even if deserializing panics.
+        struct Restore(bool);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                LENIENT.set(self.0);
+            }
+        }
+
+        let _restore = Restore(LENIENT.replace(true));
+        T::deserialize(deserializer).map(Lenient)
+    }
+}
+
+/// Limits on the input accepted when deserializing, so that hostile input fails with an error
+/// rather than overflowing the stack or exhausting memory.
+///
+/// Deserializing recurses through the nesting of the input, as do the derived `Clone`, `PartialEq` and `Debug` of nodes,
+/// so the depth is limited even when not deserializing within [Limits::deserialize], by [Limits::DEFAULT].
+/// Dropping nodes and the iterators of [traverse](crate::traverse) do not recurse.
+/// A block or inline whose tag is not its first field is read into memory to find it, as is the input of [from_deserializer],
+/// so the depth also counts the objects read, such as nodes and their positions,
+/// and the arrays other than the values of fields.
+///
+/// ```
+/// use djot_ast::{deserialize::Limits, Doc};
+///
+/// let json = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [
+///     {"tag": "para", "children": [{"tag": "emph", "children": [{"tag": "str", "text": "hi"}]}]}
+/// ]}"#;
+/// let limits = Limits { max_depth: 2, ..Limits::DEFAULT };
+/// let mut de = serde_json::Deserializer::from_str(json);
+/// let err = limits.deserialize::<Doc, _>(&mut de).unwrap_err();
+/// assert!(err.to_string().starts_with("nodes nested more than 2 deep"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The deepest nesting allowed.
+    pub max_depth: usize,
+    /// The most blocks and inlines allowed.
+    pub max_nodes: usize,
+}
+
+impl Limits {
+    /// The limits when not deserializing within others: a depth of 256, and any number of nodes.
+    pub const DEFAULT: Self = Self {
+        max_depth: 256,
+        max_nodes: usize::MAX,
+    };
+
+    /// Deserialize within these limits.
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        /// Restores the previous limits and count, even if deserializing panics.
+        struct Restore(Limits, usize);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                LIMITS.set(self.0);
+                NODES.set(self.1);
+            }
+        }
+
+        let _restore = Restore(LIMITS.replace(self), NODES.replace(0));
+        T::deserialize(deserializer)
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Marks one more level of nesting being deserialized, until dropped.
+pub(crate) struct Nested(());
+
+impl Drop for Nested {
+    fn drop(&mut self) {
+        DEPTH.set(DEPTH.get() - 1);
+    }
+}
+
+fn nest<E: de::Error>(nodes: usize) -> Result<Nested, E> {
+    let limits = LIMITS.get();
+    let depth = DEPTH.get() + 1;
+    if depth > limits.max_depth {
+        return Err(E::custom(Error::TooDeep(limits.max_depth)));
+    }
+    let nodes = NODES.get().saturating_add(nodes);
+    if nodes > limits.max_nodes {
+        return Err(E::custom(Error::TooManyNodes(limits.max_nodes)));
+    }
+    DEPTH.set(depth);
+    NODES.set(nodes);
+    Ok(Nested(()))
+}
+
+/// Count a block or inline being deserialized within the current [Limits].
+pub(crate) fn nest_node<E: de::Error>() -> Result<Nested, E> {
+    nest(1)
+}
+
+/// Count an array or object being read into memory within the current [Limits].
+pub(crate) fn nest_value<E: de::Error>() -> Result<Nested, E> {
+    nest(0)
+}
+
+/// Walks a [Value], comparing it with the definitions of the schema.
+struct Checker {
+    pointer: String,
+    tag: Option<String>,
+    errors: Vec<DeserializeError>,
+}
+
+impl Checker {
+    fn error(&mut self, error: Error) {
+        self.errors.push(DeserializeError {
+            pointer: self.pointer.clone(),
+            tag: self.tag.clone(),
+            error,
+        });
+    }
+
+    /// Check a value one level down, under the given key or index.
+    fn within(&mut self, key: impl Display, f: impl FnOnce(&mut Self)) {
+        let len = self.pointer.len();
+        let key = key.to_string().replace('~', "~0").replace('/', "~1");
+        self.pointer.push('/');
+        self.pointer.push_str(&key);
+        f(self);
+        self.pointer.truncate(len);
+    }
+
+    fn check_type(&mut self, value: &Value, ty: &Type) {
+        let invalid_type = |expected| Error::InvalidType {
+            expected,
+            found: value.kind(),
+        };
+        match (*ty, value) {
+            (Type::Text, Value::String(_)) | (Type::Boolean, Value::Bool(_)) => {}
+            (Type::Integer { minimum }, Value::U64(n)) if *n >= minimum => {}
+            (Type::Integer { minimum }, Value::U64(_) | Value::I64(_)) => {
+                self.error(Error::InvalidValue {
+                    value: match value {
+                        Value::U64(n) => n.to_string(),
+                        Value::I64(n) => n.to_string(),
+                        _ => unreachable!(),
+                    },
+                    expected: format!("an integer of at least {minimum}"),
+                })
+            }
+            (Type::Text, _) => self.error(invalid_type("a string")),
+            (Type::Integer { .. }, _) => self.error(invalid_type("an integer")),
+            (Type::Boolean, _) => self.error(invalid_type("a boolean")),
+            (Type::Ref(name), _) => self.check_def(value, find_def(name)),
+            (Type::Array(item), Value::Array(items)) => {
+                for (i, value) in items.iter().enumerate() {
+                    self.within(i, |c| c.check_type(value, item));
+                }
+            }
+            (Type::Tuple(prefix, None), Value::Array(items)) => {
+                if items.len() != prefix.len() {
+                    self.error(Error::InvalidLength {
+                        expected: prefix.len(),
+                        found: items.len(),
+                    });
+                    return;
+                }
+                for (i, (value, ty)) in items.iter().zip(prefix).enumerate() {
+                    self.within(i, |c| c.check_type(value, ty));
+                }
+            }
+            (Type::Tuple(prefix, Some(rest)), Value::Array(items)) => {
+                self.check_table_children(items, prefix, rest)
+            }
+            (Type::Array(_) | Type::Tuple(..), _) => self.error(invalid_type("an array")),
+            (Type::Map(ty), Value::Object(entries)) => {
+                for (key, value) in entries {
+                    self.within(key, |c| c.check_type(value, ty));
+                }
+            }
+            (Type::Map(_), _) => self.error(invalid_type("an object")),
+            (Type::Nullable(_), Value::Null) => {}
+            (Type::Nullable(ty), _) => self.check_type(value, ty),
+        }
+    }
+
+    /// Check the children of a table, whose tags tell the caption from the rows.
+    fn check_table_children(&mut self, items: &[Value], prefix: &[Type], rest: &Type) {
+        let Some(first) = prefix.first() else {
+            unreachable!("a table starts with its caption")
+        };
+        let child = |child, expected| Error::Child {
+            tag: "table",
+            child,
+            expected,
+        };
+        if items.is_empty() {
+            self.error(child(0, "a caption"));
+        }
+        for (i, value) in items.iter().enumerate() {
+            let (ty, expected) = match i {
+                0 => (first, "a caption"),
+                _ => (rest, "a row"),
+            };
+            let &Type::Ref(name) = ty else {
+                unreachable!("the children of a table are nodes")
+            };
+            self.within(i, |c| match c.check_tag(value, &["Caption", "Row"]) {
+                Some(found) if found == name => c.check_def(value, find_def(name)),
+                Some(_) => c.error(child(i, expected)),
+                None => {}
+            });
+        }
+    }
+
+    /// Check that a value is a node of one of the given definitions,
+    /// returning the name of the definition its tag is that of.
+    fn check_tag(&mut self, value: &Value, names: &[&'static str]) -> Option<&'static str> {
+        let Value::Object(_) = value else {
+            self.error(Error::InvalidType {
+                expected: "an object",
+                found: value.kind(),
+            });
+            return None;
+        };
+        let tag = match value.get("tag") {
+            Some(Value::String(tag)) => tag,
+            Some(other) => {
+                self.within("tag", |c| {
+                    c.error(Error::InvalidType {
+                        expected: "a string",
+                        found: other.kind(),
+                    })
+                });
+                return None;
+            }
+            None => {
+                self.error(Error::MissingField("tag"));
+                return None;
+            }
+        };
+        let mut nodes = Vec::new();
+        union_nodes(names, &mut nodes);
+        let found = nodes.iter().find(|(t, _)| t == tag).map(|(_, name)| *name);
+        if found.is_none() {
+            let outer = self.tag.replace(tag.clone());
+            self.error(Error::UnknownTag(tag.clone()));
+            self.tag = outer;
+        }
+        found
+    }
+
+    fn check_def(&mut self, value: &Value, def: &Def) {
+        match def.shape {
+            Shape::Node { .. } | Shape::Object(_) => {
+                let Value::Object(_) = value else {
+                    self.error(Error::InvalidType {
+                        expected: "an object",
+                        found: value.kind(),
+                    });
+                    return;
+                };
+                // Where only one type of node can appear, deserializing ignores the tag.
+                let outer = match (&def.shape, value.get("tag")) {
+                    (Shape::Node { .. }, Some(Value::String(tag))) => self.tag.replace(tag.clone()),
+                    _ => self.tag.clone(),
+                };
+                for field in fields(&def.shape) {
+                    if field.required && value.get(field.name).is_none() {
+                        self.error(Error::MissingField(field.name));
+                    }
+                }
+                for field in fields(&def.shape) {
+                    if let Some(v) = value.get(field.name) {
+                        self.within(field.name, |c| c.check_type(v, &field.ty));
+                    }
+                }
+                self.tag = outer;
+            }
+            Shape::Union(names) => {
+                if let Some(name) = self.check_tag(value, names) {
+                    self.check_def(value, find_def(name));
+                }
+            }
+            Shape::Strings(values) => match value {
+                Value::String(s) if values.contains(&s.as_str()) => {}
+                Value::String(s) if def.name == "OrderedListStyle" => {
+                    self.error(Error::InvalidListStyle(s.clone()))
+                }
+                Value::String(s) => self.error(Error::InvalidValue {
+                    value: s.clone(),
+                    expected: format!("one of {}", values.join(", ")),
+                }),
+                _ => self.error(Error::InvalidType {
+                    expected: "a string",
+                    found: value.kind(),
+                }),
+            },
+            Shape::Alias(ty) => self.check_type(value, &ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Doc;
+
+    fn errors(value: serde_json::Value) -> Vec<String> {
+        match from_deserializer(value) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for json in [
+            include_str!("../tests/fixtures/readme.dj.json"),
+            include_str!("../tests/fixtures/pandoc-manual.json"),
+        ] {
+            let mut de = serde_json::Deserializer::from_str(json);
+            let node = from_deserializer(&mut de).unwrap();
+            assert_eq!(node, serde_json::from_str::<AstNode>(json).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let value = json!({
+            "tag": "doc",
+            "references": {"a/b": {"tag": "reference", "label": "a/b"}},
+            "autoReferences": {},
+            "children": [
+                {"tag": "heading", "level": -1, "children": [{"tag": "str", "text": 1}]},
+                {"tag": "table", "children": [
+                    {"tag": "caption", "children": []},
+                    {"tag": "caption", "children": []},
+                    {"tag": "cell", "children": []},
+                    {"tag": "row", "head": true, "children": [
+                        {"tag": "cell", "head": false, "align": "justify", "children": []}
+                    ]}
+                ]},
+                {"tag": "definition_list", "children": [
+                    {"tag": "definition_list_item", "children": [{"tag": "term", "children": []}]}
+                ]},
+                {"children": []},
+                "para"
+            ]
+        });
+        assert_eq!(
+            errors(value),
+            [
+                "(doc): missing field `footnotes`",
+                "/references/a~1b (reference): missing field `destination`",
+                "/children/0/level (heading): invalid value '-1': expected an integer of at least 0",
+                "/children/0/children/0/text (str): invalid type: expected a string, found an integer",
+                "/children/1/children/1 (table): child 1 of a `table` should be a row",
+                "/children/1/children/2 (cell): unknown tag 'cell'",
+                "/children/1/children/3/children/0/align (cell): invalid value 'justify': \
+                 expected one of default, left, right, center",
+                "/children/2/children/0/children (definition_list_item): expected 2 children, found 1",
+                "/children/3 (doc): missing field `tag`",
+                "/children/4 (doc): invalid type: expected an object, found a string",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut de = serde_json::Deserializer::from_str(r#"{"tag": "#);
+        let errors = from_deserializer(&mut de).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].pointer, "");
+    }
+
+    #[test]
+    fn test_serde_messages() {
+        let cell = json!({"tag": "cell", "head": false, "align": "default", "children": []});
+        let row = json!({"tag": "row", "head": false, "children": [cell]});
+        let caption = json!({"tag": "caption", "children": []});
+        let term = json!({"tag": "term", "children": []});
+        let definition = json!({"tag": "definition", "children": []});
+        for (value, message) in [
+            (
+                json!({"tag": "table", "children": [caption, caption]}),
+                "child 1 of a `table` should be a row",
+            ),
+            (
+                json!({"tag": "table", "children": [row]}),
+                "child 0 of a `table` should be a caption",
+            ),
+            (
+                json!({"tag": "definition_list_item", "children": [term]}),
+                "child 1 of a `definition_list_item` should be a definition",
+            ),
+            (
+                json!({"tag": "definition_list_item", "children": [definition, term]}),
+                "child 0 of a `definition_list_item` should be a term",
+            ),
+            (
+                json!({"tag": "definition_list_item", "children": [term, definition, term]}),
+                "child 2 of a `definition_list_item` should be absent",
+            ),
+        ] {
+            let json = value.to_string();
+            let err = serde_json::from_str::<AstNode>(&json).unwrap_err();
+            assert!(err.to_string().starts_with(message), "{err}");
+            let err = match value["tag"].as_str() {
+                Some("table") => serde_json::from_str::<crate::borrowed::Table>(&json).err(),
+                _ => serde_json::from_str::<crate::borrowed::DefinitionListItem>(&json).err(),
+            };
+            assert!(err.unwrap().to_string().starts_with(message), "{json}");
+        }
+
+        let node: AstNode = serde_json::from_value(caption).unwrap();
+        let err = crate::Block::try_from(node).unwrap_err();
+        assert_eq!(err.to_string(), "AstNode is not a Block but a `caption`");
+    }
+
+    #[test]
+    fn test_check_agrees() {
+        for (value, valid) in [
+            (json!({"tag": "str", "text": "a"}), true),
+            (json!({"tag": "str", "text": "a", "pos": null}), true),
+            (
+                json!({"tag": "ordered_list", "style": "(1.", "tight": true, "children": []}),
+                false,
+            ),
+            (json!({"tag": "table", "children": []}), false),
+            (json!({"tag": "str", "text": 1.0}), false),
+        ] {
+            let checked = check_deserializer(&value).is_empty();
+            let deserialized = AstNode::deserialize(&value).is_ok();
+            assert_eq!((checked, deserialized), (valid, valid), "{value}");
+        }
+    }
+
+    /// A doc of `depth` nested block quotes around a paragraph.
+    fn nested(depth: usize) -> serde_json::Value {
+        let mut block = json!({"tag": "para", "children": [{"tag": "str", "text": "deep"}]});
+        for _ in 1..depth {
+            // `json!` would copy the block, recursively
+            let mut quote = json!({"tag": "block_quote"});
+            quote["children"] = serde_json::Value::Array(vec![block]);
+            block = quote;
+        }
+        let mut doc =
+            json!({"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}});
+        doc["children"] = serde_json::Value::Array(vec![block]);
+        doc
+    }
+
+    /// Run on a thread with room to drop deeply nested JSON, which serde_json does recursively.
+    fn big_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+        std::thread::Builder::new()
+            .stack_size(1 << 30)
+            .spawn(f)
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_default_depth() {
+        let max = Limits::DEFAULT.max_depth;
+        // the paragraph's inline is one deeper
+        assert!(serde_json::from_value::<Doc>(nested(max - 1)).is_ok());
+        assert!(serde_json::from_value::<Lenient<Doc>>(nested(max - 1)).is_ok());
+        let deep = nested(100_000);
+        let err = Doc::deserialize(&deep).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+        let err = Lenient::<Doc>::deserialize(&deep).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+        let err = AstNode::deserialize(&deep).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+        let err = AstNode::deserialize(&deep["children"][0]).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+        let err = crate::borrowed::Doc::deserialize(&deep).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+        assert_eq!(DEPTH.get(), 0);
+        // `from_value` drops the part of the value it has not read
+        let err = big_stack(move || serde_json::from_value::<Doc>(deep).unwrap_err());
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+    }
+
+    #[test]
+    fn test_borrowed_depth() {
+        use crate::borrowed;
+
+        let max = Limits::DEFAULT.max_depth;
+        // serde_json's own limit stops deep input from a string, so read it from a value
+        let value = nested(max - 1);
+        let doc = borrowed::Doc::deserialize(&value).unwrap();
+        assert_eq!(doc.into_owned(), Doc::deserialize(&value).unwrap());
+        let deep = nested(100_000);
+        let err = Vec::<borrowed::Block>::deserialize(&deep["children"]).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(max).to_string());
+        assert_eq!(DEPTH.get(), 0);
+        big_stack(move || drop(deep));
+
+        // a node with its tag after other fields is read into memory
+        let json = r#"{"children": [{"text": "owned", "tag": "str"}], "tag": "para"}"#;
+        let block: borrowed::Block = serde_json::from_str(json).unwrap();
+        let borrowed::Block::Para(para) = &block else {
+            panic!("not a paragraph")
+        };
+        let [borrowed::Inline::Str(text)] = &para.children[..] else {
+            panic!("not a str")
+        };
+        assert!(matches!(text.text, std::borrow::Cow::Owned(_)));
+        assert_eq!(block.into_owned(), serde_json::from_str(json).unwrap());
+        let err = serde_json::from_str::<borrowed::Block>(r#"{"text": "a"}"#).unwrap_err();
+        assert!(err.to_string().starts_with("missing field `tag`"), "{err}");
+    }
+
+    #[test]
+    fn test_read_once() {
+        use crate::unknown::OBJECTS_READ;
+
+        /// The number of objects read into memory in deserializing a block.
+        fn objects_read<'de, D: Deserializer<'de>>(deserializer: D) -> usize {
+            OBJECTS_READ.set(0);
+            crate::Block::deserialize(deserializer).unwrap();
+            OBJECTS_READ.get()
+        }
+
+        // within serde_json's own limit on nesting
+        let depth = 50;
+        let mut json =
+            r#"{"tag": "para", "children": [{"tag": "str", "text": "deep"}]}"#.to_string();
+        for _ in 1..depth {
+            json = format!(r#"{{"tag": "block_quote", "children": [{json}]}}"#);
+        }
+        // nodes with their tags first are read as they are deserialized
+        assert_eq!(
+            objects_read(&mut serde_json::Deserializer::from_str(&json)),
+            0
+        );
+        // nodes with their tags last are read into memory once, with the outermost
+        let value = &nested(depth)["children"][0];
+        assert_eq!(
+            value.as_object().unwrap().keys().next_back().unwrap(),
+            "tag"
+        );
+        assert_eq!(objects_read(value), depth + 1);
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits {
+            max_depth: 10,
+            max_nodes: 100,
+        };
+        assert!(limits.deserialize::<Doc, _>(nested(9)).is_ok());
+        let err = limits.deserialize::<Doc, _>(nested(10)).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(10).to_string());
+        let deep = nested(100_000);
+        let err = limits.deserialize::<Doc, _>(&deep).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(10).to_string());
+        let err = limits
+            .deserialize::<crate::borrowed::Doc, _>(&deep)
+            .unwrap_err();
+        assert_eq!(err.to_string(), Error::TooDeep(10).to_string());
+        big_stack(move || drop(deep));
+
+        let para = json!({"tag": "para", "children": [{"tag": "str", "text": "wide"}]});
+        let doc = |n| {
+            json!({"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+                "children": vec![para.clone(); n]})
+        };
+        assert!(limits.deserialize::<Doc, _>(doc(50)).is_ok());
+        let err = limits.deserialize::<Doc, _>(doc(51)).unwrap_err();
+        assert_eq!(err.to_string(), Error::TooManyNodes(100).to_string());
+        let err = limits
+            .deserialize::<crate::borrowed::Doc, _>(&doc(51))
+            .unwrap_err();
+        assert_eq!(err.to_string(), Error::TooManyNodes(100).to_string());
+
+        // the limits only apply within `deserialize`
+        assert_eq!(LIMITS.get(), Limits::DEFAULT);
+        assert!(serde_json::from_value::<Doc>(doc(51)).is_ok());
+    }
+}
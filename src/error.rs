@@ -9,6 +9,37 @@ pub enum Error {
     General(String),
     #[error("wrapped error: {0}")]
     Wrapper(#[from] Box<dyn StdError>),
+    #[error("unknown tag '{0}'")]
+    UnknownTag(String),
+    #[error("missing field `{0}`")]
+    MissingField(&'static str),
+    #[error("invalid type: expected {expected}, found {found}")]
+    InvalidType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("invalid value '{value}': expected {expected}")]
+    InvalidValue { value: String, expected: String },
+    #[error("expected {expected} children, found {found}")]
+    InvalidLength { expected: usize, found: usize },
+    #[error("invalid ordered list style '{0}'")]
+    InvalidListStyle(String),
+    #[error("child {child} of a `{tag}` should be {expected}")]
+    Child {
+        tag: &'static str,
+        child: usize,
+        expected: &'static str,
+    },
+    #[error("nodes nested more than {0} deep")]
+    TooDeep(usize),
+    #[error("more than {0} nodes")]
+    TooManyNodes(usize),
+    #[error("{enum_name} is not {expected} but a `{found}`")]
+    WrongVariant {
+        enum_name: &'static str,
+        expected: &'static str,
+        found: String,
+    },
 }
 
 impl Error {
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+
+use crate::{block::Block, inline::Inline, path::NodePath, traverse::NodeRef, Doc, Error, Result};
+
+/// Labels of the references and footnotes used by some part of a document.
+#[derive(Debug, Default)]
+pub(crate) struct UsedLabels {
+    pub references: HashSet<String>,
+    pub footnotes: HashSet<String>,
+}
+
+impl UsedLabels {
+    /// Collect the labels used by the given nodes,
+    /// following footnotes defined in `doc` which may themselves use further labels.
+    pub fn collect<'a>(doc: &'a Doc, roots: impl IntoIterator<Item = NodeRef<'a>>) -> Self {
+        let mut used = Self::default();
+        let mut stack: Vec<NodeRef<'a>> = roots.into_iter().collect();
+
+        while let Some(root) = stack.pop() {
+            for node in root.descendants() {
+                let NodeRef::Inline(inline) = node else {
+                    continue;
+                };
+                match inline {
+                    Inline::Link(l) => used.references.extend(l.reference.clone()),
+                    Inline::Image(i) => used.references.extend(i.reference.clone()),
+                    Inline::FootnoteReference(f) if used.footnotes.insert(f.text.clone()) => {
+                        if let Some(footnote) = doc.footnotes.get(&f.text) {
+                            stack.push(NodeRef::Footnote(footnote));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        used
+    }
+
+    /// Create an empty document containing copies of the used references and footnotes from `doc`.
+    pub fn to_doc(&self, doc: &Doc) -> Doc {
+        Doc {
+            references: filter_labels(&doc.references, &self.references),
+            auto_references: filter_labels(&doc.auto_references, &self.references),
+            footnotes: filter_labels(&doc.footnotes, &self.footnotes),
+            ..Default::default()
+        }
+    }
+}
+
+fn filter_labels<T: Clone>(
+    map: &IndexMap<String, T>,
+    labels: &HashSet<String>,
+) -> IndexMap<String, T> {
+    map.iter()
+        .filter(|(k, _)| labels.contains(*k))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+impl Doc {
+    /// Copy the block at `path` into a new, self-contained document.
+    ///
+    /// The new document contains exactly those references and footnotes used by the block,
+    /// including any used by those footnotes.
+    pub fn extract(&self, path: &NodePath) -> Result<Doc> {
+        let Some(NodeRef::Block(block)) = self.get(path) else {
+            return Err(Error::General(format!(
+                "path '{path}' does not point to a block"
+            )));
+        };
+        let mut out = UsedLabels::collect(self, [NodeRef::Block(block)]).to_doc(self);
+        out.children.push(Block::clone(block));
+        Ok(out)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_follows_footnotes() {
+        let s = r#"{
+            "tag": "doc",
+            "references": {
+                "used": {"tag": "reference", "label": "used", "destination": "a"},
+                "unused": {"tag": "reference", "label": "unused", "destination": "b"}
+            },
+            "autoReferences": {},
+            "footnotes": {
+                "note": {"tag": "footnote", "label": "note", "children": [
+                    {"tag": "para", "children": [
+                        {"tag": "link", "reference": "used", "children": []}
+                    ]}
+                ]},
+                "other": {"tag": "footnote", "label": "other", "children": []}
+            },
+            "children": [
+                {"tag": "para", "children": [{"tag": "str", "text": "nothing"}]},
+                {"tag": "block_quote", "children": [
+                    {"tag": "para", "children": [
+                        {"tag": "footnote_reference", "text": "note"}
+                    ]}
+                ]}
+            ]
+        }"#;
+        let doc: Doc = serde_json::from_str(s).unwrap();
+
+        let out = doc.extract(&vec![1].into()).unwrap();
+        assert_eq!(out.children, vec![doc.children[1].clone()]);
+        assert_eq!(
+            out.references.keys().collect::<Vec<_>>(),
+            vec![&"used".to_string()]
+        );
+        assert_eq!(
+            out.footnotes.keys().collect::<Vec<_>>(),
+            vec![&"note".to_string()]
+        );
+
+        let out = doc.extract(&vec![0].into()).unwrap();
+        assert!(out.references.is_empty());
+        assert!(out.footnotes.is_empty());
+
+        assert!(doc.extract(&vec![1, 0, 0].into()).is_err());
+        assert!(doc.extract(&"/children/2".parse().unwrap()).is_err());
+    }
+}
@@ -2,42 +2,59 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    macros::{atom, from_into_variants, impl_hasmeta, inline_container, text_container},
+    macros::{
+        atom, deserialize_variants, drop_children, from_into_variants, impl_hasmeta,
+        inline_container, serialize_variants, text_container,
+    },
+    unknown::Unknown,
     HasMeta, Meta, Node, NodeType,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+// The variants contain this enum again, so their bounds are omitted and the ones they need are given here.
 #[cfg_attr(
-    feature = "serde",
-    derive(Serialize, Deserialize),
-    serde(tag = "tag", rename_all = "snake_case")
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(
+        serialize_bounds(
+            __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+            __S::Error: rkyv::rancor::Source,
+        ),
+        deserialize_bounds(__D::Error: rkyv::rancor::Source),
+        bytecheck(bounds(
+            __C: rkyv::validation::ArchiveContext,
+            __C::Error: rkyv::rancor::Source,
+        )),
+    )
 )]
 pub enum Inline {
-    Str(Str),
-    SoftBreak(SoftBreak),
-    HardBreak(HardBreak),
-    NonBreakingSpace(NonBreakingSpace),
-    Symb(Symb),
-    Verbatim(Verbatim),
-    RawInline(RawInline),
-    InlineMath(InlineMath),
-    DisplayMath(DisplayMath),
-    Url(Url),
-    Email(Email),
-    FootnoteReference(FootnoteReference),
-    SmartPunctuation(SmartPunctuation),
-    Emph(Emph),
-    Strong(Strong),
-    Link(Link),
-    Image(Image),
-    Span(Span),
-    Mark(Mark),
-    Superscript(Superscript),
-    Subscript(Subscript),
-    Insert(Insert),
-    Delete(Delete),
-    DoubleQuoted(DoubleQuoted),
-    SingleQuoted(SingleQuoted),
+    Str(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Str),
+    SoftBreak(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] SoftBreak),
+    HardBreak(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] HardBreak),
+    NonBreakingSpace(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] NonBreakingSpace),
+    Symb(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Symb),
+    Verbatim(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Verbatim),
+    RawInline(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] RawInline),
+    InlineMath(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] InlineMath),
+    DisplayMath(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] DisplayMath),
+    Url(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Url),
+    Email(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Email),
+    FootnoteReference(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] FootnoteReference),
+    SmartPunctuation(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] SmartPunctuation),
+    Emph(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Emph),
+    Strong(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Strong),
+    Link(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Link),
+    Image(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Image),
+    Span(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Span),
+    Mark(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Mark),
+    Superscript(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Superscript),
+    Subscript(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Subscript),
+    Insert(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Insert),
+    Delete(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Delete),
+    DoubleQuoted(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] DoubleQuoted),
+    SingleQuoted(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] SingleQuoted),
+    /// A node with a tag this crate does not know, read when deserializing [leniently](crate::deserialize::Lenient).
+    Unknown(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Unknown),
 }
 
 impl HasMeta for Inline {
@@ -68,6 +85,7 @@ impl HasMeta for Inline {
             Inline::Delete(i) => i.meta(),
             Inline::DoubleQuoted(i) => i.meta(),
             Inline::SingleQuoted(i) => i.meta(),
+            Inline::Unknown(i) => i.meta(),
         }
     }
 
@@ -98,6 +116,7 @@ impl HasMeta for Inline {
             Inline::Delete(i) => i.meta_mut(),
             Inline::DoubleQuoted(i) => i.meta_mut(),
             Inline::SingleQuoted(i) => i.meta_mut(),
+            Inline::Unknown(i) => i.meta_mut(),
         }
     }
 }
@@ -130,11 +149,116 @@ impl Node for Inline {
             Inline::Delete(i) => i.node_type(),
             Inline::DoubleQuoted(i) => i.node_type(),
             Inline::SingleQuoted(i) => i.node_type(),
+            Inline::Unknown(i) => i.node_type(),
+        }
+    }
+}
+
+impl Inline {
+    /// Get the children of container nodes, or `None` for leaf nodes.
+    pub fn children(&self) -> Option<&Vec<Inline>> {
+        use Inline::*;
+        match self {
+            Emph(i) => Some(&i.children),
+            Strong(i) => Some(&i.children),
+            Link(i) => Some(&i.children),
+            Image(i) => Some(&i.children),
+            Span(i) => Some(&i.children),
+            Mark(i) => Some(&i.children),
+            Superscript(i) => Some(&i.children),
+            Subscript(i) => Some(&i.children),
+            Insert(i) => Some(&i.children),
+            Delete(i) => Some(&i.children),
+            DoubleQuoted(i) => Some(&i.children),
+            SingleQuoted(i) => Some(&i.children),
+            Str(_) | SoftBreak(_) | HardBreak(_) | NonBreakingSpace(_) | Symb(_) | Verbatim(_)
+            | RawInline(_) | InlineMath(_) | DisplayMath(_) | Url(_) | Email(_)
+            | FootnoteReference(_) | SmartPunctuation(_) | Unknown(_) => None,
+        }
+    }
+
+    /// Get the children of container nodes mutably, or `None` for leaf nodes.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Inline>> {
+        use Inline::*;
+        match self {
+            Emph(i) => Some(&mut i.children),
+            Strong(i) => Some(&mut i.children),
+            Link(i) => Some(&mut i.children),
+            Image(i) => Some(&mut i.children),
+            Span(i) => Some(&mut i.children),
+            Mark(i) => Some(&mut i.children),
+            Superscript(i) => Some(&mut i.children),
+            Subscript(i) => Some(&mut i.children),
+            Insert(i) => Some(&mut i.children),
+            Delete(i) => Some(&mut i.children),
+            DoubleQuoted(i) => Some(&mut i.children),
+            SingleQuoted(i) => Some(&mut i.children),
+            Str(_) | SoftBreak(_) | HardBreak(_) | NonBreakingSpace(_) | Symb(_) | Verbatim(_)
+            | RawInline(_) | InlineMath(_) | DisplayMath(_) | Url(_) | Email(_)
+            | FootnoteReference(_) | SmartPunctuation(_) | Unknown(_) => None,
         }
     }
 }
 
 from_into_variants!(
+    Inline,
+    Str,
+    SoftBreak,
+    HardBreak,
+    NonBreakingSpace,
+    Symb,
+    Verbatim,
+    RawInline,
+    InlineMath,
+    DisplayMath,
+    Url,
+    Email,
+    FootnoteReference,
+    SmartPunctuation,
+    Emph,
+    Strong,
+    Link,
+    Image,
+    Span,
+    Mark,
+    Superscript,
+    Subscript,
+    Insert,
+    Delete,
+    DoubleQuoted,
+    SingleQuoted,
+    Unknown
+);
+serialize_variants!(
+    Inline,
+    Str,
+    SoftBreak,
+    HardBreak,
+    NonBreakingSpace,
+    Symb,
+    Verbatim,
+    RawInline,
+    InlineMath,
+    DisplayMath,
+    Url,
+    Email,
+    FootnoteReference,
+    SmartPunctuation,
+    Emph,
+    Strong,
+    Link,
+    Image,
+    Span,
+    Mark,
+    Superscript,
+    Subscript,
+    Insert,
+    Delete,
+    DoubleQuoted,
+    SingleQuoted,
+    Unknown
+);
+deserialize_variants!(
     Inline,
     Str,
     SoftBreak,
@@ -181,6 +305,11 @@ text_container!(Email, "email");
     derive(Serialize, Deserialize),
     serde(rename_all = "snake_case")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy, PartialEq, Eq))
+)]
 pub enum SmartPunctuationType {
     LeftSingleQuote,
     RightSingleQuote,
@@ -197,12 +326,16 @@ pub enum SmartPunctuationType {
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "smart_punctuation")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct SmartPunctuation {
     #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub punc_type: SmartPunctuationType,
     pub text: String,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for SmartPunctuation {
     fn node_type(&self) -> crate::NodeType {
@@ -217,10 +350,14 @@ impl_hasmeta!(SmartPunctuation);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "symb")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Symb {
     pub alias: String,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Symb {
     fn node_type(&self) -> crate::NodeType {
@@ -235,11 +372,15 @@ impl_hasmeta!(Symb);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "raw_inline")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct RawInline {
     pub format: String,
     pub text: String,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for RawInline {
     fn node_type(&self) -> crate::NodeType {
@@ -254,12 +395,18 @@ impl_hasmeta!(RawInline);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "link")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Link {
-    destination: Option<String>,
-    reference: Option<String>,
-    children: Vec<Inline>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub destination: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub reference: Option<String>,
+    pub children: Vec<Inline>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Link {
     fn node_type(&self) -> crate::NodeType {
@@ -267,6 +414,7 @@ impl Node for Link {
     }
 }
 impl_hasmeta!(Link);
+drop_children!(Link, drop_inlines);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
@@ -274,12 +422,18 @@ impl_hasmeta!(Link);
     derive(Serialize, Deserialize),
     serde(tag = "tag", rename = "image")
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Image {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub destination: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub reference: Option<String>,
     pub children: Vec<Inline>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Image {
     fn node_type(&self) -> crate::NodeType {
@@ -288,6 +442,7 @@ impl Node for Image {
 }
 
 impl_hasmeta!(Image);
+drop_children!(Image, drop_inlines);
 
 inline_container!(Emph, "emph");
 inline_container!(Strong, "strong");
@@ -1,13 +1,15 @@
 use attributes::{HasMeta, Meta};
+use indexmap::IndexMap;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use traverse::{Node, NodeType};
 
 mod error;
 pub use error::{Error, Result};
+/// Re-exported for the maps of [Doc] and [attributes::Attributes], which keep their entries in order.
+pub use indexmap;
 mod macros;
-use macros::{from_into_variants, impl_hasmeta};
+use macros::{from_into_variants, impl_hasmeta, serialize_variants};
 
 pub mod block;
 use block::{
@@ -20,13 +22,65 @@ pub mod attributes;
 
 pub mod traverse;
 
+pub mod path;
+
+pub mod locate;
+
+pub mod source;
+
+pub mod validate;
+
+mod extract;
+
+pub mod split;
+
+pub mod text;
+
+pub mod chunk;
+
+pub mod replace;
+
+pub mod normalize;
+
+pub mod query;
+
+pub mod arena;
+
+pub mod unknown;
+
+#[cfg(feature = "serde")]
+pub mod borrowed;
+
+#[cfg(feature = "serde")]
+pub mod binary;
+
+#[cfg(feature = "serde")]
+pub mod schema;
+
+#[cfg(feature = "serde")]
+pub mod deserialize;
+
+#[cfg(feature = "serde")]
+pub mod migrate;
+
+#[cfg(feature = "rkyv")]
+pub mod archive;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "tag"))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "tag", rename = "reference")
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Reference {
     pub label: String,
     pub destination: String,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Reference {
     fn node_type(&self) -> NodeType {
@@ -36,12 +90,20 @@ impl Node for Reference {
 impl_hasmeta!(Reference);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "tag"))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "tag", rename = "footnote")
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Footnote {
     pub label: String,
     pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Footnote {
     fn node_type(&self) -> NodeType {
@@ -54,15 +116,19 @@ impl_hasmeta!(Footnote);
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
-    serde(rename_all = "camelCase", tag = "tag")
+    serde(rename_all = "camelCase", tag = "tag", rename = "doc")
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
 )]
 pub struct Doc {
-    pub references: HashMap<String, Reference>,
-    pub auto_references: HashMap<String, Reference>,
-    pub footnotes: HashMap<String, Footnote>,
+    pub references: IndexMap<String, Reference>,
+    pub auto_references: IndexMap<String, Reference>,
+    pub footnotes: IndexMap<String, Footnote>,
     pub children: Vec<Block>,
     #[cfg_attr(feature = "serde", serde(flatten))]
-    meta: Meta,
+    pub(crate) meta: Meta,
 }
 impl Node for Doc {
     fn node_type(&self) -> NodeType {
@@ -72,11 +138,108 @@ impl Node for Doc {
 
 impl_hasmeta!(Doc);
 
+/// The descendants of a node being dropped, moved out of it so that dropping them does not recurse.
+///
+/// The nodes whose children may contain nodes like themselves drop their children with [drop_blocks] or [drop_inlines],
+/// which move the children of each descendant out before dropping it,
+/// so the usual drop of a node never goes more than a few levels deep, however deeply the tree is nested.
+#[derive(Default)]
+struct Orphans {
+    blocks: Vec<Block>,
+    inlines: Vec<Inline>,
+}
+
+/// Move children onto a stack, taking their allocation when the stack is empty.
+fn adopt<T>(stack: &mut Vec<T>, children: &mut Vec<T>) {
+    if stack.is_empty() {
+        std::mem::swap(stack, children);
+    } else {
+        stack.append(children);
+    }
+}
+
+impl Orphans {
+    fn adopt_block(&mut self, block: &mut Block) {
+        match block {
+            Block::Para(b) => adopt(&mut self.inlines, &mut b.children),
+            Block::Heading(b) => adopt(&mut self.inlines, &mut b.children),
+            Block::Section(b) => adopt(&mut self.blocks, &mut b.children),
+            Block::Div(b) => adopt(&mut self.blocks, &mut b.children),
+            Block::BlockQuote(b) => adopt(&mut self.blocks, &mut b.children),
+            Block::OrderedList(b) => {
+                for item in &mut b.children {
+                    adopt(&mut self.blocks, &mut item.children);
+                }
+            }
+            Block::BulletList(b) => {
+                for item in &mut b.children {
+                    adopt(&mut self.blocks, &mut item.children);
+                }
+            }
+            Block::TaskList(b) => {
+                for item in &mut b.children {
+                    adopt(&mut self.blocks, &mut item.children);
+                }
+            }
+            Block::DefinitionList(b) => {
+                for item in &mut b.children {
+                    adopt(&mut self.inlines, &mut item.term.children);
+                    adopt(&mut self.blocks, &mut item.definition.children);
+                }
+            }
+            Block::Table(b) => {
+                adopt(&mut self.inlines, &mut b.caption.children);
+                for row in &mut b.rows {
+                    for cell in &mut row.children {
+                        adopt(&mut self.inlines, &mut cell.children);
+                    }
+                }
+            }
+            Block::ThematicBreak(_)
+            | Block::CodeBlock(_)
+            | Block::RawBlock(_)
+            | Block::Unknown(_) => (),
+        }
+    }
+
+    /// Drop the descendants, moving the children of each out before dropping it.
+    fn drop_all(mut self) {
+        loop {
+            if let Some(mut block) = self.blocks.pop() {
+                self.adopt_block(&mut block);
+            } else if let Some(mut inline) = self.inlines.pop() {
+                if let Some(children) = inline.children_mut() {
+                    adopt(&mut self.inlines, children);
+                }
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Drop the children of a node iteratively.
+pub(crate) fn drop_blocks(children: &mut Vec<Block>) {
+    if !children.is_empty() {
+        let mut orphans = Orphans::default();
+        adopt(&mut orphans.blocks, children);
+        orphans.drop_all();
+    }
+}
+
+/// Drop the children of a node iteratively.
+pub(crate) fn drop_inlines(children: &mut Vec<Inline>) {
+    if !children.is_empty() {
+        let mut orphans = Orphans::default();
+        adopt(&mut orphans.inlines, children);
+        orphans.drop_all();
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
-    feature = "serde",
-    derive(Serialize, Deserialize),
-    serde(tag = "tag", rename_all = "snake_case")
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
 )]
 pub enum AstNode {
     Doc(Doc),
@@ -171,8 +334,130 @@ from_into_variants!(
     Footnote,
     Reference
 );
+serialize_variants!(
+    AstNode,
+    Doc,
+    Block,
+    Inline,
+    ListItem,
+    TaskListItem,
+    DefinitionListItem,
+    Term,
+    Definition,
+    Row,
+    Cell,
+    Caption,
+    Footnote,
+    Reference
+);
+
+/// Deserialize [AstNode] by the tag of any node.
+///
+/// Blocks and inlines have no tag of their own, so the derived impl would expect `"block"` and `"inline"`.
+#[cfg(feature = "serde")]
+macro_rules! deserialize_ast_node {
+    ($($wrapper:ident: $($variant:ident),+;)+) => {
+        mod deser_ast_node {
+            use serde::Deserialize;
+
+            use crate::{
+                block::*, inline::*, macros::deserialize_variants, AstNode, Doc, Footnote, Reference,
+            };
+
+            pub(super) enum DeserAstNode {
+                $($($variant($variant),)+)+
+            }
+            deserialize_variants!(strict DeserAstNode, $($($variant),+),+);
+
+            impl From<DeserAstNode> for AstNode {
+                fn from(value: DeserAstNode) -> Self {
+                    match value {
+                        $($(DeserAstNode::$variant(n) => $wrapper::from(n).into(),)+)+
+                    }
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for AstNode {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deser_ast_node::DeserAstNode::deserialize(deserializer).map(Into::into)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+deserialize_ast_node!(
+    AstNode: Doc;
+    Block: Para, Heading, ThematicBreak, Section, Div, CodeBlock, RawBlock, BlockQuote, OrderedList,
+        BulletList, TaskList, DefinitionList, Table;
+    Inline: Str, SoftBreak, HardBreak, NonBreakingSpace, Symb, Verbatim, RawInline, InlineMath,
+        DisplayMath, Url, Email, FootnoteReference, SmartPunctuation, Emph, Strong, Link, Image, Span,
+        Mark, Superscript, Subscript, Insert, Delete, DoubleQuoted, SingleQuoted;
+    AstNode: ListItem, TaskListItem, DefinitionListItem, Term, Definition, Row, Cell, Caption,
+        Footnote, Reference;
+);
 
 /// Create a document root node.
 pub fn new_document() -> AstNode {
     Doc::default().into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use block::{BlockQuote, Para};
+    use inline::{Emph, Str};
+
+    /// A block and an inline each nested `depth` deep.
+    fn deep(depth: usize) -> (Block, Inline) {
+        let str = || {
+            Inline::Str(Str {
+                text: "deep".to_string(),
+                meta: Meta::default(),
+            })
+        };
+        let mut block = Block::Para(Para {
+            children: vec![str()],
+            meta: Meta::default(),
+        });
+        let mut inline = str();
+        for _ in 0..depth {
+            block = Block::BlockQuote(BlockQuote {
+                children: vec![block],
+                meta: Meta::default(),
+            });
+            inline = Inline::Emph(Emph {
+                children: vec![inline],
+                meta: Meta::default(),
+            });
+        }
+        (block, inline)
+    }
+
+    #[test]
+    fn test_drop_deep() {
+        let (block, inline) = deep(100_000);
+        drop(block);
+        drop(inline);
+
+        let (block, inline) = deep(100_000);
+        let mut doc = Doc::default();
+        doc.children.push(block);
+        doc.footnotes.insert(
+            "1".to_string(),
+            Footnote {
+                label: "1".to_string(),
+                children: vec![Block::Para(Para {
+                    children: vec![inline],
+                    meta: Meta::default(),
+                })],
+                meta: Meta::default(),
+            },
+        );
+        drop(doc);
+    }
+}
@@ -0,0 +1,199 @@
+//! Finding nodes by their source position.
+//!
+//! Offsets here are in the same units as [SourceLoc::offset](crate::attributes::SourceLoc::offset),
+//! and ranges include their end offset, as in djot.js positions.
+//! Note that djot.js often ends a block at the offset where the next block starts;
+//! an offset shared by two siblings is attributed to the later one.
+use crate::{
+    attributes::{HasAttributes, SourceLoc},
+    path::NodePath,
+    traverse::NodeRef,
+    Doc,
+};
+
+/// An index of the source ranges of a document's nodes, for repeated position queries.
+///
+/// Building the index visits every node once;
+/// each query then descends the tree by binary search.
+#[derive(Debug, Clone)]
+pub struct PosIndex {
+    nodes: Vec<IndexNode>,
+    roots: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexNode {
+    path: NodePath,
+    /// The node's own position, if any.
+    pos: Option<(u64, u64)>,
+    /// The range covered by the node and its descendants.
+    span: Option<(u64, u64)>,
+    /// The greatest end of this node's span and those of its earlier siblings.
+    max_end: u64,
+    children: Vec<usize>,
+}
+
+fn union(a: Option<(u64, u64)>, b: Option<(u64, u64)>) -> Option<(u64, u64)> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.max(b.1))),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+fn own_pos(node: NodeRef) -> Option<(u64, u64)> {
+    node.meta().pos().map(|p| (p.start.offset, p.end.offset))
+}
+
+impl PosIndex {
+    /// Index the positions of all nodes in the document, including footnotes.
+    pub fn new(doc: &Doc) -> Self {
+        let mut nodes = Vec::new();
+        let mut roots = Vec::new();
+        let footnotes = doc.footnotes.iter().map(|(label, f)| {
+            let path = NodePath {
+                root: crate::path::PathRoot::Footnote(label.clone()),
+                children: Vec::new(),
+            };
+            (NodeRef::Footnote(f), path)
+        });
+        let doc_children = doc
+            .children
+            .iter()
+            .enumerate()
+            .map(|(idx, b)| (NodeRef::Block(b), NodePath::root().child(idx)));
+
+        let mut stack: Vec<(NodeRef, NodePath, Option<usize>)> = doc_children
+            .chain(footnotes)
+            .map(|(n, p)| (n, p, None))
+            .collect();
+        stack.reverse();
+        while let Some((node, path, parent)) = stack.pop() {
+            let idx = nodes.len();
+            let pos = own_pos(node);
+            for (child_idx, child) in node.children().into_iter().enumerate().rev() {
+                stack.push((child, path.child(child_idx), Some(idx)));
+            }
+            nodes.push(IndexNode {
+                path,
+                pos,
+                span: pos,
+                max_end: 0,
+                children: Vec::new(),
+            });
+            match parent {
+                Some(p) => nodes[p].children.push(idx),
+                None => roots.push(idx),
+            }
+        }
+
+        // children always come after their parents
+        for idx in (0..nodes.len()).rev() {
+            let span = nodes[idx]
+                .children
+                .iter()
+                .fold(nodes[idx].pos, |acc, c| union(acc, nodes[*c].span));
+            nodes[idx].span = span;
+        }
+        let key = |nodes: &[IndexNode], i: &usize| nodes[*i].span.map_or(u64::MAX, |s| s.0);
+        let mut lists: Vec<Vec<usize>> = nodes
+            .iter_mut()
+            .map(|n| std::mem::take(&mut n.children))
+            .collect();
+        lists.push(roots);
+        for list in lists.iter_mut() {
+            list.sort_by_key(|c| key(&nodes, c));
+            let mut max_end = 0;
+            for c in list.iter() {
+                max_end = max_end.max(nodes[*c].span.map_or(0, |s| s.1));
+                nodes[*c].max_end = max_end;
+            }
+        }
+        let roots = lists.pop().unwrap();
+        for (node, children) in nodes.iter_mut().zip(lists) {
+            node.children = children;
+        }
+
+        Self { nodes, roots }
+    }
+
+    /// Find the last node among sibling `candidates` whose span contains the offset.
+    ///
+    /// djot.js sections start after their own heading, so siblings' spans can overlap slightly;
+    /// the search stops as soon as no earlier sibling reaches the offset.
+    fn containing(&self, candidates: &[usize], offset: u64) -> Option<usize> {
+        let after =
+            candidates.partition_point(|c| self.nodes[*c].span.is_some_and(|s| s.0 <= offset));
+        candidates[..after]
+            .iter()
+            .rev()
+            .take_while(|c| self.nodes[**c].max_end >= offset)
+            .find(|c| self.nodes[**c].span.is_some_and(|s| s.1 >= offset))
+            .copied()
+    }
+
+    /// Get the path of the innermost node whose position contains the offset.
+    pub fn node_at(&self, offset: u64) -> Option<&NodePath> {
+        let mut found = None;
+        let mut candidates = &self.roots;
+        while let Some(idx) = self.containing(candidates, offset) {
+            let node = &self.nodes[idx];
+            if node.pos.is_some_and(|p| p.0 <= offset && offset <= p.1) {
+                found = Some(&node.path);
+            }
+            candidates = &node.children;
+        }
+        found
+    }
+
+    /// Get the path of the innermost node whose position contains the location.
+    ///
+    /// Only the location's offset is used.
+    pub fn node_at_loc(&self, loc: &SourceLoc) -> Option<&NodePath> {
+        self.node_at(loc.offset)
+    }
+
+    /// Get the paths of all nodes whose positions lie entirely within the range, in document order.
+    pub fn nodes_in_range(&self, start: u64, end: u64) -> Vec<&NodePath> {
+        let mut out = Vec::new();
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            if !node.span.is_some_and(|s| s.0 <= end && s.1 >= start) {
+                continue;
+            }
+            if node.pos.is_some_and(|p| start <= p.0 && p.1 <= end) {
+                out.push(&node.path);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        out
+    }
+}
+
+impl Doc {
+    /// Get the path of the innermost node whose position contains the offset.
+    ///
+    /// This builds a [PosIndex], which should be kept for repeated queries.
+    pub fn node_at(&self, offset: u64) -> Option<NodePath> {
+        PosIndex::new(self).node_at(offset).cloned()
+    }
+
+    /// Get the path of the innermost node whose position contains the location.
+    ///
+    /// This builds a [PosIndex], which should be kept for repeated queries.
+    pub fn node_at_loc(&self, loc: &SourceLoc) -> Option<NodePath> {
+        PosIndex::new(self).node_at_loc(loc).cloned()
+    }
+
+    /// Get the paths of all nodes whose positions lie entirely within the range, in document order.
+    ///
+    /// This builds a [PosIndex], which should be kept for repeated queries.
+    pub fn nodes_in_range(&self, start: u64, end: u64) -> Vec<NodePath> {
+        PosIndex::new(self)
+            .nodes_in_range(start, end)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+}
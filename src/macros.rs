@@ -20,10 +20,11 @@ macro_rules! text_container {
                                                     derive(serde::Serialize, serde::Deserialize),
                                                     serde(tag = "tag", rename = $tag),
                                                 )]
+        #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
         pub struct $name {
             pub text: String,
             #[cfg_attr(feature = "serde", serde(flatten))]
-            meta: crate::Meta,
+            pub(crate) meta: crate::Meta,
         }
 
         impl crate::Node for $name {
@@ -40,13 +41,15 @@ macro_rules! inline_container {
     ($name:ident, $tag:literal) => {
         #[derive(Debug, Clone, PartialEq, Eq)]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "tag", rename = $tag))]
+        #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
         pub struct $name {
             pub children: Vec<crate::inline::Inline>,
             #[cfg_attr(feature = "serde", serde(flatten))]
-            meta: crate::Meta,
+            pub(crate) meta: crate::Meta,
         }
 
         crate::macros::impl_hasmeta!($name);
+        crate::macros::drop_children!($name, drop_inlines);
 
         impl crate::Node for $name {
             fn node_type(&self) -> crate::NodeType {
@@ -56,13 +59,26 @@ macro_rules! inline_container {
     };
 }
 
+/// Drop a node's children iteratively, for a node whose descendants may include nodes like itself,
+/// so that deeply nested nodes do not overflow the stack.
+macro_rules! drop_children {
+    ($name:ident, $drop:ident) => {
+        impl Drop for $name {
+            fn drop(&mut self) {
+                crate::$drop(&mut self.children);
+            }
+        }
+    };
+}
+
 macro_rules! atom {
     ($name:ident, $tag:literal) => {
         #[derive(Debug, Clone, PartialEq, Eq)]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "tag", rename = $tag))]
+        #[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
         pub struct $name {
             #[cfg_attr(feature = "serde", serde(flatten))]
-            meta: crate::Meta,
+            pub(crate) meta: crate::Meta,
         }
 
         crate::macros::impl_hasmeta!($name);
@@ -91,7 +107,11 @@ macro_rules! from_into_variants {
             fn try_from(v: $enum) -> crate::Result<Self> {
                 match v {
                     $enum::$variant(inner) => Ok(inner),
-                    _ => Err(crate::Error::general("wrong variant")),
+                    _ => Err(crate::Error::WrongVariant {
+                        enum_name: stringify!($enum),
+                        expected: concat!("a ", stringify!($variant)),
+                        found: crate::traverse::NodeRef::from(&v).tag().to_owned(),
+                    }),
                 }
             }
         }
@@ -99,4 +119,131 @@ macro_rules! from_into_variants {
     };
 }
 
-pub(crate) use {atom, from_into_variants, impl_hasmeta, inline_container, text_container};
+/// Serialize an enum as its variant, which writes its own `tag`.
+///
+/// Deriving `Serialize` on an internally tagged enum would write the tag a second time.
+macro_rules! serialize_variants {
+    ($enum:ident, $($variant:ident),+) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $enum {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                match self {
+                    $($enum::$variant(inner) => inner.serialize(serializer),)+
+                }
+            }
+        }
+    };
+}
+
+/// Deserialize an enum of nodes as the variant named by the `tag`,
+/// or, when deserializing [leniently](crate::deserialize::Lenient), a node with an unknown tag as its `Unknown` variant.
+/// With `strict`, the enum has no `Unknown` variant, and unknown tags are always errors.
+///
+/// Serde's internally tagged enums read each node into memory to find its tag, without counting the depth,
+/// so this reads the tag as the first field, as djot.js and this crate write it,
+/// counts the node within the [Limits](crate::deserialize::Limits), and deserializes it from the rest of the map.
+/// A node with its tag elsewhere is read into memory as a [Value](crate::unknown::Value) first.
+macro_rules! deserialize_variants {
+    (strict $enum:ident, $($variant:ident),+) => {
+        deserialize_variants!(@impl $enum, None, $($variant),+);
+    };
+    ($enum:ident, $($variant:ident),+) => {
+        deserialize_variants!(
+            @impl $enum,
+            Some(|fields| crate::unknown::Unknown::from_fields(fields).map($enum::Unknown)),
+            $($variant),+
+        );
+    };
+    (@impl $enum:ident, $unknown:expr, $($variant:ident),+) => {
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $enum {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                use indexmap::IndexMap;
+                use serde::de::{
+                    value::{MapAccessDeserializer, StrDeserializer},
+                    Error, IntoDeserializer, MapAccess,
+                };
+
+                use crate::unknown::Value;
+
+                #[derive(serde::Deserialize)]
+                #[serde(rename_all = "snake_case")]
+                enum Tag {
+                    $($variant,)+
+                }
+
+                /// Reads the node with an unknown tag from its fields, if there is a variant for it.
+                fn unknown(
+                    tag: &str,
+                ) -> Option<fn(IndexMap<String, Value>) -> std::result::Result<$enum, serde::de::value::Error>> {
+                    let unknown: Option<fn(IndexMap<String, Value>) -> _> = $unknown;
+                    let tag: StrDeserializer<serde::de::value::Error> = tag.into_deserializer();
+                    match Tag::deserialize(tag) {
+                        Err(_) if crate::deserialize::is_lenient() => unknown,
+                        _ => None,
+                    }
+                }
+
+                fn tag<E: Error>(tag: &str) -> std::result::Result<Tag, E> {
+                    let tag: StrDeserializer<E> = tag.into_deserializer();
+                    Tag::deserialize(tag)
+                }
+
+                struct NodeVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for NodeVisitor {
+                    type Value = $enum;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        formatter.write_str("a node")
+                    }
+
+                    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<$enum, A::Error> {
+                        let _nested = crate::deserialize::nest_node()?;
+                        let first = map.next_key::<String>()?;
+                        if first.as_deref() == Some("tag") {
+                            let name = map.next_value::<String>()?;
+                            if let Some(read) = unknown(&name) {
+                                let mut fields = crate::unknown::read_fields(None, map)?;
+                                fields.shift_insert(0, "tag".to_string(), Value::String(name));
+                                return read(fields).map_err(A::Error::custom);
+                            }
+                            // a function per variant keeps their results out of this frame, which is on the stack for each level
+                            let read: fn(MapAccessDeserializer<A>) -> std::result::Result<$enum, A::Error> =
+                                match tag(&name)? {
+                                    $(Tag::$variant => |node| $variant::deserialize(node).map($enum::$variant),)+
+                                };
+                            return read(MapAccessDeserializer::new(map));
+                        }
+                        let fields = crate::unknown::read_fields(first, map)?;
+                        let name = match fields.get("tag") {
+                            Some(Value::String(name)) => name,
+                            Some(other) => return Err(A::Error::invalid_type(other.unexpected(), &"a tag")),
+                            None => return Err(A::Error::missing_field("tag")),
+                        };
+                        if let Some(read) = unknown(name) {
+                            return read(fields).map_err(A::Error::custom);
+                        }
+                        let read: fn(Value) -> std::result::Result<$enum, serde::de::value::Error> = match tag(name)? {
+                            $(Tag::$variant => |node| $variant::deserialize(node).map($enum::$variant),)+
+                        };
+                        read(Value::Object(fields)).map_err(A::Error::custom)
+                    }
+                }
+
+                deserializer.deserialize_map(NodeVisitor)
+            }
+        }
+    };
+}
+
+pub(crate) use {
+    atom, deserialize_variants, drop_children, from_into_variants, impl_hasmeta, inline_container,
+    serialize_variants, text_container,
+};
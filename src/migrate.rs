@@ -0,0 +1,353 @@
+//! Reading the JSON of older versions of djot.js.
+//!
+//! djot.js's AST has changed between releases, so JSON written by an older djot.js may not deserialize as the current types.
+//! [Versioned] reads a [Doc] from such JSON by upgrading it to the current shape first,
+//! and writes a `djotJsVersion` field with the document so that later releases of this crate know what they are reading.
+//! Without that field, the version is guessed from the shape of the document.
+//!
+//! | djot.js | differences from the next version |
+//! |---------|-----------------------------------|
+//! | 0.1     | symbols are `emoji` nodes rather than `symb` |
+//! | 0.2     | the doc has no `autoReferences`; the references made for headings are among its `references` |
+//! | 0.3     | current |
+//!
+//! ```
+//! use djot_ast::{migrate::{AstVersion, Versioned}, Doc};
+//!
+//! let json = r##"{"tag": "doc", "references": {}, "footnotes": {}, "children": [
+//!     {"tag": "para", "children": [{"tag": "emoji", "alias": "smile"}]}
+//! ]}"##;
+//! let Versioned(doc): Versioned<Doc> = serde_json::from_str(json).unwrap();
+//! let json = serde_json::to_value(Versioned(&doc)).unwrap();
+//! assert_eq!(json["children"][0]["children"][0]["tag"], "symb");
+//! assert_eq!(json["djotJsVersion"], AstVersion::CURRENT.to_string());
+//! ```
+use std::{borrow::Borrow, collections::HashMap, fmt::Display, str::FromStr};
+
+use indexmap::IndexMap;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{unknown::Value, Doc, Error};
+
+/// The field holding the version of a document written by [Versioned].
+pub const VERSION_FIELD: &str = "djotJsVersion";
+
+/// A version of djot.js whose AST differs from that of the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AstVersion {
+    V0_1,
+    V0_2,
+    V0_3,
+}
+
+impl AstVersion {
+    /// The version of djot.js whose AST this crate implements.
+    pub const CURRENT: Self = Self::V0_3;
+
+    /// Every version, oldest first.
+    pub const ALL: [Self; 3] = [Self::V0_1, Self::V0_2, Self::V0_3];
+
+    /// Guess the version of djot.js a document was written by, from its shape.
+    ///
+    /// A document with no features that changed is taken to be of the current version.
+    pub fn detect(value: &Value) -> Self {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                Value::Object(fields) => {
+                    if matches!(fields.get("tag"), Some(Value::String(tag)) if tag == "emoji") {
+                        return Self::V0_1;
+                    }
+                    stack.extend(within(fields));
+                }
+                Value::Array(items) => stack.extend(items),
+                _ => (),
+            }
+        }
+        if value.get("autoReferences").is_none() {
+            return Self::V0_2;
+        }
+        Self::CURRENT
+    }
+}
+
+impl Display for AstVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::V0_1 => "0.1",
+            Self::V0_2 => "0.2",
+            Self::V0_3 => "0.3",
+        })
+    }
+}
+
+impl FromStr for AstVersion {
+    type Err = Error;
+
+    /// Parse a version such as `0.2` or `0.2.1`; patch releases do not change the AST.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('.');
+        let minor = (parts.next(), parts.next());
+        let patch_ok =
+            parts.next().is_none_or(|p| p.parse::<u32>().is_ok()) && parts.next().is_none();
+        match minor {
+            (Some("0"), Some("1")) if patch_ok => Ok(Self::V0_1),
+            (Some("0"), Some("2")) if patch_ok => Ok(Self::V0_2),
+            (Some("0"), Some("3")) if patch_ok => Ok(Self::V0_3),
+            _ => Err(Error::InvalidValue {
+                value: s.to_owned(),
+                expected: format!(
+                    "a supported djot.js version, from {} to {}",
+                    Self::ALL[0],
+                    Self::CURRENT
+                ),
+            }),
+        }
+    }
+}
+
+impl Serialize for AstVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AstVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// The values within a node that may be or contain nodes, i.e. not its attributes,
+/// whose keys and values are arbitrary strings.
+fn within(fields: &IndexMap<String, Value>) -> impl Iterator<Item = &Value> {
+    fields
+        .iter()
+        .filter(|(k, _)| !ATTRIBUTE_FIELDS.contains(&k.as_str()))
+        .map(|(_, v)| v)
+}
+
+fn within_mut(fields: &mut IndexMap<String, Value>) -> impl Iterator<Item = &mut Value> {
+    fields
+        .iter_mut()
+        .filter(|(k, _)| !ATTRIBUTE_FIELDS.contains(&k.as_str()))
+        .map(|(_, v)| v)
+}
+
+const ATTRIBUTE_FIELDS: [&str; 2] = ["attributes", "autoAttributes"];
+
+/// Upgrade the JSON of a document written by the given version of djot.js to the current shape.
+pub fn migrate(value: &mut Value, from: AstVersion) {
+    if from < AstVersion::V0_2 {
+        rename_emoji(value);
+    }
+    if from < AstVersion::V0_3 {
+        split_auto_references(value);
+    }
+}
+
+/// djot.js 0.2 renamed `emoji` nodes to `symb`.
+fn rename_emoji(value: &mut Value) {
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(fields) => {
+                if let Some(Value::String(tag)) = fields.get_mut("tag") {
+                    if tag == "emoji" {
+                        *tag = "symb".to_owned();
+                    }
+                }
+                stack.extend(within_mut(fields));
+            }
+            Value::Array(items) => stack.extend(items),
+            _ => (),
+        }
+    }
+}
+
+/// djot.js 0.3 moved the references made for headings from `references` to `autoReferences`.
+///
+/// Those are the references to the id of a section whose label is its heading's text,
+/// rather than ones the author wrote, which may point to a section too.
+fn split_auto_references(value: &mut Value) {
+    let mut labels = HashMap::new();
+    let mut stack = vec![&*value];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(fields) => {
+                if matches!(fields.get("tag"), Some(Value::String(tag)) if tag == "section") {
+                    let heading = match fields.get("children") {
+                        Some(Value::Array(children)) => children.first(),
+                        _ => None,
+                    };
+                    for key in ATTRIBUTE_FIELDS {
+                        if let (Some(Value::String(id)), Some(heading)) =
+                            (fields.get(key).and_then(|a| a.get("id")), heading)
+                        {
+                            labels.insert(
+                                format!("#{id}"),
+                                normalize_label(&string_content(heading)),
+                            );
+                        }
+                    }
+                }
+                stack.extend(within(fields));
+            }
+            Value::Array(items) => stack.extend(items),
+            _ => (),
+        }
+    }
+
+    let Value::Object(doc) = value else {
+        return;
+    };
+    if doc.contains_key("autoReferences") {
+        return;
+    }
+    let mut auto = IndexMap::new();
+    if let Some(Value::Object(references)) = doc.get_mut("references") {
+        references.retain(|label, reference| {
+            let to_section = matches!(
+                reference.get("destination"),
+                Some(Value::String(dest)) if labels.get(dest) == Some(&normalize_label(label))
+            );
+            if to_section {
+                auto.insert(label.clone(), std::mem::replace(reference, Value::Null));
+            }
+            !to_section
+        });
+    }
+    // keep djot.js's order of fields
+    let index = doc.get_index_of("references").map_or(0, |i| i + 1);
+    doc.shift_insert(index, "autoReferences".to_owned(), Value::Object(auto));
+}
+
+/// The text of a node, as djot.js finds the label of a heading's reference.
+fn string_content(value: &Value) -> String {
+    let mut text = String::new();
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value.get("text") {
+            Some(Value::String(t)) => text.push_str(t),
+            _ if matches!(value.get("tag"), Some(Value::String(tag)) if tag == "soft_break") => {
+                text.push('\n')
+            }
+            _ => {
+                if let Some(Value::Array(children)) = value.get("children") {
+                    stack.extend(children.iter().rev());
+                }
+            }
+        }
+    }
+    text
+}
+
+/// Compare labels as djot.js does, ignoring the amount and kind of whitespace.
+fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Reads a [Doc] written by any supported version of djot.js, and writes it with its version.
+///
+/// Deserializing upgrades the document with [migrate],
+/// from the version in its [VERSION_FIELD] or, if it has none, the one [AstVersion::detect] guesses.
+/// It reads the whole document into memory first, so it needs a self-describing format such as JSON.
+/// Serializing writes the document as usual, followed by the [VERSION_FIELD] of the current version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Versioned<T>(pub T);
+
+impl<T: Borrow<Doc>> Serialize for Versioned<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct WithVersion<'a> {
+            #[serde(flatten)]
+            doc: &'a Doc,
+            #[serde(rename = "djotJsVersion")]
+            version: AstVersion,
+        }
+
+        WithVersion {
+            doc: self.0.borrow(),
+            version: AstVersion::CURRENT,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Versioned<Doc> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let version = match &mut value {
+            Value::Object(fields) => fields.shift_remove(VERSION_FIELD),
+            _ => None,
+        };
+        let version = match version {
+            Some(version) => AstVersion::deserialize(version).map_err(de::Error::custom)?,
+            None => AstVersion::detect(&value),
+        };
+        migrate(&mut value, version);
+        Doc::deserialize(value)
+            .map(Versioned)
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::Lenient;
+
+    #[test]
+    fn parse_versions() {
+        for version in AstVersion::ALL {
+            assert_eq!(version.to_string().parse::<AstVersion>().unwrap(), version);
+        }
+        assert_eq!("0.2.1".parse::<AstVersion>().unwrap(), AstVersion::V0_2);
+        for s in ["0.4", "1.0", "0.2.x", "0.2.1.1", ""] {
+            assert!(s.parse::<AstVersion>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let json = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [], "djotJsVersion": "9.0"}"#;
+        let err = serde_json::from_str::<Versioned<Doc>>(json).unwrap_err();
+        assert!(
+            err.to_string().contains("supported djot.js version"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn version_overrides_detection() {
+        // a reference to a section by its heading, which 0.2 would have moved
+        let json = r##"{"tag": "doc", "references": {"a": {"tag": "reference", "label": "a", "destination": "#a"}},
+            "footnotes": {}, "children": [{"tag": "section", "attributes": {"id": "a"}, "children": [
+                {"tag": "heading", "level": 1, "children": [{"tag": "str", "text": "a"}]}
+            ]}]}"##;
+        let Versioned(doc): Versioned<Doc> = serde_json::from_str(json).unwrap();
+        assert!(doc.references.is_empty());
+        assert_eq!(doc.auto_references.len(), 1);
+
+        let json = json.replacen(
+            r#""tag": "doc","#,
+            r#""tag": "doc", "autoReferences": {}, "djotJsVersion": "0.3","#,
+            1,
+        );
+        let Versioned(doc): Versioned<Doc> = serde_json::from_str(&json).unwrap();
+        assert_eq!(doc.references.len(), 1);
+        assert!(doc.auto_references.is_empty());
+    }
+
+    #[test]
+    fn lenient_versioned() {
+        let json = r#"{"tag": "doc", "references": {}, "footnotes": {}, "children": [
+            {"tag": "admonition", "children": [{"tag": "emoji", "alias": "+1"}]}
+        ]}"#;
+        let Lenient(Versioned(doc)): Lenient<Versioned<Doc>> = serde_json::from_str(json).unwrap();
+        // the unknown node's fields are migrated too
+        let json = serde_json::to_value(&doc).unwrap();
+        assert_eq!(json["children"][0]["children"][0]["tag"], "symb");
+    }
+}
@@ -0,0 +1,171 @@
+//! Normalization of documents, so that equivalent trees from different producers compare equal.
+use crate::{
+    attributes::{HasAttributes, Pos},
+    block::Block,
+    inline::Inline,
+    traverse::NodeMut,
+    Doc,
+};
+
+/// Normalize the document's inlines and blocks in place.
+///
+/// - adjacent [Str](crate::inline::Str)s without attributes are merged,
+///   with a position spanning both if both have one
+/// - empty [Str](crate::inline::Str)s, and empty [Emph](crate::inline::Emph), [Strong](crate::inline::Strong),
+///   [Span](crate::inline::Span), [Mark](crate::inline::Mark), [Superscript](crate::inline::Superscript),
+///   [Subscript](crate::inline::Subscript), [Insert](crate::inline::Insert) and [Delete](crate::inline::Delete)
+///   containers, are removed if they have no attributes
+/// - leading and trailing [SoftBreak](crate::inline::SoftBreak)s are removed
+///   from paragraphs, headings, table cells, captions and terms
+/// - [Para](crate::block::Para)s with no children and no attributes are removed
+///
+/// Footnotes are normalized too.
+pub fn normalize(doc: &mut Doc) {
+    for footnote in doc.footnotes.values_mut() {
+        NodeMut::Footnote(footnote).walk(normalize_inline_container);
+        NodeMut::Footnote(footnote).walk(remove_empty_paras);
+    }
+    NodeMut::Doc(doc).walk(normalize_inline_container);
+    NodeMut::Doc(doc).walk(remove_empty_paras);
+}
+
+fn normalize_inline_container(node: &mut NodeMut) {
+    let inlines = match node {
+        NodeMut::Block(Block::Para(i)) => &mut i.children,
+        NodeMut::Block(Block::Heading(i)) => &mut i.children,
+        NodeMut::Cell(i) => &mut i.children,
+        NodeMut::Caption(i) => &mut i.children,
+        NodeMut::Term(i) => &mut i.children,
+        _ => return,
+    };
+    normalize_inlines(inlines);
+
+    let is_soft_break = |i: &Inline| matches!(i, Inline::SoftBreak(_));
+    let n_trailing = inlines
+        .iter()
+        .rev()
+        .take_while(|i| is_soft_break(i))
+        .count();
+    inlines.truncate(inlines.len() - n_trailing);
+    let n_leading = inlines.iter().take_while(|i| is_soft_break(i)).count();
+    inlines.drain(..n_leading);
+}
+
+fn remove_empty_paras(node: &mut NodeMut) {
+    let blocks = match node {
+        NodeMut::Doc(i) => &mut i.children,
+        NodeMut::Block(Block::Section(i)) => &mut i.children,
+        NodeMut::Block(Block::Div(i)) => &mut i.children,
+        NodeMut::Block(Block::BlockQuote(i)) => &mut i.children,
+        NodeMut::ListItem(i) => &mut i.children,
+        NodeMut::TaskListItem(i) => &mut i.children,
+        NodeMut::Definition(i) => &mut i.children,
+        NodeMut::Footnote(i) => &mut i.children,
+        _ => return,
+    };
+    blocks.retain(|b| match b {
+        Block::Para(p) => !p.children.is_empty() || has_attributes(p),
+        _ => true,
+    });
+}
+
+fn has_attributes(node: &impl HasAttributes) -> bool {
+    !node.attributes().is_empty() || !node.auto_attributes().is_empty()
+}
+
+fn is_removable(inline: &Inline) -> bool {
+    if has_attributes(inline) {
+        return false;
+    }
+    match inline {
+        Inline::Str(i) => i.text.is_empty(),
+        Inline::Emph(_)
+        | Inline::Strong(_)
+        | Inline::Span(_)
+        | Inline::Mark(_)
+        | Inline::Superscript(_)
+        | Inline::Subscript(_)
+        | Inline::Insert(_)
+        | Inline::Delete(_) => inline.children().is_some_and(|c| c.is_empty()),
+        _ => false,
+    }
+}
+
+/// Normalize inlines recursively, removing empty nodes before merging strings
+/// so that strings either side of a removed node are merged.
+fn normalize_inlines(inlines: &mut Vec<Inline>) {
+    for inline in inlines.iter_mut() {
+        if let Some(children) = inline.children_mut() {
+            normalize_inlines(children);
+        }
+    }
+
+    let mut out: Vec<Inline> = Vec::with_capacity(inlines.len());
+    for inline in inlines.drain(..).filter(|i| !is_removable(i)) {
+        match (out.last_mut(), inline) {
+            (Some(Inline::Str(prev)), Inline::Str(next))
+                if !has_attributes(prev) && !has_attributes(&next) =>
+            {
+                let pos = match (prev.pos(), next.pos()) {
+                    (Some(p), Some(n)) => Some(Pos {
+                        start: p.start,
+                        end: n.end,
+                    }),
+                    _ => None,
+                };
+                prev.text.push_str(&next.text);
+                *prev.pos_mut() = pos;
+            }
+            (_, inline) => out.push(inline),
+        }
+    }
+    *inlines = out;
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn pos(start: u64, end: u64) -> String {
+        format!(
+            r#"{{"start": {{"line": 1, "col": {start}, "offset": {start}}}, "end": {{"line": 1, "col": {end}, "offset": {end}}}}}"#
+        )
+    }
+
+    fn doc(children: &str) -> Doc {
+        let s = format!(
+            r#"{{"tag": "doc", "references": {{}}, "autoReferences": {{}}, "footnotes": {{}}, "children": [{children}]}}"#
+        );
+        serde_json::from_str(&s).unwrap()
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut input = doc(&format!(
+            r#"{{"tag": "para", "children": [
+                {{"tag": "soft_break"}},
+                {{"tag": "str", "text": "a", "pos": {}}},
+                {{"tag": "emph", "children": [{{"tag": "str", "text": ""}}]}},
+                {{"tag": "str", "text": "b", "pos": {}}},
+                {{"tag": "strong", "children": [{{"tag": "str", "text": "c"}}, {{"tag": "str", "text": "d"}}]}},
+                {{"tag": "span", "children": [], "attributes": {{"class": "x"}}}},
+                {{"tag": "soft_break"}}
+            ]}},
+            {{"tag": "para", "children": []}},
+            {{"tag": "block_quote", "children": [{{"tag": "para", "children": [{{"tag": "soft_break"}}]}}]}}"#,
+            pos(1, 1),
+            pos(3, 3),
+        ));
+        let expected = doc(&format!(
+            r#"{{"tag": "para", "children": [
+                {{"tag": "str", "text": "ab", "pos": {}}},
+                {{"tag": "strong", "children": [{{"tag": "str", "text": "cd"}}]}},
+                {{"tag": "span", "children": [], "attributes": {{"class": "x"}}}}
+            ]}},
+            {{"tag": "block_quote", "children": []}}"#,
+            pos(1, 3),
+        ));
+        normalize(&mut input);
+        assert_eq!(input, expected);
+    }
+}
@@ -0,0 +1,685 @@
+//! Addressing nodes by their location in the tree.
+use std::{collections::HashSet, fmt::Display, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Serialize};
+
+use crate::{
+    attributes::{HasAttributes, NodeId},
+    block::{Block, DefinitionListItem, Table},
+    traverse::{NodeMut, NodeRef},
+    AstNode, Doc, Error, Result,
+};
+
+/// Where a [NodePath] starts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathRoot {
+    /// The document itself.
+    #[default]
+    Doc,
+    /// The footnote with the given label in [Doc::footnotes].
+    Footnote(String),
+    /// The reference with the given label in [Doc::references].
+    Reference(String),
+    /// The reference with the given label in [Doc::auto_references].
+    AutoReference(String),
+}
+
+/// The location of a node in a [Doc].
+///
+/// Paths are displayed and parsed as JSON pointers into the djot.js JSON representation,
+/// e.g. `/children/3/children/0` or `/footnotes/note/children/1`.
+/// The empty string refers to the document itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodePath {
+    pub root: PathRoot,
+    /// Child indices leading from the root to the node.
+    pub children: Vec<usize>,
+}
+
+impl NodePath {
+    /// The path to the document itself.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Get the path of a child of this node.
+    pub fn child(&self, idx: usize) -> Self {
+        let mut out = self.clone();
+        out.children.push(idx);
+        out
+    }
+
+    /// Get the path of this node's parent, or `None` if this is a root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, rest) = self.children.split_last()?;
+        Some(Self {
+            root: self.root.clone(),
+            children: rest.to_vec(),
+        })
+    }
+
+    /// Whether this path is a strict ancestor of another.
+    pub fn is_ancestor_of(&self, other: &Self) -> bool {
+        self.root == other.root
+            && self.children.len() < other.children.len()
+            && other.children.starts_with(&self.children)
+    }
+}
+
+impl From<Vec<usize>> for NodePath {
+    fn from(value: Vec<usize>) -> Self {
+        Self {
+            root: PathRoot::Doc,
+            children: value,
+        }
+    }
+}
+
+impl From<&[usize]> for NodePath {
+    fn from(value: &[usize]) -> Self {
+        value.to_vec().into()
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+impl Display for NodePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.root {
+            PathRoot::Doc => (),
+            PathRoot::Footnote(l) => write!(f, "/footnotes/{}", escape(l))?,
+            PathRoot::Reference(l) => write!(f, "/references/{}", escape(l))?,
+            PathRoot::AutoReference(l) => write!(f, "/autoReferences/{}", escape(l))?,
+        }
+        for idx in self.children.iter() {
+            write!(f, "/children/{idx}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NodePath {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::General(format!("invalid node path '{s}'"));
+        if s.is_empty() {
+            return Ok(Self::root());
+        }
+        let mut tokens = s.strip_prefix('/').ok_or_else(invalid)?.split('/');
+        let mut children = Vec::new();
+        let mut root = PathRoot::Doc;
+        let mut first = true;
+        while let Some(token) = tokens.next() {
+            let arg = tokens.next().ok_or_else(invalid)?;
+            match token {
+                "children" => children.push(arg.parse().map_err(|_| invalid())?),
+                "footnotes" if first => root = PathRoot::Footnote(unescape(arg)),
+                "references" if first => root = PathRoot::Reference(unescape(arg)),
+                "autoReferences" if first => root = PathRoot::AutoReference(unescape(arg)),
+                _ => return Err(invalid()),
+            }
+            first = false;
+        }
+        Ok(Self { root, children })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for NodePath {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for NodePath {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+fn no_node(path: &NodePath) -> Error {
+    Error::General(format!("no node at path '{path}'"))
+}
+
+impl Doc {
+    /// Get the node at the given path.
+    pub fn get(&self, path: &NodePath) -> Option<NodeRef<'_>> {
+        let mut node = match &path.root {
+            PathRoot::Doc => NodeRef::Doc(self),
+            PathRoot::Footnote(l) => NodeRef::Footnote(self.footnotes.get(l)?),
+            PathRoot::Reference(l) => NodeRef::Reference(self.references.get(l)?),
+            PathRoot::AutoReference(l) => NodeRef::Reference(self.auto_references.get(l)?),
+        };
+        for idx in path.children.iter() {
+            node = node.children().into_iter().nth(*idx)?;
+        }
+        Some(node)
+    }
+
+    /// Get the node at the given path mutably.
+    pub fn get_mut(&mut self, path: &NodePath) -> Option<NodeMut<'_>> {
+        let mut node = match &path.root {
+            PathRoot::Doc => NodeMut::Doc(self),
+            PathRoot::Footnote(l) => NodeMut::Footnote(self.footnotes.get_mut(l)?),
+            PathRoot::Reference(l) => NodeMut::Reference(self.references.get_mut(l)?),
+            PathRoot::AutoReference(l) => NodeMut::Reference(self.auto_references.get_mut(l)?),
+        };
+        for idx in path.children.iter() {
+            node = node.into_children().into_iter().nth(*idx)?;
+        }
+        Some(node)
+    }
+
+    /// Insert a node so that it is at the given path, shifting later siblings along.
+    ///
+    /// The node must be of the right type for its parent,
+    /// e.g. a [Block] in a [Section](crate::block::Section) or a [Row](crate::block::Row) in a [Table].
+    /// Inserting at a footnote or reference root adds it to the document's map under that label.
+    pub fn insert_at(&mut self, path: &NodePath, node: AstNode) -> Result<()> {
+        let Some((idx, parent)) = path.children.split_last() else {
+            return self.insert_root(&path.root, node);
+        };
+        let parent = NodePath {
+            root: path.root.clone(),
+            children: parent.to_vec(),
+        };
+        let children = self
+            .get_mut(&parent)
+            .ok_or_else(|| no_node(&parent))?
+            .into_child_list();
+        if *idx > children.len() {
+            return Err(no_node(path));
+        }
+        children.insert(*idx, node)
+    }
+
+    /// Remove the node at the given path, shifting later siblings back.
+    ///
+    /// Table captions and the terms and definitions of definition list items cannot be removed,
+    /// and neither can the document itself.
+    pub fn remove_at(&mut self, path: &NodePath) -> Result<AstNode> {
+        let Some((idx, parent)) = path.children.split_last() else {
+            return self.remove_root(&path.root);
+        };
+        let parent = NodePath {
+            root: path.root.clone(),
+            children: parent.to_vec(),
+        };
+        let children = self
+            .get_mut(&parent)
+            .ok_or_else(|| no_node(&parent))?
+            .into_child_list();
+        if *idx >= children.len() {
+            return Err(no_node(path));
+        }
+        children.remove(*idx)
+    }
+
+    /// Replace the node at the given path, returning the old node.
+    ///
+    /// The new node must be of the same type as the old one.
+    pub fn replace_at(&mut self, path: &NodePath, node: AstNode) -> Result<AstNode> {
+        let Some((idx, parent)) = path.children.split_last() else {
+            return self.replace_root(&path.root, node);
+        };
+        let parent = NodePath {
+            root: path.root.clone(),
+            children: parent.to_vec(),
+        };
+        let children = self
+            .get_mut(&parent)
+            .ok_or_else(|| no_node(&parent))?
+            .into_child_list();
+        if *idx >= children.len() {
+            return Err(no_node(path));
+        }
+        children.replace(*idx, node)
+    }
+
+    fn insert_root(&mut self, root: &PathRoot, node: AstNode) -> Result<()> {
+        match root {
+            PathRoot::Doc => Err(Error::general("cannot insert the document root")),
+            PathRoot::Footnote(l) => {
+                self.footnotes.insert(l.clone(), node.try_into()?);
+                Ok(())
+            }
+            PathRoot::Reference(l) => {
+                self.references.insert(l.clone(), node.try_into()?);
+                Ok(())
+            }
+            PathRoot::AutoReference(l) => {
+                self.auto_references.insert(l.clone(), node.try_into()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Replace a footnote or reference in place, keeping its position in the map.
+    fn replace_root(&mut self, root: &PathRoot, node: AstNode) -> Result<AstNode> {
+        let missing = || {
+            no_node(&NodePath {
+                root: root.clone(),
+                children: Vec::new(),
+            })
+        };
+        match root {
+            PathRoot::Doc => Err(Error::general("cannot replace the document root")),
+            PathRoot::Footnote(l) => {
+                let old = self.footnotes.get_mut(l).ok_or_else(missing)?;
+                Ok(std::mem::replace(old, node.try_into()?).into())
+            }
+            PathRoot::Reference(l) => {
+                let old = self.references.get_mut(l).ok_or_else(missing)?;
+                Ok(std::mem::replace(old, node.try_into()?).into())
+            }
+            PathRoot::AutoReference(l) => {
+                let old = self.auto_references.get_mut(l).ok_or_else(missing)?;
+                Ok(std::mem::replace(old, node.try_into()?).into())
+            }
+        }
+    }
+
+    fn remove_root(&mut self, root: &PathRoot) -> Result<AstNode> {
+        let removed = match root {
+            PathRoot::Doc => return Err(Error::general("cannot remove the document root")),
+            PathRoot::Footnote(l) => self.footnotes.shift_remove(l).map(AstNode::from),
+            PathRoot::Reference(l) => self.references.shift_remove(l).map(AstNode::from),
+            PathRoot::AutoReference(l) => self.auto_references.shift_remove(l).map(AstNode::from),
+        };
+        removed.ok_or_else(|| {
+            no_node(&NodePath {
+                root: root.clone(),
+                children: Vec::new(),
+            })
+        })
+    }
+}
+
+/// A node's list of children, typed by what the node can contain.
+enum ChildList<'a> {
+    Blocks(&'a mut Vec<Block>),
+    Inlines(&'a mut Vec<crate::inline::Inline>),
+    ListItems(&'a mut Vec<crate::block::ListItem>),
+    TaskListItems(&'a mut Vec<crate::block::TaskListItem>),
+    DefinitionListItems(&'a mut Vec<DefinitionListItem>),
+    Cells(&'a mut Vec<crate::block::Cell>),
+    /// The caption, followed by the rows.
+    Table(&'a mut Table),
+    /// The term, followed by the definition.
+    DefinitionListItem(&'a mut DefinitionListItem),
+    Leaf,
+}
+
+impl<'a> NodeMut<'a> {
+    fn into_child_list(self) -> ChildList<'a> {
+        use ChildList::*;
+        match self {
+            NodeMut::Doc(i) => Blocks(&mut i.children),
+            NodeMut::Block(b) => match b {
+                Block::Para(i) => Inlines(&mut i.children),
+                Block::Heading(i) => Inlines(&mut i.children),
+                Block::Section(i) => Blocks(&mut i.children),
+                Block::Div(i) => Blocks(&mut i.children),
+                Block::BlockQuote(i) => Blocks(&mut i.children),
+                Block::OrderedList(i) => ListItems(&mut i.children),
+                Block::BulletList(i) => ListItems(&mut i.children),
+                Block::TaskList(i) => TaskListItems(&mut i.children),
+                Block::DefinitionList(i) => DefinitionListItems(&mut i.children),
+                Block::Table(i) => Table(i),
+                Block::ThematicBreak(_)
+                | Block::CodeBlock(_)
+                | Block::RawBlock(_)
+                | Block::Unknown(_) => Leaf,
+            },
+            NodeMut::Inline(i) => match i.children_mut() {
+                Some(c) => Inlines(c),
+                None => Leaf,
+            },
+            NodeMut::ListItem(i) => Blocks(&mut i.children),
+            NodeMut::TaskListItem(i) => Blocks(&mut i.children),
+            NodeMut::DefinitionListItem(i) => DefinitionListItem(i),
+            NodeMut::Term(i) => Inlines(&mut i.children),
+            NodeMut::Definition(i) => Blocks(&mut i.children),
+            NodeMut::Row(i) => Cells(&mut i.children),
+            NodeMut::Cell(i) => Inlines(&mut i.children),
+            NodeMut::Caption(i) => Inlines(&mut i.children),
+            NodeMut::Footnote(i) => Blocks(&mut i.children),
+            NodeMut::Reference(_) => Leaf,
+        }
+    }
+}
+
+impl ChildList<'_> {
+    fn len(&self) -> usize {
+        use ChildList::*;
+        match self {
+            Blocks(v) => v.len(),
+            Inlines(v) => v.len(),
+            ListItems(v) => v.len(),
+            TaskListItems(v) => v.len(),
+            DefinitionListItems(v) => v.len(),
+            Cells(v) => v.len(),
+            Table(t) => 1 + t.rows.len(),
+            DefinitionListItem(_) => 2,
+            Leaf => 0,
+        }
+    }
+
+    fn insert(self, idx: usize, node: AstNode) -> Result<()> {
+        use ChildList::*;
+        match self {
+            Blocks(v) => v.insert(idx, node.try_into()?),
+            Inlines(v) => v.insert(idx, node.try_into()?),
+            ListItems(v) => v.insert(idx, node.try_into()?),
+            TaskListItems(v) => v.insert(idx, node.try_into()?),
+            DefinitionListItems(v) => v.insert(idx, node.try_into()?),
+            Cells(v) => v.insert(idx, node.try_into()?),
+            Table(t) if idx > 0 => t.rows.insert(idx - 1, node.try_into()?),
+            Table(_) | DefinitionListItem(_) | Leaf => {
+                return Err(Error::general("cannot insert a node here"))
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(self, idx: usize) -> Result<AstNode> {
+        use ChildList::*;
+        Ok(match self {
+            Blocks(v) => v.remove(idx).into(),
+            Inlines(v) => v.remove(idx).into(),
+            ListItems(v) => v.remove(idx).into(),
+            TaskListItems(v) => v.remove(idx).into(),
+            DefinitionListItems(v) => v.remove(idx).into(),
+            Cells(v) => v.remove(idx).into(),
+            Table(t) if idx > 0 => t.rows.remove(idx - 1).into(),
+            Table(_) | DefinitionListItem(_) | Leaf => {
+                return Err(Error::general("cannot remove this node"))
+            }
+        })
+    }
+
+    fn replace(self, idx: usize, node: AstNode) -> Result<AstNode> {
+        use ChildList::*;
+        fn swap<T: Into<AstNode> + TryFrom<AstNode, Error = Error>>(
+            slot: &mut T,
+            node: AstNode,
+        ) -> Result<AstNode> {
+            Ok(std::mem::replace(slot, node.try_into()?).into())
+        }
+        match self {
+            Blocks(v) => swap(&mut v[idx], node),
+            Inlines(v) => swap(&mut v[idx], node),
+            ListItems(v) => swap(&mut v[idx], node),
+            TaskListItems(v) => swap(&mut v[idx], node),
+            DefinitionListItems(v) => swap(&mut v[idx], node),
+            Cells(v) => swap(&mut v[idx], node),
+            Table(t) if idx == 0 => swap(&mut t.caption, node),
+            Table(t) => swap(&mut t.rows[idx - 1], node),
+            DefinitionListItem(d) if idx == 0 => swap(&mut d.term, node),
+            DefinitionListItem(d) => swap(&mut d.definition, node),
+            Leaf => Err(Error::general("cannot replace this node")),
+        }
+    }
+}
+
+impl Doc {
+    /// Iterate over every node in the document with its path, depth-first.
+    ///
+    /// The document and its children come first,
+    /// followed by the footnotes, references and automatic references, each in the order of the map.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodePath, NodeRef<'_>)> {
+        let mut roots = vec![(NodePath::root(), NodeRef::Doc(self))];
+        let path = |root| NodePath {
+            root,
+            children: Vec::new(),
+        };
+        for (l, f) in &self.footnotes {
+            roots.push((path(PathRoot::Footnote(l.clone())), NodeRef::Footnote(f)));
+        }
+        for (l, r) in &self.references {
+            roots.push((path(PathRoot::Reference(l.clone())), NodeRef::Reference(r)));
+        }
+        for (l, r) in &self.auto_references {
+            roots.push((
+                path(PathRoot::AutoReference(l.clone())),
+                NodeRef::Reference(r),
+            ));
+        }
+        roots.reverse();
+        Nodes { stack: roots }
+    }
+
+    /// Give every node an id which it keeps through clones and edits.
+    ///
+    /// Nodes which already have an id keep it,
+    /// except for later copies of a duplicated id (e.g. from cloning a node), which get new ids.
+    /// New ids are larger than any existing id and are assigned in the order of [Doc::nodes],
+    /// until they run out after `u64::MAX`, when the smallest unused ids are assigned instead.
+    /// Returns the number of ids assigned.
+    pub fn assign_ids(&mut self) -> usize {
+        let existing: HashSet<NodeId> = self
+            .nodes()
+            .filter_map(|(_, node)| node.meta().node_id())
+            .collect();
+        let mut last = existing.iter().map(|id| id.get()).max().unwrap_or(0);
+        let mut smallest = 0;
+        let mut next_id = move || match NodeId::after(last) {
+            Some(id) => {
+                last = id.get();
+                id
+            }
+            None => loop {
+                smallest += 1;
+                let id = NodeId::new(smallest).expect("counting up from 1");
+                if !existing.contains(&id) {
+                    break id;
+                }
+            },
+        };
+        let mut seen = HashSet::new();
+        let mut assigned = 0;
+        let mut assign = |node: &mut NodeMut| {
+            let meta = node.meta_mut();
+            if meta.node_id().is_some_and(|id| seen.insert(id)) {
+                return;
+            }
+            let id = next_id();
+            seen.insert(id);
+            meta.set_node_id(Some(id));
+            assigned += 1;
+        };
+        NodeMut::Doc(self).walk(&mut assign);
+        for footnote in self.footnotes.values_mut() {
+            NodeMut::Footnote(footnote).walk(&mut assign);
+        }
+        for reference in self
+            .references
+            .values_mut()
+            .chain(self.auto_references.values_mut())
+        {
+            assign(&mut NodeMut::Reference(reference));
+        }
+        assigned
+    }
+
+    /// Find the node with the given id, and its path.
+    pub fn find_by_node_id(&self, id: NodeId) -> Option<(NodePath, NodeRef<'_>)> {
+        self.nodes()
+            .find(|(_, node)| node.meta().node_id() == Some(id))
+    }
+}
+
+struct Nodes<'a> {
+    stack: Vec<(NodePath, NodeRef<'a>)>,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = (NodePath, NodeRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = self.stack.pop()?;
+        for (idx, child) in node.children().into_iter().enumerate().rev() {
+            self.stack.push((path.child(idx), child));
+        }
+        Some((path, node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_roundtrip() {
+        for s in [
+            "",
+            "/children/3/children/0/children/2",
+            "/footnotes/a~1b~0c/children/1",
+            "/autoReferences/Some heading",
+        ] {
+            let path: NodePath = s.parse().unwrap();
+            assert_eq!(path.to_string(), s);
+        }
+        let path: NodePath = "/footnotes/a~1b~0c".parse().unwrap();
+        assert_eq!(path.root, PathRoot::Footnote("a/b~c".to_string()));
+
+        for s in [
+            "children/1",
+            "/children",
+            "/children/x",
+            "/children/1/footnotes/a",
+        ] {
+            assert!(s.parse::<NodePath>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_relations() {
+        let path = NodePath::from(vec![1, 2]);
+        assert_eq!(path.parent(), Some(vec![1].into()));
+        assert_eq!(NodePath::root().parent(), None);
+        assert!(path.is_ancestor_of(&path.child(0)));
+        assert!(!path.is_ancestor_of(&path));
+        assert!(!path.is_ancestor_of(&vec![1, 3, 0].into()));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use crate::{attributes::WithNodeIds, inline::Inline};
+
+    fn doc() -> Doc {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {},
+            "footnotes": {"n": {"tag": "footnote", "label": "n", "children": []}},
+            "children": [
+                {"tag": "para", "children": [{"tag": "str", "text": "a"}, {"tag": "str", "text": "b"}]},
+                {"tag": "thematic_break"}
+            ]
+        }"#;
+        serde_json::from_str(s).unwrap()
+    }
+
+    fn str_node(text: &str) -> AstNode {
+        let s = format!(r#"{{"tag": "str", "text": "{text}"}}"#);
+        serde_json::from_str::<Inline>(&s).unwrap().into()
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut d = doc();
+        let path: NodePath = "/children/0/children/1".parse().unwrap();
+        assert!(matches!(d.get(&path), Some(NodeRef::Inline(Inline::Str(s))) if s.text == "b"));
+        assert!(d.get(&"/children/0/children/2".parse().unwrap()).is_none());
+
+        d.insert_at(&path, str_node("c")).unwrap();
+        let removed = d.remove_at(&path.parent().unwrap().child(2)).unwrap();
+        assert!(matches!(removed, AstNode::Inline(Inline::Str(s)) if s.text == "b"));
+
+        let old = d.replace_at(&path, str_node("d")).unwrap();
+        assert!(matches!(old, AstNode::Inline(Inline::Str(s)) if s.text == "c"));
+        assert!(matches!(d.get(&path), Some(NodeRef::Inline(Inline::Str(s))) if s.text == "d"));
+
+        // wrong type for the parent
+        assert!(d.insert_at(&vec![0].into(), str_node("e")).is_err());
+        assert!(d.replace_at(&vec![1].into(), str_node("e")).is_err());
+
+        let footnote: NodePath = "/footnotes/n".parse().unwrap();
+        assert!(matches!(d.remove_at(&footnote), Ok(AstNode::Footnote(_))));
+        assert!(d.footnotes.is_empty());
+        assert!(d.remove_at(&NodePath::root()).is_err());
+    }
+
+    #[test]
+    fn test_node_ids() {
+        let mut d = doc();
+        let n = d.nodes().count();
+        assert_eq!(d.assign_ids(), n);
+        assert_eq!(d.assign_ids(), 0);
+        let ids: HashSet<_> = d.nodes().filter_map(|(_, n)| n.meta().node_id()).collect();
+        assert_eq!(ids.len(), n);
+
+        let path: NodePath = "/children/0/children/1".parse().unwrap();
+        let b = d.get(&path).unwrap().meta().node_id().unwrap();
+        assert_eq!(d.find_by_node_id(b).unwrap().0, path);
+
+        // moving a node keeps its id, and a copy gets a new one
+        let moved = d.remove_at(&path).unwrap();
+        d.insert_at(&"/children/0/children/0".parse().unwrap(), moved.clone())
+            .unwrap();
+        d.insert_at(&path, moved).unwrap();
+        assert_eq!(
+            d.clone().find_by_node_id(b).unwrap().0.to_string(),
+            "/children/0/children/0"
+        );
+        assert_eq!(d.assign_ids(), 1);
+        let copy = d.get(&path).unwrap().meta().node_id().unwrap();
+        assert!(copy > *ids.iter().max().unwrap());
+
+        // only serialized and deserialized when asked for
+        let json = serde_json::to_string(&d).unwrap();
+        assert!(!json.contains("nodeId"));
+        let json = serde_json::to_string(&WithNodeIds(&d)).unwrap();
+        assert!(json.contains("nodeId"));
+        let d2: Doc = serde_json::from_str(&json).unwrap();
+        assert!(d2.find_by_node_id(copy).is_none());
+        let WithNodeIds(d2): WithNodeIds<Doc> = serde_json::from_str(&json).unwrap();
+        assert!(d2.find_by_node_id(copy).is_some());
+    }
+
+    #[test]
+    fn test_node_ids_after_largest() {
+        let mut d = doc();
+        let largest = NodeId::new(u64::MAX).unwrap();
+        d.children[0].set_node_id(NodeId::new(2));
+        d.set_node_id(Some(largest));
+        let n = d.nodes().count();
+        assert_eq!(d.assign_ids(), n - 2);
+        let ids: HashSet<_> = d.nodes().filter_map(|(_, n)| n.meta().node_id()).collect();
+        assert_eq!(ids.len(), n);
+        assert!(ids.contains(&largest));
+        let mut expected: Vec<u64> = (1..=n as u64 - 1).filter(|id| *id != 2).collect();
+        expected.extend([2, u64::MAX]);
+        let mut ids: Vec<u64> = ids.into_iter().map(NodeId::get).collect();
+        ids.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(ids, expected);
+    }
+}
@@ -0,0 +1,738 @@
+//! CSS-like selectors for finding nodes.
+//!
+//! A selector matches nodes by their djot.js `tag`, e.g. `heading` or `bullet_list`, or `*` for any node,
+//! followed by any number of:
+//! - `#id` and `.class`, from the node's attributes
+//! - `[name]`, `[name=value]`, `[name~=word]`, `[name^=prefix]`, `[name$=suffix]`, `[name*=substring]`,
+//!   where `name` is a field of the node such as `level`, `destination`, `lang` or `text`,
+//!   or otherwise an attribute
+//! - `:first-child`, `:last-child`, `:only-child`, `:nth-child(An+B)`, `:nth-last-child(An+B)`,
+//!   `:empty` and `:not(selector)`
+//!
+//! Selectors can be combined with ` ` (descendant), `>` (child), `+` (next sibling) and `~` (later sibling),
+//! and several selectors separated by `,` match any of them.
+//!
+//! ```
+//! # use djot_ast::{query::Selector, Doc};
+//! let selector: Selector = r#"div.warning > para, link[destination^="http:"]"#.parse().unwrap();
+//! assert!(selector.select(&Doc::default()).is_empty());
+//! ```
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{
+    attributes::HasAttributes,
+    block::{Alignment, Block, BulletListStyle, CheckboxStatus},
+    inline::{Inline, SmartPunctuationType},
+    path::NodePath,
+    traverse::NodeRef,
+    Doc, Error, Result,
+};
+
+/// A parsed selector list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector(Vec<Complex>);
+
+/// Compound selectors joined by combinators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Complex {
+    compounds: Vec<Compound>,
+    /// The combinator before each compound after the first.
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    LaterSibling,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Compound {
+    /// `None` for `*`.
+    tag: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+    attributes: Vec<AttributeSelector>,
+    pseudos: Vec<Pseudo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttributeSelector {
+    name: String,
+    /// `None` to only check that the attribute exists.
+    test: Option<(Operator, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equals,
+    Word,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pseudo {
+    NthChild(Nth),
+    NthLastChild(Nth),
+    OnlyChild,
+    Empty,
+    Not(Selector),
+}
+
+/// The `An+B` of `:nth-child`, matching 1-based positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Nth {
+    a: i64,
+    b: i64,
+}
+
+impl Nth {
+    fn matches(&self, position: usize) -> bool {
+        // wide enough that no selector can overflow
+        let diff = position as i128 - i128::from(self.b);
+        match i128::from(self.a) {
+            0 => diff == 0,
+            a => diff % a == 0 && diff / a >= 0,
+        }
+    }
+}
+
+impl FromStr for Selector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser { input: s, pos: 0 };
+        let selector = parser.selector_list()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(selector)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::General(format!(
+            "invalid selector '{}': {message} at offset {}",
+            self.input, self.pos
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    /// Skip whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+        self.pos > start
+    }
+
+    fn selector_list(&mut self) -> Result<Selector> {
+        let mut complexes = vec![self.complex()?];
+        loop {
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(Selector(complexes));
+            }
+            complexes.push(self.complex()?);
+        }
+    }
+
+    fn complex(&mut self) -> Result<Complex> {
+        self.skip_whitespace();
+        let mut complex = Complex {
+            compounds: vec![self.compound()?],
+            combinators: Vec::new(),
+        };
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::LaterSibling,
+                Some(',' | ')') | None => return Ok(complex),
+                Some(_) if had_whitespace => {
+                    complex.combinators.push(Combinator::Descendant);
+                    complex.compounds.push(self.compound()?);
+                    continue;
+                }
+                Some(_) => return Err(self.error("unexpected character")),
+            };
+            self.pos += 1;
+            self.skip_whitespace();
+            complex.combinators.push(combinator);
+            complex.compounds.push(self.compound()?);
+        }
+    }
+
+    fn compound(&mut self) -> Result<Compound> {
+        let start = self.pos;
+        let mut compound = Compound::default();
+        if !self.eat('*') {
+            let tag = self.ident();
+            if !tag.is_empty() {
+                compound.tag = Some(tag);
+            }
+        }
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    compound.ids.push(self.required_ident()?);
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    compound.classes.push(self.required_ident()?);
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    compound.attributes.push(self.attribute()?);
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    compound.pseudos.push(self.pseudo()?);
+                }
+                _ => break,
+            }
+        }
+        if self.pos == start {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(compound)
+    }
+
+    fn ident(&mut self) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.pos += 1;
+                if let Some(escaped) = self.peek() {
+                    out.push(escaped);
+                    self.pos += escaped.len_utf8();
+                }
+            } else if c.is_alphanumeric() || c == '-' || c == '_' {
+                out.push(c);
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        out
+    }
+
+    fn required_ident(&mut self) -> Result<String> {
+        let ident = self.ident();
+        if ident.is_empty() {
+            Err(self.error("expected a name"))
+        } else {
+            Ok(ident)
+        }
+    }
+
+    fn attribute(&mut self) -> Result<AttributeSelector> {
+        self.skip_whitespace();
+        let name = self.required_ident()?;
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(AttributeSelector { name, test: None });
+        }
+        let operator = match self.peek() {
+            Some('=') => Operator::Equals,
+            Some('~') => Operator::Word,
+            Some('^') => Operator::Prefix,
+            Some('$') => Operator::Suffix,
+            Some('*') => Operator::Substring,
+            _ => return Err(self.error("expected an attribute operator")),
+        };
+        if operator != Operator::Equals {
+            self.pos += 1;
+        }
+        self.expect('=')?;
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                self.quoted(quote)?
+            }
+            _ => self.required_ident()?,
+        };
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(AttributeSelector {
+            name,
+            test: Some((operator, value)),
+        })
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String> {
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    if let Some(c) = self.peek() {
+                        out.push(c);
+                        self.pos += c.len_utf8();
+                    }
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn pseudo(&mut self) -> Result<Pseudo> {
+        let name = self.required_ident()?;
+        let pseudo = match name.as_str() {
+            "first-child" => Pseudo::NthChild(Nth { a: 0, b: 1 }),
+            "last-child" => Pseudo::NthLastChild(Nth { a: 0, b: 1 }),
+            "only-child" => Pseudo::OnlyChild,
+            "empty" => Pseudo::Empty,
+            "nth-child" | "nth-last-child" => {
+                self.expect('(')?;
+                let nth = self.nth()?;
+                self.expect(')')?;
+                if name == "nth-child" {
+                    Pseudo::NthChild(nth)
+                } else {
+                    Pseudo::NthLastChild(nth)
+                }
+            }
+            "not" => {
+                self.expect('(')?;
+                let selector = self.selector_list()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                Pseudo::Not(selector)
+            }
+            _ => return Err(self.error(&format!("unknown pseudo-class ':{name}'"))),
+        };
+        Ok(pseudo)
+    }
+
+    fn nth(&mut self) -> Result<Nth> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '+' | '-' | ' '))
+        {
+            self.pos += c.len_utf8();
+        }
+        let text: String = self.input[start..self.pos]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        let invalid = || self.error(&format!("invalid An+B '{text}'"));
+        let nth = match text.as_str() {
+            "odd" => Nth { a: 2, b: 1 },
+            "even" => Nth { a: 2, b: 0 },
+            _ => match text.split_once('n') {
+                None => Nth {
+                    a: 0,
+                    b: text.parse().map_err(|_| invalid())?,
+                },
+                Some((a, b)) => {
+                    let a = match a {
+                        "" | "+" => 1,
+                        "-" => -1,
+                        a => a.parse().map_err(|_| invalid())?,
+                    };
+                    let b = match b {
+                        "" => 0,
+                        b => b
+                            .strip_prefix('+')
+                            .unwrap_or(b)
+                            .parse()
+                            .map_err(|_| invalid())?,
+                    };
+                    Nth { a, b }
+                }
+            },
+        };
+        Ok(nth)
+    }
+}
+
+/// A node being matched, with links to its relatives.
+struct Entry<'a> {
+    path: NodePath,
+    node: NodeRef<'a>,
+    parent: Option<usize>,
+    /// 0-based index among its siblings.
+    index: usize,
+}
+
+impl Selector {
+    /// Find the nodes matching the selector, in the order of [Doc::nodes].
+    pub fn select<'a>(&self, doc: &'a Doc) -> Vec<(NodePath, NodeRef<'a>)> {
+        let mut entries: Vec<Entry<'a>> = Vec::new();
+        let mut by_path: HashMap<NodePath, usize> = HashMap::new();
+        for (path, node) in doc.nodes() {
+            let parent = path.parent().and_then(|p| by_path.get(&p).copied());
+            let index = path.children.last().copied().unwrap_or(0);
+            by_path.insert(path.clone(), entries.len());
+            entries.push(Entry {
+                path,
+                node,
+                parent,
+                index,
+            });
+        }
+
+        // siblings are found through their parent's entry and their index
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (idx, entry) in entries.iter().enumerate() {
+            if let Some(parent) = entry.parent {
+                children.entry(parent).or_default().push(idx);
+            }
+        }
+        let tree = Tree {
+            entries: &entries,
+            children,
+        };
+        (0..entries.len())
+            .filter(|idx| tree.matches(self, *idx))
+            .map(|idx| (entries[idx].path.clone(), entries[idx].node))
+            .collect()
+    }
+}
+
+struct Tree<'t, 'a> {
+    entries: &'t [Entry<'a>],
+    children: HashMap<usize, Vec<usize>>,
+}
+
+impl Tree<'_, '_> {
+    fn matches(&self, selector: &Selector, idx: usize) -> bool {
+        selector
+            .0
+            .iter()
+            .any(|c| self.matches_complex(c, c.compounds.len() - 1, idx))
+    }
+
+    fn siblings(&self, idx: usize) -> &[usize] {
+        match self.entries[idx].parent.and_then(|p| self.children.get(&p)) {
+            Some(siblings) => siblings,
+            None => &[],
+        }
+    }
+
+    fn previous_siblings(&self, idx: usize) -> &[usize] {
+        &self.siblings(idx)[..self.entries[idx].index]
+    }
+
+    /// Check whether the compounds of `complex` up to and including `k` match, ending at `idx`.
+    fn matches_complex(&self, complex: &Complex, k: usize, idx: usize) -> bool {
+        if !self.matches_compound(&complex.compounds[k], idx) {
+            return false;
+        }
+        if k == 0 {
+            return true;
+        }
+        match complex.combinators[k - 1] {
+            Combinator::Child => self.entries[idx]
+                .parent
+                .is_some_and(|p| self.matches_complex(complex, k - 1, p)),
+            Combinator::Descendant => {
+                let mut ancestor = self.entries[idx].parent;
+                while let Some(a) = ancestor {
+                    if self.matches_complex(complex, k - 1, a) {
+                        return true;
+                    }
+                    ancestor = self.entries[a].parent;
+                }
+                false
+            }
+            Combinator::NextSibling => self
+                .previous_siblings(idx)
+                .last()
+                .is_some_and(|s| self.matches_complex(complex, k - 1, *s)),
+            Combinator::LaterSibling => self
+                .previous_siblings(idx)
+                .iter()
+                .any(|s| self.matches_complex(complex, k - 1, *s)),
+        }
+    }
+
+    fn matches_compound(&self, compound: &Compound, idx: usize) -> bool {
+        let entry = &self.entries[idx];
+        let node = entry.node;
+        if compound.tag.as_ref().is_some_and(|t| t != node.tag()) {
+            return false;
+        }
+        let meta = node.meta();
+        if !compound.ids.iter().all(|id| meta.id() == Some(id)) {
+            return false;
+        }
+        let classes = meta.classes();
+        if !compound
+            .classes
+            .iter()
+            .all(|c| classes.contains(&c.as_str()))
+        {
+            return false;
+        }
+        for attribute in &compound.attributes {
+            let Some(value) = property(node, &attribute.name) else {
+                return false;
+            };
+            let matched = match &attribute.test {
+                None => true,
+                Some((Operator::Equals, v)) => value == *v,
+                Some((Operator::Word, v)) => value.split_whitespace().any(|w| w == v),
+                Some((Operator::Prefix, v)) => value.starts_with(v.as_str()),
+                Some((Operator::Suffix, v)) => value.ends_with(v.as_str()),
+                Some((Operator::Substring, v)) => value.contains(v.as_str()),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        compound.pseudos.iter().all(|pseudo| match pseudo {
+            Pseudo::NthChild(nth) => entry.parent.is_some() && nth.matches(entry.index + 1),
+            Pseudo::NthLastChild(nth) => {
+                entry.parent.is_some() && nth.matches(self.siblings(idx).len() - entry.index)
+            }
+            Pseudo::OnlyChild => entry.parent.is_some() && self.siblings(idx).len() == 1,
+            Pseudo::Empty => !self.children.contains_key(&idx),
+            Pseudo::Not(selector) => !self.matches(selector, idx),
+        })
+    }
+}
+
+/// Look up a field of the node by its djot.js JSON name, or otherwise an attribute.
+fn property(node: NodeRef, name: &str) -> Option<String> {
+    let field = match (node, name) {
+        (NodeRef::Block(b), _) => match (b, name) {
+            (Block::Heading(h), "level") => Some(h.level.to_string()),
+            (Block::CodeBlock(c), "lang") => c.lang.clone(),
+            (Block::CodeBlock(c), "text") => Some(c.text.clone()),
+            (Block::RawBlock(r), "format") => Some(r.format.clone()),
+            (Block::RawBlock(r), "text") => Some(r.text.clone()),
+            (Block::OrderedList(l), "style") => Some(l.style.to_string()),
+            (Block::OrderedList(l), "start") => l.start.map(|s| s.to_string()),
+            (Block::OrderedList(l), "tight") => Some(l.tight.to_string()),
+            (Block::BulletList(l), "style") => Some(
+                match l.style {
+                    BulletListStyle::Plus => "+",
+                    BulletListStyle::Dash => "-",
+                    BulletListStyle::Asterisk => "*",
+                }
+                .to_string(),
+            ),
+            (Block::BulletList(l), "tight") => Some(l.tight.to_string()),
+            (Block::TaskList(l), "tight") => Some(l.tight.to_string()),
+            _ => None,
+        },
+        (NodeRef::Inline(i), _) => match (i, name) {
+            (Inline::Str(i), "text") => Some(i.text.clone()),
+            (Inline::Verbatim(i), "text") => Some(i.text.clone()),
+            (Inline::InlineMath(i), "text") => Some(i.text.clone()),
+            (Inline::DisplayMath(i), "text") => Some(i.text.clone()),
+            (Inline::Url(i), "text") => Some(i.text.clone()),
+            (Inline::Email(i), "text") => Some(i.text.clone()),
+            (Inline::FootnoteReference(i), "text") => Some(i.text.clone()),
+            (Inline::RawInline(i), "format") => Some(i.format.clone()),
+            (Inline::RawInline(i), "text") => Some(i.text.clone()),
+            (Inline::Symb(i), "alias") => Some(i.alias.clone()),
+            (Inline::SmartPunctuation(i), "text") => Some(i.text.clone()),
+            (Inline::SmartPunctuation(i), "type") => Some(
+                match i.punc_type {
+                    SmartPunctuationType::LeftSingleQuote => "left_single_quote",
+                    SmartPunctuationType::RightSingleQuote => "right_single_quote",
+                    SmartPunctuationType::LeftDoubleQuote => "left_double_quote",
+                    SmartPunctuationType::RightDoubleQuote => "right_double_quote",
+                    SmartPunctuationType::Ellipses => "ellipses",
+                    SmartPunctuationType::EmDash => "em_dash",
+                    SmartPunctuationType::EnDash => "en_dash",
+                }
+                .to_string(),
+            ),
+            (Inline::Link(l), "destination") => l.destination.clone(),
+            (Inline::Link(l), "reference") => l.reference.clone(),
+            (Inline::Image(l), "destination") => l.destination.clone(),
+            (Inline::Image(l), "reference") => l.reference.clone(),
+            _ => None,
+        },
+        (NodeRef::TaskListItem(i), "checkbox") => Some(
+            match i.checkbox {
+                CheckboxStatus::Checked => "checked",
+                CheckboxStatus::Unchecked => "unchecked",
+            }
+            .to_string(),
+        ),
+        (NodeRef::Row(r), "head") => Some(r.head.to_string()),
+        (NodeRef::Cell(c), "head") => Some(c.head.to_string()),
+        (NodeRef::Cell(c), "align") => Some(
+            match c.align {
+                Alignment::Default => "default",
+                Alignment::Left => "left",
+                Alignment::Right => "right",
+                Alignment::Center => "center",
+            }
+            .to_string(),
+        ),
+        (NodeRef::Footnote(f), "label") => Some(f.label.clone()),
+        (NodeRef::Reference(r), "label") => Some(r.label.clone()),
+        (NodeRef::Reference(r), "destination") => Some(r.destination.clone()),
+        _ => None,
+    };
+    field.or_else(|| node.meta().get_attribute(name).map(str::to_string))
+}
+
+impl Doc {
+    /// Find the nodes matching a [Selector], in the order of [Doc::nodes].
+    pub fn select(&self, selector: &str) -> Result<Vec<(NodePath, NodeRef<'_>)>> {
+        Ok(selector.parse::<Selector>()?.select(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for s in [
+            "heading[level=2]",
+            r#"link[destination^="http:"]"#,
+            "div.warning > para",
+            "table cell:first-child",
+            "#install",
+            "* + para ~ *:not(.a, [b])",
+            "ordered_list > list_item:nth-child(2n + 1):nth-last-child(-n+3)",
+            "span[x='a\\'b']",
+        ] {
+            assert!(s.parse::<Selector>().is_ok(), "{s}");
+        }
+        for s in [
+            "",
+            "para >",
+            "a[",
+            "a[b=]",
+            ":foo",
+            ":nth-child(x)",
+            "a,",
+            ".",
+            "a!",
+        ] {
+            let err = s.parse::<Selector>().unwrap_err().to_string();
+            assert!(err.contains("invalid selector"), "{s}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_nth() {
+        let parse = |s: &str| {
+            let mut parser = Parser { input: s, pos: 0 };
+            parser.nth().unwrap()
+        };
+        let positions = |nth: Nth| (1..=7).filter(|p| nth.matches(*p)).collect::<Vec<_>>();
+        assert_eq!(positions(parse("odd")), vec![1, 3, 5, 7]);
+        assert_eq!(positions(parse("even")), vec![2, 4, 6]);
+        assert_eq!(positions(parse("3")), vec![3]);
+        assert_eq!(positions(parse("3n+1")), vec![1, 4, 7]);
+        assert_eq!(positions(parse("-n + 3")), vec![1, 2, 3]);
+        assert_eq!(positions(parse("n")), (1..=7).collect::<Vec<_>>());
+        assert!(positions(parse("-9223372036854775808")).is_empty());
+        assert!(positions(parse("-n-9223372036854775808")).is_empty());
+        assert_eq!(positions(parse("-9223372036854775808n+1")), vec![1]);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn paths(doc: &Doc, selector: &str) -> Vec<String> {
+        doc.select(selector)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+            "children": [
+                {"tag": "heading", "level": 1, "attributes": {"id": "install"}, "children": []},
+                {"tag": "div", "attributes": {"class": "note warning"}, "children": [
+                    {"tag": "para", "children": [
+                        {"tag": "link", "destination": "http://a", "children": []},
+                        {"tag": "link", "destination": "https://b", "children": []}
+                    ]},
+                    {"tag": "para", "children": []}
+                ]},
+                {"tag": "heading", "level": 2, "children": [{"tag": "str", "text": "x"}]}
+            ]
+        }"#;
+        let doc: Doc = serde_json::from_str(s).unwrap();
+        assert_eq!(paths(&doc, "heading[level=2]"), vec!["/children/2"]);
+        assert_eq!(paths(&doc, "#install"), vec!["/children/0"]);
+        assert_eq!(
+            paths(&doc, "div.warning > para"),
+            vec!["/children/1/children/0", "/children/1/children/1"]
+        );
+        assert!(paths(&doc, "div.warning.other > para").is_empty());
+        assert_eq!(
+            paths(&doc, r#"link[destination^="http:"]"#),
+            vec!["/children/1/children/0/children/0"]
+        );
+        assert_eq!(
+            paths(&doc, "div link:last-child"),
+            vec!["/children/1/children/0/children/1"]
+        );
+        assert_eq!(paths(&doc, "para:empty"), vec!["/children/1/children/1"]);
+        assert_eq!(paths(&doc, "heading + div"), vec!["/children/1"]);
+        assert_eq!(paths(&doc, "heading ~ heading"), vec!["/children/2"]);
+        assert_eq!(
+            paths(&doc, "heading:not([id]), str:only-child"),
+            vec!["/children/2", "/children/2/children/0"]
+        );
+        assert_eq!(paths(&doc, "doc").len(), 1);
+        assert!(paths(&doc, "doc:first-child").is_empty());
+        assert!(doc.select("heading[").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Search and replace on the text of a document.
+use std::ops::Range;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+use crate::{
+    block::Block,
+    inline::{Inline, Str},
+    traverse::NodeMut,
+    Doc,
+};
+
+/// What to search for in [replace_text].
+#[derive(Debug, Clone, Copy)]
+pub enum Pattern<'a> {
+    /// Match this exact text. An empty literal matches nothing.
+    Literal(&'a str),
+    /// Match this regular expression.
+    /// `$name` and `${name}` in the replacement are expanded as in [Regex::replace].
+    #[cfg(feature = "regex")]
+    Regex(&'a Regex),
+}
+
+impl<'a> From<&'a str> for Pattern<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::Literal(value)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl<'a> From<&'a Regex> for Pattern<'a> {
+    fn from(value: &'a Regex) -> Self {
+        Self::Regex(value)
+    }
+}
+
+impl Pattern<'_> {
+    /// Find non-overlapping matches in the text, with their expanded replacements.
+    fn matches(&self, text: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+        match self {
+            Pattern::Literal("") => Vec::new(),
+            Pattern::Literal(lit) => text
+                .match_indices(lit)
+                .map(|(idx, m)| (idx..idx + m.len(), replacement.to_string()))
+                .collect(),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(re) => re
+                .captures_iter(text)
+                .map(|caps| {
+                    let mut out = String::new();
+                    caps.expand(replacement, &mut out);
+                    (caps.get(0).unwrap().range(), out)
+                })
+                .collect(),
+        }
+    }
+
+    fn replace(&self, text: &mut String, replacement: &str) -> usize {
+        let matches = self.matches(text, replacement);
+        for (range, repl) in matches.iter().rev() {
+            text.replace_range(range.clone(), repl);
+        }
+        matches.len()
+    }
+}
+
+/// Which content [replace_text] should leave alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaceOptions {
+    /// Skip [Verbatim](crate::inline::Verbatim) and [CodeBlock](crate::block::CodeBlock) text.
+    pub skip_code: bool,
+    /// Skip [InlineMath](crate::inline::InlineMath) and [DisplayMath](crate::inline::DisplayMath) text.
+    pub skip_math: bool,
+    /// Skip [RawInline](crate::inline::RawInline) and [RawBlock](crate::block::RawBlock) text.
+    pub skip_raw: bool,
+}
+
+/// Replace matches of a pattern in the document's text, returning the number of replacements.
+///
+/// Text in [Str] nodes is searched as runs of adjacent strings,
+/// so matches may span several [Str] nodes, including across the boundaries of
+/// containers like [Emph](crate::inline::Emph) and [Strong](crate::inline::Strong).
+/// Any other inline (e.g. a soft break or verbatim) ends a run.
+/// The replacement is placed in the [Str] where the match starts,
+/// the rest of the match is removed from the following [Str]s,
+/// and all markup is preserved, even if it is left empty.
+///
+/// Verbatim, code block, math and raw text is searched node by node,
+/// unless skipped by the `options`.
+/// Footnotes are included; references, URLs and link destinations are not.
+pub fn replace_text<'p>(
+    doc: &mut Doc,
+    pattern: impl Into<Pattern<'p>>,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> usize {
+    let pattern = pattern.into();
+    let mut count = 0;
+    let mut visit = |node: &mut NodeMut| {
+        let inlines = match node {
+            NodeMut::Block(Block::Para(i)) => &mut i.children,
+            NodeMut::Block(Block::Heading(i)) => &mut i.children,
+            NodeMut::Caption(i) => &mut i.children,
+            NodeMut::Cell(i) => &mut i.children,
+            NodeMut::Term(i) => &mut i.children,
+            NodeMut::Block(Block::CodeBlock(i)) if !options.skip_code => {
+                count += pattern.replace(&mut i.text, replacement);
+                return;
+            }
+            NodeMut::Block(Block::RawBlock(i)) if !options.skip_raw => {
+                count += pattern.replace(&mut i.text, replacement);
+                return;
+            }
+            NodeMut::Inline(inline) => {
+                let text = match inline {
+                    Inline::Verbatim(i) if !options.skip_code => &mut i.text,
+                    Inline::InlineMath(i) if !options.skip_math => &mut i.text,
+                    Inline::DisplayMath(i) if !options.skip_math => &mut i.text,
+                    Inline::RawInline(i) if !options.skip_raw => &mut i.text,
+                    _ => return,
+                };
+                count += pattern.replace(text, replacement);
+                return;
+            }
+            _ => return,
+        };
+
+        let mut runs = vec![Vec::new()];
+        collect_runs(inlines, &mut runs);
+        for run in runs.into_iter().filter(|r| !r.is_empty()) {
+            count += replace_in_run(run, &pattern, replacement);
+        }
+    };
+
+    for footnote in doc.footnotes.values_mut() {
+        NodeMut::Footnote(footnote).walk(&mut visit);
+    }
+    NodeMut::Doc(doc).walk(visit);
+    count
+}
+
+/// Collect the [Str]s in some inlines into runs, starting a new run at every other leaf.
+fn collect_runs<'a>(inlines: &'a mut [Inline], runs: &mut Vec<Vec<&'a mut Str>>) {
+    for inline in inlines {
+        match inline {
+            Inline::Str(s) => runs.last_mut().unwrap().push(s),
+            other => match other.children_mut() {
+                Some(children) => collect_runs(children, runs),
+                None => runs.push(Vec::new()),
+            },
+        }
+    }
+}
+
+fn replace_in_run(mut run: Vec<&mut Str>, pattern: &Pattern, replacement: &str) -> usize {
+    let text: String = run.iter().map(|s| s.text.as_str()).collect();
+    let matches = pattern.matches(&text, replacement);
+    if matches.is_empty() {
+        return 0;
+    }
+
+    // byte ranges of each string in the concatenated text
+    let mut bounds = Vec::with_capacity(run.len());
+    let mut start = 0;
+    for s in run.iter() {
+        bounds.push(start..start + s.text.len());
+        start += s.text.len();
+    }
+    let owner = |offset: usize| {
+        bounds
+            .iter()
+            .position(|b| b.contains(&offset))
+            .unwrap_or(bounds.len() - 1)
+    };
+
+    let mut new_texts = vec![String::new(); run.len()];
+    let copy = |from: usize, to: usize, new_texts: &mut Vec<String>| {
+        for (b, new) in bounds.iter().zip(new_texts.iter_mut()) {
+            let (s, e) = (from.max(b.start), to.min(b.end));
+            if s < e {
+                new.push_str(&text[s..e]);
+            }
+        }
+    };
+
+    let mut cursor = 0;
+    for (range, repl) in matches.iter() {
+        copy(cursor, range.start, &mut new_texts);
+        new_texts[owner(range.start)].push_str(repl);
+        cursor = range.end;
+    }
+    copy(cursor, text.len(), &mut new_texts);
+
+    for (s, new) in run.iter_mut().zip(new_texts) {
+        s.text = new;
+    }
+    matches.len()
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn doc() -> Doc {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {},
+            "children": [{"tag": "para", "children": [
+                {"tag": "str", "text": "say hel"},
+                {"tag": "emph", "children": [{"tag": "str", "text": "lo wo"}]},
+                {"tag": "str", "text": "rld now"},
+                {"tag": "soft_break"},
+                {"tag": "verbatim", "text": "hello world"}
+            ]}]
+        }"#;
+        serde_json::from_str(s).unwrap()
+    }
+
+    fn texts(doc: &Doc) -> Vec<String> {
+        crate::traverse::NodeRef::Doc(doc)
+            .descendants()
+            .filter_map(|n| match n {
+                crate::traverse::NodeRef::Inline(Inline::Str(s)) => Some(s.text.clone()),
+                crate::traverse::NodeRef::Inline(Inline::Verbatim(s)) => Some(s.text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replace_across_strs() {
+        let mut d = doc();
+        let options = ReplaceOptions {
+            skip_code: true,
+            ..Default::default()
+        };
+        assert_eq!(replace_text(&mut d, "hello world", "goodbye", &options), 1);
+        assert_eq!(texts(&d), vec!["say goodbye", "", " now", "hello world"]);
+
+        let mut d = doc();
+        let count = replace_text(&mut d, "hello world", "bye", &Default::default());
+        assert_eq!(count, 2);
+        assert_eq!(texts(&d), vec!["say bye", "", " now", "bye"]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_replace_regex() {
+        let mut d = doc();
+        let re = Regex::new(r"(?<first>\w+)lo w(\w)").unwrap();
+        let options = ReplaceOptions {
+            skip_code: true,
+            ..Default::default()
+        };
+        assert_eq!(replace_text(&mut d, &re, "${first}p-$2", &options), 1);
+        assert_eq!(texts(&d), vec!["say help-o", "", "rld now", "hello world"]);
+    }
+}
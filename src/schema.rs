@@ -0,0 +1,1015 @@
+//! A JSON Schema and TypeScript definitions of the djot.js JSON read by the serde implementations.
+//!
+//! [json_schema] describes [AstNode](crate::AstNode), with a definition for each type under `$defs`,
+//! so that other services can check documents before deserializing them.
+//! Every document the schema accepts deserializes,
+//! and the schema rejects what deserializing rejects, with two exceptions:
+//! where only one type of node can appear, such as the items of a list, deserializing ignores the `tag`,
+//! which the schema requires as djot.js always writes it;
+//! and JSON Schema counts `1.0` as an integer.
+//!
+//! [typescript] writes the same types in the style of djot.js's `ast.ts`.
+//!
+//! ```
+//! let schema: serde_json::Value = serde_json::from_str(&djot_ast::schema::json_schema()).unwrap();
+//! assert_eq!(schema["$defs"]["Table"]["properties"]["children"]["prefixItems"][0]["$ref"], "#/$defs/Caption");
+//! ```
+use std::fmt::Write;
+
+/// The type of a field.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Type {
+    Text,
+    Integer {
+        minimum: u64,
+    },
+    Boolean,
+    /// One of the definitions, by name.
+    Ref(&'static str),
+    Array(&'static Type),
+    /// An array of the given items, then any number of the last type if there is one.
+    Tuple(&'static [Type], Option<&'static Type>),
+    /// An object of values with any keys.
+    Map(&'static Type),
+    Nullable(&'static Type),
+}
+
+#[derive(Debug)]
+pub(crate) struct Field {
+    pub(crate) name: &'static str,
+    pub(crate) ty: Type,
+    pub(crate) required: bool,
+}
+
+const fn required(name: &'static str, ty: Type) -> Field {
+    Field {
+        name,
+        ty,
+        required: true,
+    }
+}
+
+/// A field which can be missing or `null`, for an `Option`.
+const fn optional(name: &'static str, ty: &'static Type) -> Field {
+    Field {
+        name,
+        ty: Type::Nullable(ty),
+        required: false,
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Shape {
+    /// An object with a `tag`, the fields, and the fields of [Meta](crate::attributes::Meta).
+    Node {
+        tag: &'static str,
+        fields: &'static [Field],
+    },
+    Object(&'static [Field]),
+    /// One of the definitions.
+    Union(&'static [&'static str]),
+    /// One of the strings.
+    Strings(&'static [&'static str]),
+    Alias(Type),
+}
+
+#[derive(Debug)]
+pub(crate) struct Def {
+    pub(crate) name: &'static str,
+    pub(crate) shape: Shape,
+}
+
+const fn node(name: &'static str, tag: &'static str, fields: &'static [Field]) -> Def {
+    Def {
+        name,
+        shape: Shape::Node { tag, fields },
+    }
+}
+
+const fn def(name: &'static str, shape: Shape) -> Def {
+    Def { name, shape }
+}
+
+const INLINES: Type = Type::Array(&Type::Ref("Inline"));
+const BLOCKS: Type = Type::Array(&Type::Ref("Block"));
+
+/// The fields every node has, from its [Meta](crate::attributes::Meta).
+const META: &[Field] = &[
+    Field {
+        name: "attributes",
+        ty: Type::Ref("Attributes"),
+        required: false,
+    },
+    Field {
+        name: "autoAttributes",
+        ty: Type::Ref("Attributes"),
+        required: false,
+    },
+    optional("pos", &Type::Ref("Pos")),
+    optional("nodeId", &Type::Integer { minimum: 1 }),
+];
+
+pub(crate) const DEFS: &[Def] = {
+    use Type::*;
+    &[
+        def(
+            "AstNode",
+            Shape::Union(&[
+                "Doc",
+                "Block",
+                "Inline",
+                "ListItem",
+                "TaskListItem",
+                "DefinitionListItem",
+                "Term",
+                "Definition",
+                "Row",
+                "Cell",
+                "Caption",
+                "Footnote",
+                "Reference",
+            ]),
+        ),
+        node(
+            "Doc",
+            "doc",
+            &[
+                required("references", Map(&Ref("Reference"))),
+                required("autoReferences", Map(&Ref("Reference"))),
+                required("footnotes", Map(&Ref("Footnote"))),
+                required("children", BLOCKS),
+            ],
+        ),
+        node(
+            "Reference",
+            "reference",
+            &[required("label", Text), required("destination", Text)],
+        ),
+        node(
+            "Footnote",
+            "footnote",
+            &[required("label", Text), required("children", BLOCKS)],
+        ),
+        def(
+            "Block",
+            Shape::Union(&[
+                "Para",
+                "Heading",
+                "ThematicBreak",
+                "Section",
+                "Div",
+                "CodeBlock",
+                "RawBlock",
+                "BlockQuote",
+                "OrderedList",
+                "BulletList",
+                "TaskList",
+                "DefinitionList",
+                "Table",
+            ]),
+        ),
+        node("Para", "para", &[required("children", INLINES)]),
+        node(
+            "Heading",
+            "heading",
+            &[
+                required("level", Integer { minimum: 0 }),
+                required("children", INLINES),
+            ],
+        ),
+        node("ThematicBreak", "thematic_break", &[]),
+        node("Section", "section", &[required("children", BLOCKS)]),
+        node("Div", "div", &[required("children", BLOCKS)]),
+        node(
+            "CodeBlock",
+            "code_block",
+            &[required("text", Text), optional("lang", &Text)],
+        ),
+        node(
+            "RawBlock",
+            "raw_block",
+            &[required("format", Text), required("text", Text)],
+        ),
+        node("BlockQuote", "block_quote", &[required("children", BLOCKS)]),
+        node(
+            "OrderedList",
+            "ordered_list",
+            &[
+                required("style", Ref("OrderedListStyle")),
+                required("children", Array(&Ref("ListItem"))),
+                optional("start", &Integer { minimum: 0 }),
+                required("tight", Boolean),
+            ],
+        ),
+        def(
+            "OrderedListStyle",
+            Shape::Strings(&[
+                "1.", "1)", "(1)", "a.", "a)", "(a)", "A.", "A)", "(A)", "i.", "i)", "(i)", "I.",
+                "I)", "(I)",
+            ]),
+        ),
+        node(
+            "BulletList",
+            "bullet_list",
+            &[
+                required("tight", Boolean),
+                required("style", Ref("BulletListStyle")),
+                required("children", Array(&Ref("ListItem"))),
+            ],
+        ),
+        def("BulletListStyle", Shape::Strings(&["+", "-", "*"])),
+        node("ListItem", "list_item", &[required("children", BLOCKS)]),
+        node(
+            "TaskList",
+            "task_list",
+            &[
+                required("tight", Boolean),
+                required("children", Array(&Ref("TaskListItem"))),
+            ],
+        ),
+        node(
+            "TaskListItem",
+            "task_list_item",
+            &[
+                required("checkbox", Ref("CheckboxStatus")),
+                required("children", BLOCKS),
+            ],
+        ),
+        def("CheckboxStatus", Shape::Strings(&["checked", "unchecked"])),
+        node(
+            "DefinitionList",
+            "definition_list",
+            &[required("children", Array(&Ref("DefinitionListItem")))],
+        ),
+        node(
+            "DefinitionListItem",
+            "definition_list_item",
+            &[required(
+                "children",
+                Tuple(&[Ref("Term"), Ref("Definition")], None),
+            )],
+        ),
+        node("Term", "term", &[required("children", INLINES)]),
+        node("Definition", "definition", &[required("children", BLOCKS)]),
+        node(
+            "Table",
+            "table",
+            &[required(
+                "children",
+                Tuple(&[Ref("Caption")], Some(&Ref("Row"))),
+            )],
+        ),
+        node("Caption", "caption", &[required("children", INLINES)]),
+        node(
+            "Row",
+            "row",
+            &[
+                required("children", Array(&Ref("Cell"))),
+                required("head", Boolean),
+            ],
+        ),
+        node(
+            "Cell",
+            "cell",
+            &[
+                required("children", INLINES),
+                required("head", Boolean),
+                required("align", Ref("Alignment")),
+            ],
+        ),
+        def(
+            "Alignment",
+            Shape::Strings(&["default", "left", "right", "center"]),
+        ),
+        def(
+            "Inline",
+            Shape::Union(&[
+                "Str",
+                "SoftBreak",
+                "HardBreak",
+                "NonBreakingSpace",
+                "Symb",
+                "Verbatim",
+                "RawInline",
+                "InlineMath",
+                "DisplayMath",
+                "Url",
+                "Email",
+                "FootnoteReference",
+                "SmartPunctuation",
+                "Emph",
+                "Strong",
+                "Link",
+                "Image",
+                "Span",
+                "Mark",
+                "Superscript",
+                "Subscript",
+                "Insert",
+                "Delete",
+                "DoubleQuoted",
+                "SingleQuoted",
+            ]),
+        ),
+        node("Str", "str", &[required("text", Text)]),
+        node("SoftBreak", "soft_break", &[]),
+        node("HardBreak", "hard_break", &[]),
+        node("NonBreakingSpace", "non_breaking_space", &[]),
+        node("Symb", "symb", &[required("alias", Text)]),
+        node("Verbatim", "verbatim", &[required("text", Text)]),
+        node(
+            "RawInline",
+            "raw_inline",
+            &[required("format", Text), required("text", Text)],
+        ),
+        node("InlineMath", "inline_math", &[required("text", Text)]),
+        node("DisplayMath", "display_math", &[required("text", Text)]),
+        node("Url", "url", &[required("text", Text)]),
+        node("Email", "email", &[required("text", Text)]),
+        node(
+            "FootnoteReference",
+            "footnote_reference",
+            &[required("text", Text)],
+        ),
+        node(
+            "SmartPunctuation",
+            "smart_punctuation",
+            &[
+                required("type", Ref("SmartPunctuationType")),
+                required("text", Text),
+            ],
+        ),
+        def(
+            "SmartPunctuationType",
+            Shape::Strings(&[
+                "left_single_quote",
+                "right_single_quote",
+                "left_double_quote",
+                "right_double_quote",
+                "ellipses",
+                "em_dash",
+                "en_dash",
+            ]),
+        ),
+        node("Emph", "emph", &[required("children", INLINES)]),
+        node("Strong", "strong", &[required("children", INLINES)]),
+        node(
+            "Link",
+            "link",
+            &[
+                optional("destination", &Text),
+                optional("reference", &Text),
+                required("children", INLINES),
+            ],
+        ),
+        node(
+            "Image",
+            "image",
+            &[
+                optional("destination", &Text),
+                optional("reference", &Text),
+                required("children", INLINES),
+            ],
+        ),
+        node("Span", "span", &[required("children", INLINES)]),
+        node("Mark", "mark", &[required("children", INLINES)]),
+        node(
+            "Superscript",
+            "superscript",
+            &[required("children", INLINES)],
+        ),
+        node("Subscript", "subscript", &[required("children", INLINES)]),
+        node("Insert", "insert", &[required("children", INLINES)]),
+        node("Delete", "delete", &[required("children", INLINES)]),
+        node(
+            "DoubleQuoted",
+            "double_quoted",
+            &[required("children", INLINES)],
+        ),
+        node(
+            "SingleQuoted",
+            "single_quoted",
+            &[required("children", INLINES)],
+        ),
+        def("Attributes", Shape::Alias(Map(&Text))),
+        def(
+            "Pos",
+            Shape::Object(&[
+                required("start", Ref("SourceLoc")),
+                required("end", Ref("SourceLoc")),
+            ]),
+        ),
+        def(
+            "SourceLoc",
+            Shape::Object(&[
+                required("line", Integer { minimum: 0 }),
+                required("col", Integer { minimum: 0 }),
+                required("offset", Integer { minimum: 0 }),
+            ]),
+        ),
+    ]
+};
+
+/// The fields of a definition, with the fields of [Meta](crate::attributes::Meta) for nodes.
+pub(crate) fn fields(shape: &Shape) -> impl Iterator<Item = &'static Field> {
+    let (fields, meta): (&'static [Field], &'static [Field]) = match *shape {
+        Shape::Node { fields, .. } => (fields, META),
+        Shape::Object(fields) => (fields, &[]),
+        _ => (&[], &[]),
+    };
+    fields.iter().chain(meta)
+}
+
+/// A JSON value, to write the schema without depending on a JSON library.
+enum Json {
+    Bool(bool),
+    Number(u64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn str(s: impl Into<String>) -> Self {
+        Self::Str(s.into())
+    }
+
+    /// Write the value indented by two spaces a level, as `serde_json::to_string_pretty` does.
+    fn write(&self, out: &mut String, indent: usize) {
+        let newline = |out: &mut String, indent: usize| {
+            out.push('\n');
+            out.extend(std::iter::repeat_n("  ", indent));
+        };
+        match self {
+            Json::Bool(b) => write!(out, "{b}").unwrap(),
+            Json::Number(n) => write!(out, "{n}").unwrap(),
+            // The strings are all printable ASCII, which `Debug` quotes as JSON does.
+            Json::Str(s) => write!(out, "{s:?}").unwrap(),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, indent + 1);
+                    item.write(out, indent + 1);
+                }
+                newline(out, indent);
+                out.push(']');
+            }
+            Json::Object(entries) if entries.is_empty() => out.push_str("{}"),
+            Json::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, indent + 1);
+                    write!(out, "{key:?}: ").unwrap();
+                    value.write(out, indent + 1);
+                }
+                newline(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn type_schema(ty: &Type) -> Json {
+    let typed = |name: &str, mut rest: Vec<(&'static str, Json)>| {
+        rest.insert(0, ("type", Json::str(name)));
+        Json::Object(rest)
+    };
+    match *ty {
+        Type::Text => typed("string", vec![]),
+        Type::Integer { minimum } => typed("integer", vec![("minimum", Json::Number(minimum))]),
+        Type::Boolean => typed("boolean", vec![]),
+        Type::Ref(name) => Json::Object(vec![("$ref", Json::str(format!("#/$defs/{name}")))]),
+        Type::Array(item) => typed("array", vec![("items", type_schema(item))]),
+        Type::Tuple(prefix, rest) => typed(
+            "array",
+            vec![
+                (
+                    "prefixItems",
+                    Json::Array(prefix.iter().map(type_schema).collect()),
+                ),
+                ("minItems", Json::Number(prefix.len() as u64)),
+                ("items", rest.map_or(Json::Bool(false), type_schema)),
+            ],
+        ),
+        Type::Map(value) => typed("object", vec![("additionalProperties", type_schema(value))]),
+        Type::Nullable(ty) => Json::Object(vec![(
+            "anyOf",
+            Json::Array(vec![type_schema(ty), typed("null", vec![])]),
+        )]),
+    }
+}
+
+/// The definition with the given name.
+pub(crate) fn find_def(name: &str) -> &'static Def {
+    DEFS.iter()
+        .find(|d| d.name == name)
+        .unwrap_or_else(|| panic!("no definition `{name}`"))
+}
+
+/// The tags and names of the nodes in a union, including those of the unions within it.
+pub(crate) fn union_nodes(names: &[&'static str], out: &mut Vec<(&'static str, &'static str)>) {
+    for &name in names {
+        match find_def(name).shape {
+            Shape::Node { tag, .. } => out.push((tag, name)),
+            Shape::Union(names) => union_nodes(names, out),
+            _ => unreachable!("`{name}` is not a node"),
+        }
+    }
+}
+
+fn def_schema(def: &Def) -> Json {
+    match def.shape {
+        Shape::Node { .. } | Shape::Object(_) => {
+            let mut properties = Vec::new();
+            let mut required = Vec::new();
+            if let Shape::Node { tag, .. } = def.shape {
+                properties.push(("tag", Json::Object(vec![("const", Json::str(tag))])));
+                required.push(Json::str("tag"));
+            }
+            for field in fields(&def.shape) {
+                properties.push((field.name, type_schema(&field.ty)));
+                if field.required {
+                    required.push(Json::str(field.name));
+                }
+            }
+            Json::Object(vec![
+                ("type", Json::str("object")),
+                ("properties", Json::Object(properties)),
+                ("required", Json::Array(required)),
+            ])
+        }
+        // Choosing the alternative by its tag, rather than with `oneOf`,
+        // keeps validators from checking the children of a node against every alternative.
+        Shape::Union(names) => {
+            let mut nodes = Vec::new();
+            union_nodes(names, &mut nodes);
+            Json::Object(vec![
+                ("type", Json::str("object")),
+                (
+                    "properties",
+                    Json::Object(vec![(
+                        "tag",
+                        Json::Object(vec![(
+                            "enum",
+                            Json::Array(nodes.iter().map(|(tag, _)| Json::str(*tag)).collect()),
+                        )]),
+                    )]),
+                ),
+                ("required", Json::Array(vec![Json::str("tag")])),
+                (
+                    "allOf",
+                    Json::Array(
+                        nodes
+                            .iter()
+                            .map(|(tag, name)| {
+                                Json::Object(vec![
+                                    (
+                                        "if",
+                                        Json::Object(vec![(
+                                            "properties",
+                                            Json::Object(vec![(
+                                                "tag",
+                                                Json::Object(vec![("const", Json::str(*tag))]),
+                                            )]),
+                                        )]),
+                                    ),
+                                    ("then", type_schema(&Type::Ref(name))),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+            ])
+        }
+        Shape::Strings(values) => Json::Object(vec![
+            ("type", Json::str("string")),
+            (
+                "enum",
+                Json::Array(values.iter().map(|v| Json::str(*v)).collect()),
+            ),
+        ]),
+        Shape::Alias(ty) => type_schema(&ty),
+    }
+}
+
+/// A JSON Schema (draft 2020-12) for [AstNode](crate::AstNode), with each type of node under `$defs`.
+///
+/// Use `"$ref": "#/$defs/Doc"`, for example, to accept only documents.
+pub fn json_schema() -> String {
+    let schema = Json::Object(vec![
+        (
+            "$schema",
+            Json::str("https://json-schema.org/draft/2020-12/schema"),
+        ),
+        ("title", Json::str("djot AST")),
+        ("$ref", Json::str("#/$defs/AstNode")),
+        (
+            "$defs",
+            Json::Object(DEFS.iter().map(|d| (d.name, def_schema(d))).collect()),
+        ),
+    ]);
+    let mut out = String::new();
+    schema.write(&mut out, 0);
+    out.push('\n');
+    out
+}
+
+fn ts_type(ty: &Type) -> String {
+    match *ty {
+        Type::Text => "string".to_string(),
+        Type::Integer { .. } => "number".to_string(),
+        Type::Boolean => "boolean".to_string(),
+        Type::Ref(name) => name.to_string(),
+        Type::Array(item @ Type::Nullable(_)) => format!("({})[]", ts_type(item)),
+        Type::Array(item) => format!("{}[]", ts_type(item)),
+        Type::Tuple(prefix, rest) => {
+            let mut items: Vec<_> = prefix.iter().map(ts_type).collect();
+            items.extend(rest.map(|r| format!("...{}[]", ts_type(r))));
+            format!("[{}]", items.join(", "))
+        }
+        Type::Map(value) => format!("Record<string, {}>", ts_type(value)),
+        Type::Nullable(ty) => format!("{} | null", ts_type(ty)),
+    }
+}
+
+/// TypeScript definitions of the types in [json_schema], in the style of djot.js's `ast.ts`.
+pub fn typescript() -> String {
+    let mut out = String::from("// The djot AST, as read by the djot_ast crate.\n");
+    for def in DEFS {
+        out.push('\n');
+        let alternatives = |out: &mut String, items: Vec<String>| {
+            write!(out, "export type {} =", def.name).unwrap();
+            for item in items {
+                write!(out, "\n  | {item}").unwrap();
+            }
+            out.push_str(";\n");
+        };
+        match def.shape {
+            Shape::Node { .. } | Shape::Object(_) => {
+                writeln!(out, "export interface {} {{", def.name).unwrap();
+                if let Shape::Node { tag, .. } = def.shape {
+                    writeln!(out, "  tag: {tag:?};").unwrap();
+                }
+                for field in fields(&def.shape) {
+                    let optional = if field.required { "" } else { "?" };
+                    writeln!(out, "  {}{optional}: {};", field.name, ts_type(&field.ty)).unwrap();
+                }
+                out.push_str("}\n");
+            }
+            Shape::Union(names) => {
+                alternatives(&mut out, names.iter().map(|n| n.to_string()).collect())
+            }
+            Shape::Strings(values) => {
+                alternatives(&mut out, values.iter().map(|v| format!("{v:?}")).collect())
+            }
+            Shape::Alias(ty) => {
+                writeln!(out, "export type {} = {};", def.name, ts_type(&ty)).unwrap()
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use jsonschema::Validator;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{traverse::NodeRef, AstNode};
+
+    fn validator() -> Validator {
+        let schema: Value = serde_json::from_str(&json_schema()).unwrap();
+        jsonschema::draft202012::new(&schema).unwrap()
+    }
+
+    /// The smallest value of a type, with only the required fields of objects.
+    fn example(ty: &Type) -> Value {
+        match *ty {
+            Type::Text => json!(""),
+            Type::Integer { minimum } => json!(minimum),
+            Type::Boolean => json!(false),
+            Type::Ref(name) => example_def(find_def(name)),
+            Type::Array(_) => json!([]),
+            Type::Tuple(prefix, _) => prefix.iter().map(example).collect(),
+            Type::Map(_) => json!({}),
+            Type::Nullable(_) => Value::Null,
+        }
+    }
+
+    fn example_def(def: &Def) -> Value {
+        match def.shape {
+            Shape::Node { .. } | Shape::Object(_) => {
+                let mut object = serde_json::Map::new();
+                if let Shape::Node { tag, .. } = def.shape {
+                    object.insert("tag".into(), json!(tag));
+                }
+                for field in fields(&def.shape).filter(|f| f.required) {
+                    object.insert(field.name.into(), example(&field.ty));
+                }
+                object.into()
+            }
+            Shape::Union(names) => example_def(find_def(names[0])),
+            Shape::Strings(values) => json!(values[0]),
+            Shape::Alias(ty) => example(&ty),
+        }
+    }
+
+    fn deserializes(value: &Value) -> bool {
+        serde_json::from_value::<AstNode>(value.clone()).is_ok()
+    }
+
+    #[test]
+    fn test_fixtures() {
+        let validator = validator();
+        for json in [
+            include_str!("../tests/fixtures/readme.dj.json"),
+            include_str!("../tests/fixtures/pandoc-manual.json"),
+        ] {
+            let value: Value = serde_json::from_str(json).unwrap();
+            assert!(validator.is_valid(&value));
+        }
+    }
+
+    #[test]
+    fn test_nodes() {
+        let validator = validator();
+        for def in DEFS {
+            let Shape::Node { tag, .. } = def.shape else {
+                continue;
+            };
+            let value = example_def(def);
+            assert!(validator.is_valid(&value), "{value}");
+            let node: AstNode = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(NodeRef::from(&node).tag(), tag);
+            assert!(validator.is_valid(&serde_json::to_value(&node).unwrap()));
+
+            for field in fields(&def.shape).filter(|f| f.required) {
+                let mut missing = value.clone();
+                missing.as_object_mut().unwrap().remove(field.name);
+                assert!(!validator.is_valid(&missing), "{missing}");
+                assert!(!deserializes(&missing), "{missing}");
+            }
+            for field in fields(&def.shape).filter(|f| !f.required) {
+                let mut present = value.clone();
+                let ty = match field.ty {
+                    Type::Nullable(ty) => ty,
+                    ref ty => ty,
+                };
+                present[field.name] = example(ty);
+                assert!(validator.is_valid(&present), "{present}");
+                assert!(deserializes(&present), "{present}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let validator = validator();
+        let mut cell = example_def(find_def("Cell"));
+        let mut list = example_def(find_def("OrderedList"));
+        for (value, valid) in [
+            ("1.", true),
+            ("(I)", true),
+            ("(1.", false),
+            ("1.x", false),
+            ("b)", false),
+        ] {
+            list["style"] = json!(value);
+            assert_eq!(validator.is_valid(&list), valid, "{list}");
+            assert_eq!(deserializes(&list), valid, "{list}");
+        }
+        for value in ["default", "left", "right", "center"] {
+            cell["align"] = json!(value);
+            assert!(validator.is_valid(&cell) && deserializes(&cell), "{cell}");
+        }
+        cell["align"] = json!("justify");
+        assert!(!validator.is_valid(&cell) && !deserializes(&cell), "{cell}");
+    }
+
+    #[test]
+    fn test_table_and_definition_list_item() {
+        let validator = validator();
+        let caption = example_def(find_def("Caption"));
+        let row = example_def(find_def("Row"));
+        let term = example_def(find_def("Term"));
+        let definition = example_def(find_def("Definition"));
+        for (children, valid) in [
+            (json!([caption, row, row]), true),
+            (json!([]), false),
+            (json!([row]), false),
+            (json!([caption, caption]), false),
+            (json!([caption, row, caption]), false),
+        ] {
+            let table = json!({"tag": "table", "children": children});
+            assert_eq!(validator.is_valid(&table), valid, "{table}");
+            assert_eq!(deserializes(&table), valid, "{table}");
+        }
+        for (children, valid) in [
+            (json!([term, definition]), true),
+            (json!([term]), false),
+            (json!([term, definition, definition]), false),
+        ] {
+            let item = json!({"tag": "definition_list_item", "children": children});
+            assert_eq!(validator.is_valid(&item), valid, "{item}");
+            assert_eq!(deserializes(&item), valid, "{item}");
+        }
+    }
+
+    /// The tags of the interfaces in TypeScript definitions, and the names of their fields,
+    /// including those of the interfaces they extend.
+    fn ts_interfaces(ts: &str) -> BTreeMap<String, BTreeSet<String>> {
+        // Remove comments, then read each `interface Name extends Base<T>, ... { field?: type; ... }`.
+        let mut code = String::new();
+        let mut rest = ts;
+        while let Some(start) = rest.find(['/']) {
+            code.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(comment) = rest.strip_prefix("//") {
+                rest = comment.find('\n').map_or("", |end| &comment[end..]);
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+            } else {
+                code.push('/');
+                rest = &rest[1..];
+            }
+        }
+        code.push_str(rest);
+
+        let mut interfaces = BTreeMap::new();
+        let mut tags = BTreeMap::new();
+        let mut rest = code.as_str();
+        while let Some(start) = rest.find("interface ") {
+            rest = &rest[start + "interface ".len()..];
+            let Some(open) = rest.find('{') else { break };
+            let header = &rest[..open];
+            let name = header
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap();
+            let bases: Vec<String> = header
+                .split_once("extends")
+                .map(|(_, bases)| {
+                    let mut depth = 0;
+                    let mut names = vec![String::new()];
+                    for c in bases.chars() {
+                        match c {
+                            '<' => depth += 1,
+                            '>' => depth -= 1,
+                            ',' if depth == 0 => names.push(String::new()),
+                            c if depth == 0 && !c.is_whitespace() => {
+                                names.last_mut().unwrap().push(c)
+                            }
+                            _ => {}
+                        }
+                    }
+                    names
+                })
+                .unwrap_or_default();
+
+            let mut depth = 0;
+            let mut fields = BTreeSet::new();
+            let mut entry = String::new();
+            let mut end = rest.len();
+            for (i, c) in rest[open + 1..].char_indices() {
+                let closing = depth == 0 && c == '}';
+                match c {
+                    _ if closing => end = open + 1 + i,
+                    '{' | '(' | '[' | '<' => depth += 1,
+                    '}' | ')' | ']' | '>' => depth -= 1,
+                    _ => {}
+                }
+                if closing || depth == 0 && matches!(c, ';' | '\n' | ',') {
+                    if let Some((field, value)) = entry.split_once(':') {
+                        let field = field.trim().trim_end_matches('?').to_string();
+                        if field == "tag" {
+                            tags.insert(
+                                name.to_string(),
+                                value.trim().trim_matches(['"', '\'']).to_string(),
+                            );
+                        }
+                        fields.insert(field);
+                    }
+                    entry.clear();
+                    if closing {
+                        break;
+                    }
+                } else {
+                    entry.push(c);
+                }
+            }
+            interfaces.insert(name.to_string(), (bases, fields));
+            rest = &rest[end..];
+        }
+
+        fn all_fields(
+            name: &str,
+            interfaces: &BTreeMap<String, (Vec<String>, BTreeSet<String>)>,
+            out: &mut BTreeSet<String>,
+        ) {
+            if let Some((bases, fields)) = interfaces.get(name) {
+                out.extend(fields.iter().cloned());
+                for base in bases {
+                    all_fields(base, interfaces, out);
+                }
+            }
+        }
+        tags.into_iter()
+            .map(|(name, tag)| {
+                let mut fields = BTreeSet::new();
+                all_fields(&name, &interfaces, &mut fields);
+                fields.remove("tag");
+                (tag, fields)
+            })
+            .collect()
+    }
+
+    /// Differences between the tags and fields of TypeScript definitions and those of the schema.
+    ///
+    /// Optional fields of ours missing from theirs are allowed, as djot.js adds nothing such as `nodeId`.
+    fn ts_differences(ts: &str) -> Vec<String> {
+        let theirs = ts_interfaces(ts);
+        let ours: BTreeMap<_, _> = DEFS
+            .iter()
+            .filter_map(|d| match d.shape {
+                Shape::Node { tag, .. } => {
+                    Some((tag.to_string(), fields(&d.shape).collect::<Vec<_>>()))
+                }
+                _ => None,
+            })
+            .collect();
+        let mut differences = Vec::new();
+        for tag in ours.keys().filter(|t| !theirs.contains_key(*t)) {
+            differences.push(format!("no interface for `{tag}`"));
+        }
+        for (tag, their_fields) in &theirs {
+            let Some(our_fields) = ours.get(tag) else {
+                differences.push(format!("`{tag}` is not in the schema"));
+                continue;
+            };
+            for field in their_fields {
+                if !our_fields.iter().any(|f| f.name == field) {
+                    differences.push(format!("`{tag}` has `{field}`, which the schema does not"));
+                }
+            }
+            for field in our_fields.iter().filter(|f| f.required) {
+                if !their_fields.contains(field.name) {
+                    differences.push(format!(
+                        "`{tag}` lacks `{}`, which the schema requires",
+                        field.name
+                    ));
+                }
+            }
+        }
+        differences
+    }
+
+    #[test]
+    fn test_typescript() {
+        assert_eq!(ts_differences(&typescript()), Vec::<String>::new());
+
+        let ts = r#"
+            // A comment with interface Ignored { tag: "ignored" }
+            interface HasAttributes { attributes?: Attributes; pos?: Pos }
+            interface HasChildren<A> { children: A[] }
+            export interface Para extends HasAttributes, HasChildren<Inline> {
+              tag: "para";
+              extra: { nested: string };
+            }
+        "#;
+        let interfaces = ts_interfaces(ts);
+        assert_eq!(interfaces.keys().collect::<Vec<_>>(), ["para"]);
+        assert_eq!(
+            interfaces["para"].iter().collect::<Vec<_>>(),
+            ["attributes", "children", "extra", "pos"]
+        );
+    }
+
+    #[test]
+    fn test_ast_ts() {
+        let ts = include_str!("../tests/fixtures/ast.ts");
+        assert_eq!(ts_differences(ts), Vec::<String>::new());
+    }
+
+    /// Compares the schema with another copy of djot.js's `ast.ts`, such as a checkout's,
+    /// whose path must be given in `DJOT_AST_TS`:
+    ///
+    /// `DJOT_AST_TS=path/to/djot.js/src/ast.ts cargo test -- --ignored test_djot_js_ast_ts`
+    #[test]
+    #[ignore = "needs a copy of djot.js's ast.ts in DJOT_AST_TS"]
+    fn test_djot_js_ast_ts() {
+        let path =
+            std::env::var_os("DJOT_AST_TS").expect("DJOT_AST_TS should be the path of ast.ts");
+        let ts = std::fs::read_to_string(path).unwrap();
+        assert_eq!(ts_differences(&ts), Vec::<String>::new());
+    }
+}
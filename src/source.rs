@@ -0,0 +1,242 @@
+//! Converting between djot.js source positions and offsets into the source text.
+//!
+//! djot.js runs on JavaScript strings, so [SourceLoc::offset] counts UTF-16 code units,
+//! [SourceLoc::line] counts from 1, and [SourceLoc::col] counts UTF-16 code units from 1.
+//! A [Pos] includes the character at its end location.
+use std::ops::Range;
+
+use crate::attributes::{HasAttributes, Pos, SourceLoc};
+
+/// A zero-based line and UTF-16 column, as used by the Language Server Protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf16Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// The same point in the source, counted in each unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Point {
+    line: usize,
+    byte: usize,
+    char: usize,
+    utf16: usize,
+}
+
+impl Point {
+    fn advance(&mut self, c: char) {
+        self.byte += c.len_utf8();
+        self.char += 1;
+        self.utf16 += c.len_utf16();
+    }
+}
+
+/// An index of the line starts in some source text, for converting between positions.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    lines: Vec<Point>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the lines of the source from which a document was parsed.
+    pub fn new(source: &'a str) -> Self {
+        let mut lines = vec![Point {
+            line: 0,
+            byte: 0,
+            char: 0,
+            utf16: 0,
+        }];
+        let mut point = lines[0];
+        for c in source.chars() {
+            point.advance(c);
+            if c == '\n' {
+                point.line += 1;
+                lines.push(point);
+            }
+        }
+        Self { source, lines }
+    }
+
+    /// Get the source text.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Find the point at which `key` reaches `target`,
+    /// or `None` if it is out of range or falls inside a character.
+    fn point(&self, target: usize, key: fn(&Point) -> usize) -> Option<Point> {
+        let line = self
+            .lines
+            .partition_point(|l| key(l) <= target)
+            .checked_sub(1)?;
+        let mut point = self.lines[line];
+        let line_end = self
+            .lines
+            .get(line + 1)
+            .map_or(self.source.len(), |l| l.byte);
+        for c in self.source[point.byte..line_end].chars() {
+            if key(&point) >= target {
+                break;
+            }
+            point.advance(c);
+        }
+        (key(&point) == target).then_some(point)
+    }
+
+    fn point_at(&self, loc: &SourceLoc) -> Option<Point> {
+        self.point(usize::try_from(loc.offset).ok()?, |p| p.utf16)
+    }
+
+    fn loc(&self, point: Point) -> SourceLoc {
+        let col = point.utf16 - self.lines[point.line].utf16;
+        SourceLoc {
+            line: point.line as u64 + 1,
+            col: col as u64 + 1,
+            offset: point.utf16 as u64,
+        }
+    }
+
+    /// Get the byte offset of a location.
+    pub fn byte_offset(&self, loc: &SourceLoc) -> Option<usize> {
+        self.point_at(loc).map(|p| p.byte)
+    }
+
+    /// Get the character (i.e. Unicode scalar value) offset of a location.
+    pub fn char_offset(&self, loc: &SourceLoc) -> Option<usize> {
+        self.point_at(loc).map(|p| p.char)
+    }
+
+    /// Get the zero-based line and UTF-16 column of a location.
+    pub fn utf16_position(&self, loc: &SourceLoc) -> Option<Utf16Position> {
+        let point = self.point_at(loc)?;
+        Some(Utf16Position {
+            line: point.line.try_into().ok()?,
+            character: (point.utf16 - self.lines[point.line].utf16)
+                .try_into()
+                .ok()?,
+        })
+    }
+
+    /// Get the location of a byte offset.
+    pub fn loc_from_byte(&self, offset: usize) -> Option<SourceLoc> {
+        self.point(offset, |p| p.byte).map(|p| self.loc(p))
+    }
+
+    /// Get the location of a character offset.
+    pub fn loc_from_char(&self, offset: usize) -> Option<SourceLoc> {
+        self.point(offset, |p| p.char).map(|p| self.loc(p))
+    }
+
+    /// Get the location of a zero-based line and UTF-16 column.
+    pub fn loc_from_utf16(&self, position: Utf16Position) -> Option<SourceLoc> {
+        let line = self.lines.get(position.line as usize)?;
+        let point = self.point(line.utf16 + position.character as usize, |p| p.utf16)?;
+        (point.line == line.line).then(|| self.loc(point))
+    }
+
+    /// Get the byte range of a position, including the character at its end.
+    pub fn byte_range(&self, pos: &Pos) -> Option<Range<usize>> {
+        let start = self.byte_offset(&pos.start)?;
+        let end = self.byte_offset(&pos.end)?;
+        let end_len = self.source[end..].chars().next().map_or(0, char::len_utf8);
+        Some(start..end + end_len)
+    }
+
+    /// Get the UTF-16 range of a position, excluding its end: the form used by the Language Server Protocol.
+    pub fn utf16_range(&self, pos: &Pos) -> Option<Range<Utf16Position>> {
+        self.utf16_range_of_bytes(self.byte_range(pos)?)
+    }
+
+    /// Convert a byte range into a UTF-16 range.
+    pub fn utf16_range_of_bytes(&self, range: Range<usize>) -> Option<Range<Utf16Position>> {
+        let start = self.utf16_position(&self.loc_from_byte(range.start)?)?;
+        let end = self.utf16_position(&self.loc_from_byte(range.end)?)?;
+        Some(start..end)
+    }
+
+    /// Get the source text of a position.
+    pub fn slice(&self, pos: &Pos) -> Option<&'a str> {
+        self.byte_range(pos).map(|r| &self.source[r])
+    }
+
+    /// Get the source text of a node, if it has a position.
+    pub fn slice_node(&self, node: &impl HasAttributes) -> Option<&'a str> {
+        self.slice(node.pos()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let source = "a😀é\nxy";
+        let index = LineIndex::new(source);
+
+        // 'é' is 1 UTF-16 unit after the 2-unit emoji
+        let loc = SourceLoc {
+            line: 1,
+            col: 4,
+            offset: 3,
+        };
+        assert_eq!(index.byte_offset(&loc), Some(5));
+        assert_eq!(index.char_offset(&loc), Some(2));
+        assert_eq!(
+            index.utf16_position(&loc),
+            Some(Utf16Position {
+                line: 0,
+                character: 3
+            })
+        );
+        assert_eq!(index.loc_from_byte(5), Some(loc));
+        assert_eq!(index.loc_from_char(2), Some(loc));
+        assert_eq!(
+            index.loc_from_utf16(Utf16Position {
+                line: 0,
+                character: 3
+            }),
+            Some(loc)
+        );
+
+        // inside the emoji
+        assert_eq!(index.loc_from_byte(2), None);
+        assert_eq!(
+            index.byte_offset(&SourceLoc {
+                line: 1,
+                col: 3,
+                offset: 2
+            }),
+            None
+        );
+
+        let y = index.loc_from_char(5).unwrap();
+        assert_eq!((y.line, y.col, y.offset), (2, 2, 6));
+        assert_eq!(
+            index.loc_from_utf16(Utf16Position {
+                line: 0,
+                character: 10
+            }),
+            None
+        );
+
+        let pos = Pos {
+            start: index.loc_from_char(1).unwrap(),
+            end: index.loc_from_char(2).unwrap(),
+        };
+        assert_eq!(index.slice(&pos), Some("😀é"));
+        assert_eq!(
+            index.utf16_range(&pos),
+            Some(
+                Utf16Position {
+                    line: 0,
+                    character: 1
+                }..Utf16Position {
+                    line: 0,
+                    character: 4
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Splitting a document into several smaller documents.
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    attributes::{HasAttributes, HasMeta},
+    block::{Block, Section},
+    extract::UsedLabels,
+    inline::Inline,
+    text::inline_text,
+    traverse::{NodeMut, NodeRef},
+    Doc,
+};
+
+/// Name of the page holding any content which precedes the first split heading.
+pub const INDEX_PAGE: &str = "index";
+
+/// Attribute of each split document holding the name of its page.
+pub const PAGE_ATTRIBUTE: &str = "page";
+
+/// Attribute of each split document holding the text of the headings of its trail,
+/// separated by [BREADCRUMB_SEPARATOR].
+pub const BREADCRUMB_ATTRIBUTE: &str = "breadcrumb";
+
+/// Separator of the headings in the [BREADCRUMB_ATTRIBUTE].
+pub const BREADCRUMB_SEPARATOR: &str = " > ";
+
+#[derive(Debug)]
+struct Page {
+    name: String,
+    breadcrumb: Vec<String>,
+    children: Vec<Block>,
+}
+
+struct Splitter {
+    level: u64,
+    trail: Vec<(u64, String)>,
+    pages: Vec<Page>,
+    /// Ids in the document and page names given so far, which generated names must avoid.
+    taken: HashSet<String>,
+}
+
+impl Splitter {
+    fn page_name(&mut self, meta_id: Option<&str>) -> String {
+        if let Some(id) = meta_id {
+            return id.to_string();
+        }
+        let mut n = self.pages.len();
+        loop {
+            let name = format!("page-{n}");
+            if self.taken.insert(name.clone()) {
+                return name;
+            }
+            n += 1;
+        }
+    }
+
+    /// Start a new page for a heading of the given level, returning its index.
+    fn start_page(&mut self, level: u64, name: String, title: String) -> usize {
+        self.trail.retain(|(l, _)| *l < level);
+        self.trail.push((level, title));
+        self.pages.push(Page {
+            name,
+            breadcrumb: self.trail.iter().map(|(_, n)| n.clone()).collect(),
+            children: Vec::new(),
+        });
+        self.pages.len() - 1
+    }
+
+    /// Distribute blocks between pages; any which precede the first split are returned.
+    fn split(&mut self, blocks: &[Block]) -> Vec<Block> {
+        let mut kept = Vec::new();
+        let first_page = self.pages.len();
+        for block in blocks {
+            match block {
+                Block::Section(s) => match section_level(s) {
+                    Some(l) if l == self.level => {
+                        let name = self.page_name(s.id());
+                        let idx = self.start_page(l, name, section_title(s));
+                        self.pages[idx].children.push(block.clone());
+                    }
+                    Some(l) if l < self.level => {
+                        let name = self.page_name(s.id());
+                        let idx = self.start_page(l, name, section_title(s));
+                        let mut intro = Section::default();
+                        *intro.meta_mut() = s.meta().clone();
+                        let (heading, rest) = s.children.split_at(1);
+                        intro.children = heading.to_vec();
+                        intro.children.extend(self.split(rest));
+                        self.pages[idx].children.push(intro.into());
+                    }
+                    _ => self.keep(first_page, &mut kept, block),
+                },
+                Block::Heading(h) if h.level <= self.level => {
+                    let name = self.page_name(h.id());
+                    let idx = self.start_page(h.level, name, inline_text(&h.children));
+                    self.pages[idx].children.push(block.clone());
+                }
+                _ => self.keep(first_page, &mut kept, block),
+            }
+        }
+        kept
+    }
+
+    /// Add a block to the latest page if one was started since `first_page`, otherwise to `kept`.
+    fn keep(&mut self, first_page: usize, kept: &mut Vec<Block>, block: &Block) {
+        if self.pages.len() > first_page {
+            if let Some(page) = self.pages.last_mut() {
+                page.children.push(block.clone());
+                return;
+            }
+        }
+        kept.push(block.clone());
+    }
+}
+
+fn section_title(section: &Section) -> String {
+    match section.children.first() {
+        Some(Block::Heading(h)) => inline_text(&h.children),
+        _ => String::new(),
+    }
+}
+
+fn section_level(section: &Section) -> Option<u64> {
+    match section.children.first() {
+        Some(Block::Heading(h)) => Some(h.level),
+        _ => None,
+    }
+}
+
+/// Split a document into one document per section whose heading is at or above the given level.
+///
+/// Sections with a heading deeper than `level` stay inside their parent's document.
+/// Content which precedes the first split heading goes into a page named [INDEX_PAGE];
+/// other pages are named after the `id` attribute of their section or heading,
+/// or, without one, `page-N`, numbered so as not to clash with any id in the document.
+/// Each document records its page name in the [PAGE_ATTRIBUTE] attribute,
+/// and the heading text of its ancestors and itself in the [BREADCRUMB_ATTRIBUTE] attribute.
+///
+/// Each document gets copies of the references and footnotes it uses.
+/// Links to `#id` which now live on a different page are rewritten to `page#id`.
+pub fn split_by_heading(doc: &Doc, level: u64) -> Vec<Doc> {
+    let mut splitter = Splitter {
+        level,
+        trail: Vec::new(),
+        pages: Vec::new(),
+        taken: NodeRef::Doc(doc)
+            .descendants()
+            .filter_map(|n| n.meta().id().map(str::to_string))
+            .chain([INDEX_PAGE.to_string()])
+            .collect(),
+    };
+    let leading = splitter.split(&doc.children);
+    if !leading.is_empty() {
+        splitter.pages.insert(
+            0,
+            Page {
+                name: INDEX_PAGE.to_string(),
+                breadcrumb: Vec::new(),
+                children: leading,
+            },
+        );
+    }
+
+    let mut id_pages = HashMap::new();
+    for page in splitter.pages.iter() {
+        for block in page.children.iter() {
+            for node in NodeRef::Block(block).descendants() {
+                if let Some(id) = node.meta().id() {
+                    id_pages.insert(id.to_string(), page.name.clone());
+                }
+            }
+        }
+    }
+
+    splitter
+        .pages
+        .into_iter()
+        .map(|page| {
+            let mut out =
+                UsedLabels::collect(doc, page.children.iter().map(NodeRef::Block)).to_doc(doc);
+            out.children = page.children;
+            let attrs = out.attributes_mut();
+            attrs.insert(PAGE_ATTRIBUTE.to_string(), page.name.clone());
+            attrs.insert(
+                BREADCRUMB_ATTRIBUTE.to_string(),
+                page.breadcrumb.join(BREADCRUMB_SEPARATOR),
+            );
+
+            let rewrite = |dest: &mut String| {
+                let Some(id) = dest.strip_prefix('#') else {
+                    return;
+                };
+                match id_pages.get(id) {
+                    Some(p) if *p != page.name => *dest = format!("{p}{dest}"),
+                    _ => (),
+                }
+            };
+            rewrite_links(&mut out, rewrite);
+            out
+        })
+        .collect()
+}
+
+fn rewrite_links(doc: &mut Doc, mut rewrite: impl FnMut(&mut String)) {
+    for r in doc
+        .references
+        .values_mut()
+        .chain(doc.auto_references.values_mut())
+    {
+        rewrite(&mut r.destination);
+    }
+    let mut visit = |node: &mut NodeMut| {
+        if let NodeMut::Inline(Inline::Link(l)) = node {
+            if let Some(d) = l.destination.as_mut() {
+                rewrite(d);
+            }
+        }
+    };
+    for footnote in doc.footnotes.values_mut() {
+        NodeMut::Footnote(footnote).walk(&mut visit);
+    }
+    NodeMut::Doc(doc).walk(visit);
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    fn section(id: &str, level: u64, children: &str) -> String {
+        format!(
+            r#"{{"tag": "section", "attributes": {{"id": "{id}"}}, "children": [
+                {{"tag": "heading", "level": {level}, "children": [{{"tag": "str", "text": "{id}"}}]}}
+                {children}
+            ]}}"#
+        )
+    }
+
+    fn link(dest: &str) -> String {
+        format!(
+            r#", {{"tag": "para", "children": [{{"tag": "link", "destination": "{dest}", "children": []}}]}}"#
+        )
+    }
+
+    #[test]
+    fn test_split_by_heading() {
+        let b = section("b", 2, &link("#a"));
+        let a = section("a", 1, &format!("{}, {b}", link("#b")));
+        let c = section("c", 1, &link("#b"));
+        let s = format!(
+            r#"{{"tag": "doc", "references": {{}}, "autoReferences": {{}}, "footnotes": {{}}, "children": [
+                {{"tag": "para", "children": []}}, {a}, {c}
+            ]}}"#
+        );
+        let doc: Doc = serde_json::from_str(&s).unwrap();
+
+        let pages = split_by_heading(&doc, 2);
+        let names: Vec<_> = pages
+            .iter()
+            .map(|p| p.get_attribute(PAGE_ATTRIBUTE).unwrap())
+            .collect();
+        assert_eq!(names, vec![INDEX_PAGE, "a", "b", "c"]);
+        let crumbs: Vec<_> = pages
+            .iter()
+            .map(|p| p.get_attribute(BREADCRUMB_ATTRIBUTE).unwrap())
+            .collect();
+        assert_eq!(crumbs, vec!["", "a", "a > b", "c"]);
+
+        let dests = |doc: &Doc| -> Vec<String> {
+            NodeRef::Doc(doc)
+                .descendants()
+                .filter_map(|n| match n {
+                    NodeRef::Inline(Inline::Link(l)) => l.destination.clone(),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(dests(&pages[1]), vec!["b#b"]);
+        assert_eq!(dests(&pages[2]), vec!["a#a"]);
+        assert_eq!(dests(&pages[3]), vec!["b#b"]);
+
+        let pages = split_by_heading(&doc, 1);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(dests(&pages[1]), vec!["#b", "#a"]);
+    }
+
+    #[test]
+    fn test_generated_names() {
+        // the second heading has no id, and its generated name would clash with the first's
+        let s = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [
+            {"tag": "heading", "level": 1, "attributes": {"id": "page-1"},
+                "children": [{"tag": "str", "text": "First heading"}]},
+            {"tag": "heading", "level": 1, "children": [{"tag": "str", "text": "Second"}]}
+        ]}"#;
+        let doc: Doc = serde_json::from_str(s).unwrap();
+        let pages = split_by_heading(&doc, 1);
+        let names: Vec<_> = pages
+            .iter()
+            .map(|p| p.get_attribute(PAGE_ATTRIBUTE).unwrap())
+            .collect();
+        assert_eq!(names, vec!["page-1", "page-2"]);
+        assert_eq!(
+            pages[0].get_attribute(BREADCRUMB_ATTRIBUTE),
+            Some("First heading")
+        );
+    }
+}
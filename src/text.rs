@@ -0,0 +1,257 @@
+//! Plain text extraction.
+use std::ops::Range;
+
+use crate::{
+    attributes::{HasAttributes, Pos, SourceLoc},
+    block::{Block, DefinitionListItem, ListItem, Row, TaskListItem},
+    inline::Inline,
+    path::NodePath,
+    traverse::NodeRef,
+    Doc,
+};
+
+/// Append the plain text of some inlines to a string.
+///
+/// Markup is dropped, quotes are rendered as curly quotes,
+/// soft breaks become spaces and hard breaks become newlines.
+/// Raw inlines and footnote references produce no text.
+pub fn push_inline_text(inlines: &[Inline], out: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Str(i) => out.push_str(&i.text),
+            Inline::Verbatim(i) => out.push_str(&i.text),
+            Inline::InlineMath(i) => out.push_str(&i.text),
+            Inline::DisplayMath(i) => out.push_str(&i.text),
+            Inline::Url(i) => out.push_str(&i.text),
+            Inline::Email(i) => out.push_str(&i.text),
+            Inline::SmartPunctuation(i) => out.push_str(&i.text),
+            Inline::Symb(i) => {
+                out.push(':');
+                out.push_str(&i.alias);
+                out.push(':');
+            }
+            Inline::SoftBreak(_) | Inline::NonBreakingSpace(_) => out.push(' '),
+            Inline::HardBreak(_) => out.push('\n'),
+            Inline::RawInline(_) | Inline::FootnoteReference(_) => (),
+            Inline::DoubleQuoted(i) => {
+                out.push('“');
+                push_inline_text(&i.children, out);
+                out.push('”');
+            }
+            Inline::SingleQuoted(i) => {
+                out.push('‘');
+                push_inline_text(&i.children, out);
+                out.push('’');
+            }
+            _ => {
+                if let Some(children) = inline.children() {
+                    push_inline_text(children, out);
+                }
+            }
+        }
+    }
+}
+
+/// Get the plain text of some inlines.
+///
+/// See [push_inline_text].
+pub fn inline_text(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    push_inline_text(inlines, &mut out);
+    out
+}
+
+/// Get the plain text of a block.
+///
+/// Paragraphs and other blocks are separated by blank lines,
+/// list items and table rows by newlines, and table cells by ` | `.
+/// Raw blocks, thematic breaks and unknown blocks produce no text.
+pub fn block_text(block: &Block) -> String {
+    match block {
+        Block::Para(i) => inline_text(&i.children),
+        Block::Heading(i) => inline_text(&i.children),
+        Block::CodeBlock(i) => i.text.trim_end_matches('\n').to_string(),
+        Block::RawBlock(_) | Block::ThematicBreak(_) | Block::Unknown(_) => String::new(),
+        Block::Section(i) => blocks_text(&i.children),
+        Block::Div(i) => blocks_text(&i.children),
+        Block::BlockQuote(i) => blocks_text(&i.children),
+        Block::OrderedList(i) => join_lines(i.children.iter().map(list_item_text)),
+        Block::BulletList(i) => join_lines(i.children.iter().map(list_item_text)),
+        Block::TaskList(i) => join_lines(i.children.iter().map(task_list_item_text)),
+        Block::DefinitionList(i) => join_lines(i.children.iter().map(definition_list_item_text)),
+        Block::Table(i) => {
+            let caption = inline_text(&i.caption.children);
+            let rows = i.rows.iter().map(row_text);
+            join_lines(std::iter::once(caption).chain(rows))
+        }
+    }
+}
+
+/// Get the plain text of a sequence of blocks, separated by blank lines.
+pub fn blocks_text(blocks: &[Block]) -> String {
+    join_nonempty(blocks.iter().map(block_text), "\n\n")
+}
+
+/// Get the plain text of any node.
+///
+/// A reference's text is its destination.
+pub fn node_text(node: NodeRef) -> String {
+    match node {
+        NodeRef::Doc(i) => blocks_text(&i.children),
+        NodeRef::Block(i) => block_text(i),
+        NodeRef::Inline(i) => inline_text(std::slice::from_ref(i)),
+        NodeRef::ListItem(i) => list_item_text(i),
+        NodeRef::TaskListItem(i) => task_list_item_text(i),
+        NodeRef::DefinitionListItem(i) => definition_list_item_text(i),
+        NodeRef::Term(i) => inline_text(&i.children),
+        NodeRef::Definition(i) => blocks_text(&i.children),
+        NodeRef::Row(i) => row_text(i),
+        NodeRef::Cell(i) => inline_text(&i.children),
+        NodeRef::Caption(i) => inline_text(&i.children),
+        NodeRef::Footnote(i) => blocks_text(&i.children),
+        NodeRef::Reference(i) => i.destination.clone(),
+    }
+}
+
+pub(crate) fn list_item_text(item: &ListItem) -> String {
+    blocks_text(&item.children)
+}
+
+pub(crate) fn task_list_item_text(item: &TaskListItem) -> String {
+    blocks_text(&item.children)
+}
+
+pub(crate) fn definition_list_item_text(item: &DefinitionListItem) -> String {
+    let term = inline_text(&item.term.children);
+    let definition = blocks_text(&item.definition.children);
+    join_lines([term, definition].into_iter())
+}
+
+pub(crate) fn row_text(row: &Row) -> String {
+    let cells: Vec<_> = row
+        .children
+        .iter()
+        .map(|c| inline_text(&c.children))
+        .collect();
+    cells.join(" | ")
+}
+
+fn join_lines(parts: impl Iterator<Item = String>) -> String {
+    join_nonempty(parts, "\n")
+}
+
+fn join_nonempty(parts: impl Iterator<Item = String>, sep: &str) -> String {
+    let mut out = String::new();
+    for part in parts.filter(|p| !p.is_empty()) {
+        if !out.is_empty() {
+            out.push_str(sep);
+        }
+        out.push_str(&part);
+    }
+    out
+}
+
+/// A piece of the text in a [TextMap] and the node it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSegment {
+    /// Byte range of the segment in [TextMap::text].
+    pub range: Range<usize>,
+    /// Location of the node in the document.
+    pub path: NodePath,
+    /// Source position of the node, if known.
+    pub pos: Option<Pos>,
+}
+
+/// The plain text of a document, with a mapping from text offsets back to nodes.
+///
+/// Created by [extract_text].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextMap {
+    pub text: String,
+    /// Segments of the text which came from a node, in order.
+    /// Text between segments (i.e. block separators) does not belong to any node.
+    pub segments: Vec<TextSegment>,
+}
+
+impl TextMap {
+    /// Get the segment containing the given byte offset in the text.
+    pub fn segment_at(&self, offset: usize) -> Option<&TextSegment> {
+        let idx = self.segments.partition_point(|s| s.range.end <= offset);
+        self.segments.get(idx).filter(|s| s.range.contains(&offset))
+    }
+
+    /// Get the path of the node which produced the text at the given byte offset.
+    pub fn path_at(&self, offset: usize) -> Option<&NodePath> {
+        self.segment_at(offset).map(|s| &s.path)
+    }
+
+    /// Get the source location of the text at the given byte offset.
+    ///
+    /// If the node's text is the same length as its source, i.e. it contains no escapes,
+    /// the location is that of the character itself.
+    /// Otherwise, it is the start of the node.
+    pub fn source_loc_at(&self, offset: usize) -> Option<SourceLoc> {
+        let segment = self.segment_at(offset)?;
+        let pos = segment.pos?;
+        let utf16_len = |s: &str| s.encode_utf16().count() as u64;
+        let text = &self.text[segment.range.clone()];
+        let source_len = pos
+            .end
+            .offset
+            .checked_add(1)
+            .and_then(|end| end.checked_sub(pos.start.offset));
+        if pos.start.line != pos.end.line || source_len != Some(utf16_len(text)) {
+            return Some(pos.start);
+        }
+        let n_before = utf16_len(&self.text[segment.range.start..offset]);
+        Some(SourceLoc {
+            line: pos.start.line,
+            col: pos.start.col.saturating_add(n_before),
+            offset: pos.start.offset + n_before,
+        })
+    }
+}
+
+/// Extract the plain text of the document for natural language processing.
+///
+/// Only [Str](crate::inline::Str), [SmartPunctuation](crate::inline::SmartPunctuation),
+/// [NonBreakingSpace](crate::inline::NonBreakingSpace) and breaks produce text;
+/// verbatim, math, raw content and URLs do not.
+/// Non-breaking spaces and soft breaks become spaces, and hard breaks become newlines.
+/// Paragraphs, headings, table cells and other blocks of inlines are separated by blank lines.
+/// Footnotes are not included.
+pub fn extract_text(doc: &Doc) -> TextMap {
+    let mut map = TextMap::default();
+    let mut stack = vec![(NodeRef::Doc(doc), NodePath::root())];
+    while let Some((node, path)) = stack.pop() {
+        let text = match node {
+            NodeRef::Block(Block::Para(_) | Block::Heading(_))
+            | NodeRef::Cell(_)
+            | NodeRef::Caption(_)
+            | NodeRef::Term(_) => {
+                if !map.text.is_empty() && !map.text.ends_with("\n\n") {
+                    map.text.push_str("\n\n");
+                }
+                None
+            }
+            NodeRef::Inline(Inline::Str(i)) => Some(i.text.as_str()),
+            NodeRef::Inline(Inline::SmartPunctuation(i)) => Some(i.text.as_str()),
+            NodeRef::Inline(Inline::NonBreakingSpace(_) | Inline::SoftBreak(_)) => Some(" "),
+            NodeRef::Inline(Inline::HardBreak(_)) => Some("\n"),
+            _ => None,
+        };
+        if let Some(text) = text {
+            let start = map.text.len();
+            map.text.push_str(text);
+            map.segments.push(TextSegment {
+                range: start..map.text.len(),
+                path: path.clone(),
+                pos: node.meta().pos().copied(),
+            });
+        }
+        for (idx, child) in node.children().into_iter().enumerate().rev() {
+            stack.push((child, path.child(idx)));
+        }
+    }
+    map
+}
@@ -1,3 +1,12 @@
+use crate::{
+    attributes::{HasMeta, Meta},
+    block::{
+        Block, Caption, Cell, Definition, DefinitionListItem, ListItem, Row, TaskListItem, Term,
+    },
+    inline::Inline,
+    AstNode, Doc, Footnote, Reference,
+};
+
 pub enum NodeType {
     Root(usize),
     Branch(usize),
@@ -27,3 +36,404 @@ impl NodeType {
 pub trait Node {
     fn node_type(&self) -> NodeType;
 }
+
+/// A borrowed reference to any node in the tree.
+///
+/// Children are visited in the same order as the `children` array of the djot.js JSON,
+/// so a [Table](crate::block::Table) yields its caption before its rows,
+/// and a [DefinitionListItem] yields its term then its definition.
+/// The [Doc]'s references and footnotes are not children.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeRef<'a> {
+    Doc(&'a Doc),
+    Block(&'a Block),
+    Inline(&'a Inline),
+    ListItem(&'a ListItem),
+    TaskListItem(&'a TaskListItem),
+    DefinitionListItem(&'a DefinitionListItem),
+    Term(&'a Term),
+    Definition(&'a Definition),
+    Row(&'a Row),
+    Cell(&'a Cell),
+    Caption(&'a Caption),
+    Footnote(&'a Footnote),
+    Reference(&'a Reference),
+}
+
+impl<'a> NodeRef<'a> {
+    /// Get the node's metadata.
+    pub fn meta(&self) -> &'a Meta {
+        use NodeRef::*;
+        match *self {
+            Doc(i) => i.meta(),
+            Block(i) => i.meta(),
+            Inline(i) => i.meta(),
+            ListItem(i) => i.meta(),
+            TaskListItem(i) => i.meta(),
+            DefinitionListItem(i) => i.meta(),
+            Term(i) => i.meta(),
+            Definition(i) => i.meta(),
+            Row(i) => i.meta(),
+            Cell(i) => i.meta(),
+            Caption(i) => i.meta(),
+            Footnote(i) => i.meta(),
+            Reference(i) => i.meta(),
+        }
+    }
+
+    /// Get the node's `tag` in the djot.js JSON, e.g. `"bullet_list"` or `"str"`.
+    ///
+    /// [Unknown](crate::unknown::Unknown) nodes give the tag they were read with.
+    pub fn tag(&self) -> &'a str {
+        match *self {
+            NodeRef::Doc(_) => "doc",
+            NodeRef::Block(b) => match b {
+                Block::Para(_) => "para",
+                Block::Heading(_) => "heading",
+                Block::ThematicBreak(_) => "thematic_break",
+                Block::Section(_) => "section",
+                Block::Div(_) => "div",
+                Block::CodeBlock(_) => "code_block",
+                Block::RawBlock(_) => "raw_block",
+                Block::BlockQuote(_) => "block_quote",
+                Block::OrderedList(_) => "ordered_list",
+                Block::BulletList(_) => "bullet_list",
+                Block::TaskList(_) => "task_list",
+                Block::DefinitionList(_) => "definition_list",
+                Block::Table(_) => "table",
+                Block::Unknown(u) => &u.tag,
+            },
+            NodeRef::Inline(i) => match i {
+                Inline::Str(_) => "str",
+                Inline::SoftBreak(_) => "soft_break",
+                Inline::HardBreak(_) => "hard_break",
+                Inline::NonBreakingSpace(_) => "non_breaking_space",
+                Inline::Symb(_) => "symb",
+                Inline::Verbatim(_) => "verbatim",
+                Inline::RawInline(_) => "raw_inline",
+                Inline::InlineMath(_) => "inline_math",
+                Inline::DisplayMath(_) => "display_math",
+                Inline::Url(_) => "url",
+                Inline::Email(_) => "email",
+                Inline::FootnoteReference(_) => "footnote_reference",
+                Inline::SmartPunctuation(_) => "smart_punctuation",
+                Inline::Emph(_) => "emph",
+                Inline::Strong(_) => "strong",
+                Inline::Link(_) => "link",
+                Inline::Image(_) => "image",
+                Inline::Span(_) => "span",
+                Inline::Mark(_) => "mark",
+                Inline::Superscript(_) => "superscript",
+                Inline::Subscript(_) => "subscript",
+                Inline::Insert(_) => "insert",
+                Inline::Delete(_) => "delete",
+                Inline::DoubleQuoted(_) => "double_quoted",
+                Inline::SingleQuoted(_) => "single_quoted",
+                Inline::Unknown(u) => &u.tag,
+            },
+            NodeRef::ListItem(_) => "list_item",
+            NodeRef::TaskListItem(_) => "task_list_item",
+            NodeRef::DefinitionListItem(_) => "definition_list_item",
+            NodeRef::Term(_) => "term",
+            NodeRef::Definition(_) => "definition",
+            NodeRef::Row(_) => "row",
+            NodeRef::Cell(_) => "cell",
+            NodeRef::Caption(_) => "caption",
+            NodeRef::Footnote(_) => "footnote",
+            NodeRef::Reference(_) => "reference",
+        }
+    }
+
+    /// Get the node's direct children.
+    pub fn children(&self) -> Vec<NodeRef<'a>> {
+        fn blocks(bs: &[Block]) -> Vec<NodeRef<'_>> {
+            bs.iter().map(NodeRef::Block).collect()
+        }
+        fn inlines(is: &[Inline]) -> Vec<NodeRef<'_>> {
+            is.iter().map(NodeRef::Inline).collect()
+        }
+
+        match *self {
+            NodeRef::Doc(d) => blocks(&d.children),
+            NodeRef::Block(b) => match b {
+                Block::Para(i) => inlines(&i.children),
+                Block::Heading(i) => inlines(&i.children),
+                Block::Section(i) => blocks(&i.children),
+                Block::Div(i) => blocks(&i.children),
+                Block::BlockQuote(i) => blocks(&i.children),
+                Block::OrderedList(i) => i.children.iter().map(NodeRef::ListItem).collect(),
+                Block::BulletList(i) => i.children.iter().map(NodeRef::ListItem).collect(),
+                Block::TaskList(i) => i.children.iter().map(NodeRef::TaskListItem).collect(),
+                Block::DefinitionList(i) => {
+                    i.children.iter().map(NodeRef::DefinitionListItem).collect()
+                }
+                Block::Table(i) => std::iter::once(NodeRef::Caption(&i.caption))
+                    .chain(i.rows.iter().map(NodeRef::Row))
+                    .collect(),
+                Block::ThematicBreak(_)
+                | Block::CodeBlock(_)
+                | Block::RawBlock(_)
+                | Block::Unknown(_) => Vec::new(),
+            },
+            NodeRef::Inline(i) => match i.children() {
+                Some(c) => inlines(c),
+                None => Vec::new(),
+            },
+            NodeRef::ListItem(i) => blocks(&i.children),
+            NodeRef::TaskListItem(i) => blocks(&i.children),
+            NodeRef::DefinitionListItem(i) => {
+                vec![NodeRef::Term(&i.term), NodeRef::Definition(&i.definition)]
+            }
+            NodeRef::Term(i) => inlines(&i.children),
+            NodeRef::Definition(i) => blocks(&i.children),
+            NodeRef::Row(i) => i.children.iter().map(NodeRef::Cell).collect(),
+            NodeRef::Cell(i) => inlines(&i.children),
+            NodeRef::Caption(i) => inlines(&i.children),
+            NodeRef::Footnote(i) => blocks(&i.children),
+            NodeRef::Reference(_) => Vec::new(),
+        }
+    }
+
+    /// Iterate over this node and all of its descendants, depth-first in document order.
+    pub fn descendants(&self) -> Descendants<'a> {
+        Descendants { stack: vec![*self] }
+    }
+
+    /// Clone the referenced node into an owned [AstNode].
+    pub fn to_owned_node(&self) -> AstNode {
+        use NodeRef::*;
+        match *self {
+            Doc(i) => i.clone().into(),
+            Block(i) => i.clone().into(),
+            Inline(i) => i.clone().into(),
+            ListItem(i) => i.clone().into(),
+            TaskListItem(i) => i.clone().into(),
+            DefinitionListItem(i) => i.clone().into(),
+            Term(i) => i.clone().into(),
+            Definition(i) => i.clone().into(),
+            Row(i) => i.clone().into(),
+            Cell(i) => i.clone().into(),
+            Caption(i) => i.clone().into(),
+            Footnote(i) => i.clone().into(),
+            Reference(i) => i.clone().into(),
+        }
+    }
+}
+
+impl Node for NodeRef<'_> {
+    fn node_type(&self) -> NodeType {
+        use NodeRef::*;
+        match self {
+            Doc(i) => i.node_type(),
+            Block(i) => i.node_type(),
+            Inline(i) => i.node_type(),
+            ListItem(i) => i.node_type(),
+            TaskListItem(i) => i.node_type(),
+            DefinitionListItem(i) => i.node_type(),
+            Term(i) => i.node_type(),
+            Definition(i) => i.node_type(),
+            Row(i) => i.node_type(),
+            Cell(i) => i.node_type(),
+            Caption(i) => i.node_type(),
+            Footnote(i) => i.node_type(),
+            Reference(i) => i.node_type(),
+        }
+    }
+}
+
+impl<'a> From<&'a Block> for NodeRef<'a> {
+    fn from(value: &'a Block) -> Self {
+        Self::Block(value)
+    }
+}
+
+impl<'a> From<&'a Inline> for NodeRef<'a> {
+    fn from(value: &'a Inline) -> Self {
+        Self::Inline(value)
+    }
+}
+
+impl<'a> From<&'a AstNode> for NodeRef<'a> {
+    fn from(value: &'a AstNode) -> Self {
+        match value {
+            AstNode::Doc(i) => Self::Doc(i),
+            AstNode::Block(i) => Self::Block(i),
+            AstNode::Inline(i) => Self::Inline(i),
+            AstNode::ListItem(i) => Self::ListItem(i),
+            AstNode::TaskListItem(i) => Self::TaskListItem(i),
+            AstNode::DefinitionListItem(i) => Self::DefinitionListItem(i),
+            AstNode::Term(i) => Self::Term(i),
+            AstNode::Definition(i) => Self::Definition(i),
+            AstNode::Row(i) => Self::Row(i),
+            AstNode::Cell(i) => Self::Cell(i),
+            AstNode::Caption(i) => Self::Caption(i),
+            AstNode::Footnote(i) => Self::Footnote(i),
+            AstNode::Reference(i) => Self::Reference(i),
+        }
+    }
+}
+
+/// Depth-first, pre-order iterator over a node and its descendants.
+///
+/// Created by [NodeRef::descendants].
+/// Uses an explicit stack rather than recursion.
+pub struct Descendants<'a> {
+    stack: Vec<NodeRef<'a>>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children().into_iter().rev());
+        Some(node)
+    }
+}
+
+/// A mutable reference to any node in the tree.
+///
+/// See [NodeRef] for the ordering of children.
+#[derive(Debug)]
+pub enum NodeMut<'a> {
+    Doc(&'a mut Doc),
+    Block(&'a mut Block),
+    Inline(&'a mut Inline),
+    ListItem(&'a mut ListItem),
+    TaskListItem(&'a mut TaskListItem),
+    DefinitionListItem(&'a mut DefinitionListItem),
+    Term(&'a mut Term),
+    Definition(&'a mut Definition),
+    Row(&'a mut Row),
+    Cell(&'a mut Cell),
+    Caption(&'a mut Caption),
+    Footnote(&'a mut Footnote),
+    Reference(&'a mut Reference),
+}
+
+impl<'a> NodeMut<'a> {
+    /// Reborrow as an immutable reference.
+    pub fn as_ref(&self) -> NodeRef<'_> {
+        use NodeMut::*;
+        match self {
+            Doc(i) => NodeRef::Doc(i),
+            Block(i) => NodeRef::Block(i),
+            Inline(i) => NodeRef::Inline(i),
+            ListItem(i) => NodeRef::ListItem(i),
+            TaskListItem(i) => NodeRef::TaskListItem(i),
+            DefinitionListItem(i) => NodeRef::DefinitionListItem(i),
+            Term(i) => NodeRef::Term(i),
+            Definition(i) => NodeRef::Definition(i),
+            Row(i) => NodeRef::Row(i),
+            Cell(i) => NodeRef::Cell(i),
+            Caption(i) => NodeRef::Caption(i),
+            Footnote(i) => NodeRef::Footnote(i),
+            Reference(i) => NodeRef::Reference(i),
+        }
+    }
+
+    /// Get the node's metadata mutably.
+    pub fn meta_mut(&mut self) -> &mut Meta {
+        use NodeMut::*;
+        match self {
+            Doc(i) => i.meta_mut(),
+            Block(i) => i.meta_mut(),
+            Inline(i) => i.meta_mut(),
+            ListItem(i) => i.meta_mut(),
+            TaskListItem(i) => i.meta_mut(),
+            DefinitionListItem(i) => i.meta_mut(),
+            Term(i) => i.meta_mut(),
+            Definition(i) => i.meta_mut(),
+            Row(i) => i.meta_mut(),
+            Cell(i) => i.meta_mut(),
+            Caption(i) => i.meta_mut(),
+            Footnote(i) => i.meta_mut(),
+            Reference(i) => i.meta_mut(),
+        }
+    }
+
+    /// Convert into mutable references to the node's direct children.
+    pub fn into_children(self) -> Vec<NodeMut<'a>> {
+        fn blocks(bs: &mut [Block]) -> Vec<NodeMut<'_>> {
+            bs.iter_mut().map(NodeMut::Block).collect()
+        }
+        fn inlines(is: &mut [Inline]) -> Vec<NodeMut<'_>> {
+            is.iter_mut().map(NodeMut::Inline).collect()
+        }
+
+        match self {
+            NodeMut::Doc(d) => blocks(&mut d.children),
+            NodeMut::Block(b) => match b {
+                Block::Para(i) => inlines(&mut i.children),
+                Block::Heading(i) => inlines(&mut i.children),
+                Block::Section(i) => blocks(&mut i.children),
+                Block::Div(i) => blocks(&mut i.children),
+                Block::BlockQuote(i) => blocks(&mut i.children),
+                Block::OrderedList(i) => i.children.iter_mut().map(NodeMut::ListItem).collect(),
+                Block::BulletList(i) => i.children.iter_mut().map(NodeMut::ListItem).collect(),
+                Block::TaskList(i) => i.children.iter_mut().map(NodeMut::TaskListItem).collect(),
+                Block::DefinitionList(i) => i
+                    .children
+                    .iter_mut()
+                    .map(NodeMut::DefinitionListItem)
+                    .collect(),
+                Block::Table(i) => std::iter::once(NodeMut::Caption(&mut i.caption))
+                    .chain(i.rows.iter_mut().map(NodeMut::Row))
+                    .collect(),
+                Block::ThematicBreak(_)
+                | Block::CodeBlock(_)
+                | Block::RawBlock(_)
+                | Block::Unknown(_) => Vec::new(),
+            },
+            NodeMut::Inline(i) => match i.children_mut() {
+                Some(c) => inlines(c),
+                None => Vec::new(),
+            },
+            NodeMut::ListItem(i) => blocks(&mut i.children),
+            NodeMut::TaskListItem(i) => blocks(&mut i.children),
+            NodeMut::DefinitionListItem(i) => vec![
+                NodeMut::Term(&mut i.term),
+                NodeMut::Definition(&mut i.definition),
+            ],
+            NodeMut::Term(i) => inlines(&mut i.children),
+            NodeMut::Definition(i) => blocks(&mut i.children),
+            NodeMut::Row(i) => i.children.iter_mut().map(NodeMut::Cell).collect(),
+            NodeMut::Cell(i) => inlines(&mut i.children),
+            NodeMut::Caption(i) => inlines(&mut i.children),
+            NodeMut::Footnote(i) => blocks(&mut i.children),
+            NodeMut::Reference(_) => Vec::new(),
+        }
+    }
+
+    /// Call `f` on this node and then on each of its descendants, depth-first in document order.
+    ///
+    /// Each node is visited before its children are borrowed,
+    /// so `f` may replace or modify children before they are visited.
+    pub fn walk(self, mut f: impl FnMut(&mut NodeMut<'a>)) {
+        let mut stack = vec![self];
+        while let Some(mut node) = stack.pop() {
+            f(&mut node);
+            stack.extend(node.into_children().into_iter().rev());
+        }
+    }
+}
+
+impl<'a> From<&'a mut AstNode> for NodeMut<'a> {
+    fn from(value: &'a mut AstNode) -> Self {
+        match value {
+            AstNode::Doc(i) => Self::Doc(i),
+            AstNode::Block(i) => Self::Block(i),
+            AstNode::Inline(i) => Self::Inline(i),
+            AstNode::ListItem(i) => Self::ListItem(i),
+            AstNode::TaskListItem(i) => Self::TaskListItem(i),
+            AstNode::DefinitionListItem(i) => Self::DefinitionListItem(i),
+            AstNode::Term(i) => Self::Term(i),
+            AstNode::Definition(i) => Self::Definition(i),
+            AstNode::Row(i) => Self::Row(i),
+            AstNode::Cell(i) => Self::Cell(i),
+            AstNode::Caption(i) => Self::Caption(i),
+            AstNode::Footnote(i) => Self::Footnote(i),
+            AstNode::Reference(i) => Self::Reference(i),
+        }
+    }
+}
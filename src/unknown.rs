@@ -0,0 +1,481 @@
+//! Nodes of a type this crate does not know, kept as they were read.
+//!
+//! Deserializing a block or inline with an unknown tag fails,
+//! so a document from a newer djot.js with a new type of node cannot be read at all.
+//! Deserializing within [Lenient](crate::deserialize::Lenient) instead reads such a node as
+//! [Block::Unknown](crate::block::Block::Unknown) or [Inline::Unknown](crate::inline::Inline::Unknown),
+//! which keeps its fields so that it serializes unchanged.
+//! [validate](crate::validate::validate) warns about each one.
+//!
+//! ```
+//! use djot_ast::{block::Block, deserialize::Lenient, Doc};
+//!
+//! let json = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [
+//!     {"tag": "admonition", "kind": "note", "children": [{"tag": "para", "children": []}]}
+//! ]}"#;
+//! assert!(serde_json::from_str::<Doc>(json).is_err());
+//! let Lenient(doc): Lenient<Doc> = serde_json::from_str(json).unwrap();
+//! let Block::Unknown(unknown) = &doc.children[0] else { panic!() };
+//! assert_eq!(unknown.tag, "admonition");
+//! assert_eq!(
+//!     serde_json::to_value(&doc).unwrap(),
+//!     serde_json::from_str::<serde_json::Value>(json).unwrap()
+//! );
+//! ```
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+
+use crate::{attributes::Meta, macros::impl_hasmeta, Node, NodeType};
+#[cfg(feature = "serde")]
+use crate::{
+    block::Block,
+    deserialize::{nest_value, Lenient},
+    inline::Inline,
+};
+
+/// A block or inline whose tag this crate does not know.
+///
+/// Its children, if it has any, are among its fields, so it is a leaf of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct Unknown {
+    pub tag: String,
+    /// The fields other than the tag, attributes and position, in the order they were read.
+    pub fields: IndexMap<String, Value>,
+    pub(crate) meta: Meta,
+}
+impl Node for Unknown {
+    fn node_type(&self) -> NodeType {
+        NodeType::Leaf
+    }
+}
+impl_hasmeta!(Unknown);
+
+/// The fields of [Meta] in djot.js JSON.
+#[cfg(feature = "serde")]
+const META_FIELDS: &[&str] = &["attributes", "autoAttributes", "pos"];
+
+#[cfg(feature = "serde")]
+impl Unknown {
+    /// Read a node from the fields of its object, including its tag.
+    pub(crate) fn from_fields(
+        mut fields: IndexMap<String, Value>,
+    ) -> Result<Self, de::value::Error> {
+        let tag = match fields.shift_remove("tag") {
+            Some(Value::String(tag)) => tag,
+            Some(_) => return Err(de::Error::custom("the tag is not a string")),
+            None => return Err(de::Error::missing_field("tag")),
+        };
+        let (meta, fields): (IndexMap<_, _>, _) = fields.into_iter().partition(|(k, _)| {
+            META_FIELDS.contains(&k.as_str())
+                || (k == "nodeId" && crate::attributes::with_node_ids())
+        });
+        Ok(Self {
+            tag,
+            fields,
+            meta: Meta::deserialize(Value::Object(meta))?,
+        })
+    }
+
+    /// The node's `children`, if they are all blocks of known types (with any descendants read leniently).
+    ///
+    /// This lets a renderer show the contents of a node it does not know how to render.
+    pub fn blocks(&self) -> Option<Vec<Block>> {
+        self.children(|b| matches!(b, Block::Unknown(_)))
+    }
+
+    /// The node's `children`, if they are all inlines of known types (with any descendants read leniently).
+    pub fn inlines(&self) -> Option<Vec<Inline>> {
+        self.children(|i| matches!(i, Inline::Unknown(_)))
+    }
+
+    fn children<T: de::DeserializeOwned>(&self, unknown: impl Fn(&T) -> bool) -> Option<Vec<T>> {
+        let children = self.fields.get("children")?.clone();
+        let Lenient(children) = Lenient::<Vec<T>>::deserialize(children).ok()?;
+        (!children.iter().any(unknown)).then_some(children)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Unknown {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SerUnknown<'a> {
+            tag: &'a str,
+            #[serde(flatten)]
+            fields: &'a IndexMap<String, Value>,
+            #[serde(flatten)]
+            meta: &'a Meta,
+        }
+
+        SerUnknown {
+            tag: &self.tag,
+            fields: &self.fields,
+            meta: &self.meta,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Unknown {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Object(fields) => Self::from_fields(fields).map_err(de::Error::custom),
+            other => Err(de::Error::invalid_type(other.unexpected(), &"a node")),
+        }
+    }
+}
+
+/// Any value of a self-describing format such as JSON, in the order it was read.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(
+        serialize_bounds(
+            __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+            __S::Error: rkyv::rancor::Source,
+        ),
+        deserialize_bounds(__D::Error: rkyv::rancor::Source),
+        bytecheck(bounds(
+            __C: rkyv::validation::ArchiveContext,
+            __C::Error: rkyv::rancor::Source,
+        )),
+    )
+)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    U64(u64),
+    /// A negative integer.
+    I64(i64),
+    F64(f64),
+    String(String),
+    Array(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Vec<Value>),
+    Object(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] IndexMap<String, Value>),
+}
+
+/// Floats are compared by their bits, so that every value equals itself.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        use Value::*;
+        match (self, other) {
+            (Null, Null) => true,
+            (Bool(a), Bool(b)) => a == b,
+            (U64(a), U64(b)) => a == b,
+            (I64(a), I64(b)) => a == b,
+            (F64(a), F64(b)) => a.to_bits() == b.to_bits(),
+            (String(a), String(b)) => a == b,
+            (Array(a), Array(b)) => a == b,
+            (Object(a), Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl Value {
+    /// Describe the type of the value, e.g. `"a string"`.
+    #[cfg(feature = "serde")]
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::U64(_) | Value::I64(_) => "an integer",
+            Value::F64(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    /// Get a field of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Value::Null => de::Unexpected::Unit,
+            Value::Bool(b) => de::Unexpected::Bool(*b),
+            Value::U64(n) => de::Unexpected::Unsigned(*n),
+            Value::I64(n) => de::Unexpected::Signed(*n),
+            Value::F64(n) => de::Unexpected::Float(*n),
+            Value::String(s) => de::Unexpected::Str(s),
+            Value::Array(_) => de::Unexpected::Seq,
+            Value::Object(_) => de::Unexpected::Map,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::U64(v) => serializer.serialize_u64(*v),
+            Value::I64(v) => serializer.serialize_i64(*v),
+            Value::F64(v) => serializer.serialize_f64(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Array(items) => serializer.collect_seq(items),
+            Value::Object(fields) => serializer.collect_map(fields),
+        }
+    }
+}
+
+/// Don't trust the input's claim of its length with more than a little memory.
+#[cfg(feature = "serde")]
+fn cautious(hint: Option<usize>) -> usize {
+    hint.unwrap_or_default().min(1024)
+}
+
+/// Reads a value, which is a field of an object if `in_object`.
+///
+/// Each object counts towards the depth, as does each array other than the value of a field,
+/// so that the depth of a node's value is that of the nodes in it.
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy)]
+struct ValueVisitor {
+    in_object: bool,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> DeserializeSeed<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::U64(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(match u64::try_from(v) {
+            Ok(v) => Value::U64(v),
+            Err(_) => Value::I64(v),
+        })
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::F64(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let _nested = if self.in_object {
+            None
+        } else {
+            Some(nest_value()?)
+        };
+        let mut items = Vec::with_capacity(cautious(seq.size_hint()));
+        let item = ValueVisitor { in_object: false };
+        while let Some(item) = seq.next_element_seed(item)? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Value, A::Error> {
+        let _nested = nest_value()?;
+        read_fields(None, map).map(Value::Object)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+thread_local! {
+    /// The number of objects read into memory, so that tests can check that nodes are not read more than once.
+    pub(crate) static OBJECTS_READ: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Read the fields of an object into memory, after its first key, `first`, if it has one.
+///
+/// The fields count towards the depth as within [Value]'s deserialization, but the object itself does not.
+/// A `tag` is moved first, so that a node deserialized from the object finds it without reading the object again.
+#[cfg(feature = "serde")]
+pub(crate) fn read_fields<'de, A: MapAccess<'de>>(
+    first: Option<String>,
+    mut map: A,
+) -> Result<IndexMap<String, Value>, A::Error> {
+    #[cfg(test)]
+    OBJECTS_READ.set(OBJECTS_READ.get() + 1);
+    let field = ValueVisitor { in_object: true };
+    let mut fields = IndexMap::with_capacity(cautious(map.size_hint()));
+    if let Some(key) = first {
+        fields.insert(key, map.next_value_seed(field)?);
+    }
+    while let Some((key, value)) = map.next_entry_seed(std::marker::PhantomData::<String>, field)? {
+        fields.insert(key, value);
+    }
+    if let Some(idx) = fields.get_index_of("tag") {
+        fields.move_index(idx, 0);
+    }
+    Ok(fields)
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor { in_object: false })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> IntoDeserializer<'de, de::value::Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Deserialize anything from a value, as if from the format it was read from.
+#[cfg(feature = "serde")]
+impl<'de> Deserializer<'de> for Value {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::U64(v) => visitor.visit_u64(v),
+            Value::I64(v) => visitor.visit_i64(v),
+            Value::F64(v) => visitor.visit_f64(v),
+            Value::String(v) => visitor.visit_string(v),
+            Value::Array(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter())),
+            Value::Object(fields) => visitor.visit_map(MapDeserializer::new(fields.into_iter())),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use crate::{
+        attributes::HasAttributes, binary::Compact, block::Block, deserialize::Lenient,
+        inline::Inline, Doc,
+    };
+
+    const JSON: &str = r#"{"tag":"doc","references":{},"autoReferences":{},"footnotes":{},"children":[{"tag":"para","children":[{"tag":"str","text":"a"},{"tag":"emoji","name":"smile","size":1.5,"skin":null,"pos":{"start":{"line":1,"col":2,"offset":1},"end":{"line":1,"col":8,"offset":7}},"attributes":{"class":"big"}}]},{"tag":"admonition","kind":"note","depth":-1,"children":[{"tag":"para","children":[]}]}]}"#;
+
+    #[test]
+    fn test_round_trip() {
+        let Lenient(doc): Lenient<Doc> = serde_json::from_str(JSON).unwrap();
+        let Block::Para(para) = &doc.children[0] else {
+            panic!("not a paragraph")
+        };
+        let Inline::Unknown(emoji) = &para.children[1] else {
+            panic!("not unknown")
+        };
+        assert_eq!(emoji.tag, "emoji");
+        assert_eq!(emoji.fields["size"], Value::F64(1.5));
+        assert_eq!(emoji.attributes()["class"], "big");
+        let Block::Unknown(admonition) = &doc.children[1] else {
+            panic!("not unknown")
+        };
+        assert_eq!(admonition.fields["depth"], Value::I64(-1));
+        assert_eq!(serde_json::to_string(&doc).unwrap(), JSON);
+
+        assert!(matches!(
+            admonition.blocks().as_deref(),
+            Some([Block::Para(_)])
+        ));
+        assert!(admonition.inlines().is_none());
+        assert!(emoji.blocks().is_none());
+        assert!(emoji.inlines().is_none());
+    }
+
+    #[test]
+    fn test_strict() {
+        assert!(serde_json::from_str::<Doc>(JSON).is_err());
+        // leniency only covers unknown tags
+        let invalid = r#"{"tag": "div", "children": [{"tag": "para"}]}"#;
+        assert!(serde_json::from_str::<Lenient<Block>>(invalid).is_err());
+        assert!(serde_json::from_str::<Lenient<Doc>>(JSON).is_ok());
+        assert!(serde_json::from_str::<Doc>(JSON).is_err());
+    }
+
+    #[test]
+    fn test_compact() {
+        let Lenient(doc): Lenient<Doc> = serde_json::from_str(JSON).unwrap();
+        let bytes = postcard::to_allocvec(&Compact(&doc)).unwrap();
+        let Compact(decoded): Compact<Doc> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, doc);
+    }
+}
@@ -0,0 +1,224 @@
+//! Checking documents for problems which a renderer would have to guess around.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    attributes::HasAttributes,
+    block::Block,
+    inline::Inline,
+    path::{NodePath, PathRoot},
+    traverse::NodeRef,
+    Doc,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A problem found in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The node with the problem.
+    pub path: NodePath,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(path: &NodePath, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            path: path.clone(),
+            message,
+        }
+    }
+
+    fn warning(path: &NodePath, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            path: path.clone(),
+            message,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at '{}': {}", self.severity, self.path, self.message)
+    }
+}
+
+/// Check the document for problems, returning them in document order.
+///
+/// Errors are:
+/// - links and images referring to undefined references
+/// - footnote references to undefined footnotes
+/// - references and footnotes stored under a key other than their label
+/// - more than one node with the same `id` attribute
+/// - headings with a level outside 1 to 6
+///
+/// Warnings are:
+/// - footnotes and explicit references which are never used
+/// - tables whose rows have different numbers of cells
+/// - [unknown](crate::unknown) blocks and inlines, which renderers cannot know what to do with
+pub fn validate(doc: &Doc) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let mut ids: HashMap<&str, NodePath> = HashMap::new();
+    let mut used_references = HashSet::new();
+    let mut used_footnotes = HashSet::new();
+
+    for (path, node) in doc.nodes() {
+        if let Some(id) = node.meta().attributes().get("id") {
+            if let Some(first) = ids.get(id.as_str()) {
+                out.push(Diagnostic::error(
+                    &path,
+                    format!("duplicate id '{id}', first used at '{first}'"),
+                ));
+            } else {
+                ids.insert(id, path.clone());
+            }
+        }
+
+        match node {
+            NodeRef::Inline(Inline::Link(i)) => {
+                check_reference(doc, &path, i.reference.as_deref(), &mut out);
+                used_references.extend(i.reference.as_deref());
+            }
+            NodeRef::Inline(Inline::Image(i)) => {
+                check_reference(doc, &path, i.reference.as_deref(), &mut out);
+                used_references.extend(i.reference.as_deref());
+            }
+            NodeRef::Inline(Inline::FootnoteReference(i)) => {
+                if !doc.footnotes.contains_key(&i.text) {
+                    out.push(Diagnostic::error(
+                        &path,
+                        format!("undefined footnote '{}'", i.text),
+                    ));
+                }
+                used_footnotes.insert(i.text.as_str());
+            }
+            NodeRef::Block(Block::Heading(h)) if !(1..=6).contains(&h.level) => {
+                out.push(Diagnostic::error(
+                    &path,
+                    format!("heading level {} is not between 1 and 6", h.level),
+                ));
+            }
+            NodeRef::Block(Block::Table(t)) => {
+                let mut lengths = t.rows.iter().map(|r| r.children.len());
+                if let Some(first) = lengths.next() {
+                    if lengths.any(|l| l != first) {
+                        out.push(Diagnostic::warning(
+                            &path,
+                            "table rows have different numbers of cells".to_string(),
+                        ));
+                    }
+                }
+            }
+            NodeRef::Block(Block::Unknown(u)) | NodeRef::Inline(Inline::Unknown(u)) => {
+                out.push(Diagnostic::warning(
+                    &path,
+                    format!("unknown node '{}'", u.tag),
+                ));
+            }
+            NodeRef::Footnote(f) => check_label(&path, &f.label, &mut out),
+            NodeRef::Reference(r) => check_label(&path, &r.label, &mut out),
+            _ => (),
+        }
+    }
+
+    for (path, node) in doc.nodes() {
+        match (&path.root, node) {
+            (PathRoot::Footnote(l), NodeRef::Footnote(_))
+                if !used_footnotes.contains(l.as_str()) =>
+            {
+                out.push(Diagnostic::warning(&path, format!("unused footnote '{l}'")));
+            }
+            (PathRoot::Reference(l), NodeRef::Reference(_))
+                if !used_references.contains(l.as_str()) =>
+            {
+                out.push(Diagnostic::warning(
+                    &path,
+                    format!("unused reference '{l}'"),
+                ));
+            }
+            _ => (),
+        }
+    }
+    out
+}
+
+fn check_reference(doc: &Doc, path: &NodePath, label: Option<&str>, out: &mut Vec<Diagnostic>) {
+    let Some(label) = label else {
+        return;
+    };
+    if !doc.references.contains_key(label) && !doc.auto_references.contains_key(label) {
+        out.push(Diagnostic::error(
+            path,
+            format!("undefined reference '{label}'"),
+        ));
+    }
+}
+
+fn check_label(path: &NodePath, label: &str, out: &mut Vec<Diagnostic>) {
+    let key = match &path.root {
+        PathRoot::Footnote(k) | PathRoot::Reference(k) | PathRoot::AutoReference(k) => k,
+        PathRoot::Doc => return,
+    };
+    if key != label {
+        out.push(Diagnostic::error(
+            path,
+            format!("label '{label}' is stored under '{key}'"),
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+    use crate::deserialize::Lenient;
+
+    #[test]
+    fn test_validate() {
+        let s = r#"{
+            "tag": "doc", "references": {}, "autoReferences": {},
+            "footnotes": {"unused": {"tag": "footnote", "label": "unused", "children": []}},
+            "children": [
+                {"tag": "para", "attributes": {"id": "a"}, "children": [
+                    {"tag": "link", "reference": "missing", "children": []},
+                    {"tag": "footnote_reference", "text": "nope"}
+                ]},
+                {"tag": "heading", "level": 7, "attributes": {"id": "a"}, "children": []},
+                {"tag": "admonition", "children": []}
+            ]
+        }"#;
+        let Lenient(doc) = serde_json::from_str(s).unwrap();
+        let found: Vec<_> = validate(&doc)
+            .into_iter()
+            .map(|d| (d.severity, d.path.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Error, "/children/0/children/0".to_string()),
+                (Severity::Error, "/children/0/children/1".to_string()),
+                (Severity::Error, "/children/1".to_string()),
+                (Severity::Error, "/children/1".to_string()),
+                (Severity::Warning, "/children/2".to_string()),
+                (Severity::Warning, "/footnotes/unused".to_string()),
+            ]
+        );
+    }
+}
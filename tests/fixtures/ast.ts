@@ -0,0 +1,334 @@
+// The AST types of djot.js, from its src/ast.ts at version 0.3.2,
+// which the schema is compared with in src/schema.rs.
+// Replace this copy with the new src/ast.ts when djot.js changes its AST.
+
+export interface Attributes {
+  [key: string]: string;
+}
+
+export interface SourceLoc {
+  line: number;
+  col: number;
+  offset: number;
+}
+
+export interface Pos {
+  start: SourceLoc;
+  end: SourceLoc;
+}
+
+export interface HasAttributes {
+  attributes?: Attributes;
+  autoAttributes?: Attributes;
+  pos?: Pos;
+}
+
+export interface HasChildren<A> {
+  children: A[];
+}
+
+export interface HasText {
+  text: string;
+}
+
+export type Block =
+  | Para
+  | Heading
+  | ThematicBreak
+  | Section
+  | Div
+  | CodeBlock
+  | RawBlock
+  | BlockQuote
+  | OrderedList
+  | BulletList
+  | TaskList
+  | DefinitionList
+  | Table;
+
+export interface Para extends HasAttributes, HasChildren<Inline> {
+  tag: "para";
+}
+
+export interface Heading extends HasAttributes, HasChildren<Inline> {
+  tag: "heading";
+  level: number;
+}
+
+export interface ThematicBreak extends HasAttributes {
+  tag: "thematic_break";
+}
+
+export interface Section extends HasAttributes, HasChildren<Block> {
+  tag: "section";
+}
+
+export interface Div extends HasAttributes, HasChildren<Block> {
+  tag: "div";
+}
+
+export interface BlockQuote extends HasAttributes, HasChildren<Block> {
+  tag: "block_quote";
+}
+
+export interface CodeBlock extends HasAttributes, HasText {
+  tag: "code_block";
+  lang?: string;
+}
+
+export interface RawBlock extends HasAttributes, HasText {
+  tag: "raw_block";
+  format: string;
+}
+
+export type BulletListStyle = "-" | "+" | "*";
+
+export interface BulletList extends HasAttributes, HasChildren<ListItem> {
+  tag: "bullet_list";
+  style: BulletListStyle;
+  tight: boolean;
+}
+
+export type OrderedListStyle =
+  | "1." | "1)" | "(1)"
+  | "a." | "a)" | "(a)"
+  | "A." | "A)" | "(A)"
+  | "i." | "i)" | "(i)"
+  | "I." | "I)" | "(I)";
+
+export interface OrderedList extends HasAttributes, HasChildren<ListItem> {
+  tag: "ordered_list";
+  style: OrderedListStyle;
+  tight: boolean;
+  start?: number;
+}
+
+export interface TaskList extends HasAttributes, HasChildren<TaskListItem> {
+  tag: "task_list";
+  tight: boolean;
+}
+
+export interface DefinitionList extends HasAttributes, HasChildren<DefinitionListItem> {
+  tag: "definition_list";
+}
+
+export interface Table extends HasAttributes, HasChildren<Caption | Row> {
+  tag: "table";
+}
+
+export type Inline =
+  | Str
+  | SoftBreak
+  | HardBreak
+  | NonBreakingSpace
+  | Symb
+  | Verbatim
+  | RawInline
+  | InlineMath
+  | DisplayMath
+  | Url
+  | Email
+  | FootnoteReference
+  | SmartPunctuation
+  | Emph
+  | Strong
+  | Link
+  | Image
+  | Span
+  | Mark
+  | Superscript
+  | Subscript
+  | Delete
+  | Insert
+  | DoubleQuoted
+  | SingleQuoted;
+
+export interface Str extends HasAttributes, HasText {
+  tag: "str";
+}
+
+export interface FootnoteReference extends HasAttributes, HasText {
+  tag: "footnote_reference";
+}
+
+export interface SoftBreak extends HasAttributes {
+  tag: "soft_break";
+}
+
+export interface HardBreak extends HasAttributes {
+  tag: "hard_break";
+}
+
+export interface NonBreakingSpace extends HasAttributes {
+  tag: "non_breaking_space";
+}
+
+export interface Symb extends HasAttributes {
+  tag: "symb";
+  alias: string;
+}
+
+export interface Verbatim extends HasAttributes, HasText {
+  tag: "verbatim";
+}
+
+export interface RawInline extends HasAttributes, HasText {
+  tag: "raw_inline";
+  format: string;
+}
+
+export interface InlineMath extends HasAttributes, HasText {
+  tag: "inline_math";
+}
+
+export interface DisplayMath extends HasAttributes, HasText {
+  tag: "display_math";
+}
+
+export interface Url extends HasAttributes, HasText {
+  tag: "url";
+}
+
+export interface Email extends HasAttributes, HasText {
+  tag: "email";
+}
+
+export type SmartPunctuationType =
+  | "left_single_quote"
+  | "right_single_quote"
+  | "left_double_quote"
+  | "right_double_quote"
+  | "ellipses"
+  | "em_dash"
+  | "en_dash";
+
+export interface SmartPunctuation extends HasAttributes, HasText {
+  tag: "smart_punctuation";
+  type: SmartPunctuationType;
+}
+
+export interface Emph extends HasAttributes, HasChildren<Inline> {
+  tag: "emph";
+}
+
+export interface Strong extends HasAttributes, HasChildren<Inline> {
+  tag: "strong";
+}
+
+export interface Link extends HasAttributes, HasChildren<Inline> {
+  tag: "link";
+  destination?: string;
+  reference?: string;
+}
+
+export interface Image extends HasAttributes, HasChildren<Inline> {
+  tag: "image";
+  destination?: string;
+  reference?: string;
+}
+
+export interface Span extends HasAttributes, HasChildren<Inline> {
+  tag: "span";
+}
+
+export interface Mark extends HasAttributes, HasChildren<Inline> {
+  tag: "mark";
+}
+
+export interface Superscript extends HasAttributes, HasChildren<Inline> {
+  tag: "superscript";
+}
+
+export interface Subscript extends HasAttributes, HasChildren<Inline> {
+  tag: "subscript";
+}
+
+export interface Delete extends HasAttributes, HasChildren<Inline> {
+  tag: "delete";
+}
+
+export interface Insert extends HasAttributes, HasChildren<Inline> {
+  tag: "insert";
+}
+
+export interface DoubleQuoted extends HasAttributes, HasChildren<Inline> {
+  tag: "double_quoted";
+}
+
+export interface SingleQuoted extends HasAttributes, HasChildren<Inline> {
+  tag: "single_quoted";
+}
+
+export interface ListItem extends HasAttributes, HasChildren<Block> {
+  tag: "list_item";
+}
+
+export type CheckboxStatus = "checked" | "unchecked";
+
+export interface TaskListItem extends HasAttributes, HasChildren<Block> {
+  tag: "task_list_item";
+  checkbox: CheckboxStatus;
+}
+
+export interface DefinitionListItem extends HasAttributes {
+  tag: "definition_list_item";
+  children: [Term, Definition];
+}
+
+export interface Term extends HasAttributes, HasChildren<Inline> {
+  tag: "term";
+}
+
+export interface Definition extends HasAttributes, HasChildren<Block> {
+  tag: "definition";
+}
+
+export interface Caption extends HasAttributes, HasChildren<Inline> {
+  tag: "caption";
+}
+
+export type Alignment = "default" | "left" | "right" | "center";
+
+export interface Row extends HasAttributes, HasChildren<Cell> {
+  tag: "row";
+  head: boolean;
+}
+
+export interface Cell extends HasAttributes, HasChildren<Inline> {
+  tag: "cell";
+  head: boolean;
+  align: Alignment;
+}
+
+export interface Reference extends HasAttributes {
+  tag: "reference";
+  label: string;
+  destination: string;
+}
+
+export interface Footnote extends HasAttributes, HasChildren<Block> {
+  tag: "footnote";
+  label: string;
+}
+
+export interface Doc extends HasAttributes, HasChildren<Block> {
+  tag: "doc";
+  references: Record<string, Reference>;
+  autoReferences: Record<string, Reference>;
+  footnotes: Record<string, Footnote>;
+}
+
+export type AstNode =
+  | Doc
+  | Block
+  | Inline
+  | ListItem
+  | TaskListItem
+  | DefinitionListItem
+  | Term
+  | Definition
+  | Row
+  | Cell
+  | Caption
+  | Footnote
+  | Reference;
@@ -1,11 +1,36 @@
-use djot_ast::AstNode;
+use djot_ast::{
+    arena::Arena,
+    attributes::HasAttributes,
+    binary::Compact,
+    block::Block,
+    borrowed,
+    chunk::Chunker,
+    inline::Inline,
+    locate::PosIndex,
+    migrate::{AstVersion, Versioned},
+    normalize::normalize,
+    path::NodePath,
+    source::LineIndex,
+    split::split_by_heading,
+    text::extract_text,
+    traverse::NodeRef,
+    unknown::Value,
+    validate::{validate, Severity},
+    AstNode, Doc,
+};
 use rstest::{fixture, rstest};
+use std::{borrow::Cow, collections::HashSet};
 
 #[fixture]
 fn pandoc_json() -> &'static str {
     include_str!("fixtures/pandoc-manual.json")
 }
 
+#[fixture]
+fn pandoc_dj() -> &'static str {
+    include_str!("fixtures/pandoc-manual.dj")
+}
+
 #[fixture]
 fn readme_dj() -> &'static str {
     include_str!("fixtures/readme.dj")
@@ -29,3 +54,402 @@ fn deser_readme(readme_json: &str) {
 fn deser_pandoc(pandoc_json: &str) {
     can_deser(pandoc_json);
 }
+
+#[rstest]
+fn reserialize_fixtures(readme_json: &str, pandoc_json: &str) {
+    for json in [readme_json, pandoc_json] {
+        let node = can_deser(json);
+        let reserialized = serde_json::to_string(&node).unwrap();
+        assert_eq!(can_deser(&reserialized), node);
+        assert!(reserialized.starts_with(r#"{"tag":"doc","#));
+    }
+}
+
+#[rstest]
+fn reserialize_fixtures_exactly(readme_json: &str, pandoc_json: &str) {
+    let doc: Doc = serde_json::from_str(readme_json).unwrap();
+    assert_eq!(
+        serde_json::to_string_pretty(&doc).unwrap(),
+        readme_json.trim_end()
+    );
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    assert_eq!(serde_json::to_string(&doc).unwrap(), pandoc_json.trim_end());
+}
+
+#[rstest]
+fn borrowed_fixtures(readme_json: &str, pandoc_json: &str) {
+    for json in [readme_json, pandoc_json] {
+        let borrowed: borrowed::Doc = serde_json::from_str(json).unwrap();
+        let owned = borrowed.clone().into_owned();
+        assert_eq!(owned, serde_json::from_str::<Doc>(json).unwrap());
+        assert_eq!(
+            serde_json::to_value(&owned).unwrap(),
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+        let borrowed_labels = borrowed
+            .auto_references
+            .keys()
+            .filter(|k| matches!(k, Cow::Borrowed(_)))
+            .count();
+        assert_eq!(borrowed_labels, borrowed.auto_references.len());
+    }
+}
+
+#[rstest]
+fn arena_fixtures(readme_json: &str, pandoc_json: &str) {
+    for json in [readme_json, pandoc_json] {
+        let doc: Doc = serde_json::from_str(json).unwrap();
+        let arena = Arena::from(doc.clone());
+        let roots = std::iter::once(arena.root()).chain(arena.footnotes().map(|(_, i)| i));
+        let linked: usize = roots.map(|r| arena.descendants(r).count()).sum();
+        assert_eq!(linked, arena.len());
+        let references = doc.references.len() + doc.auto_references.len();
+        assert_eq!(linked, doc.nodes().count() - references);
+        for (_, footnote) in arena.footnotes() {
+            assert!(arena
+                .descendants(footnote)
+                .skip(1)
+                .all(|i| arena.ancestors(i).last() == Some(footnote)));
+        }
+        assert_eq!(arena.into_doc().unwrap(), doc);
+    }
+}
+
+#[rstest]
+fn binary_fixtures(readme_json: &str, pandoc_json: &str) {
+    for json in [readme_json, pandoc_json] {
+        let doc: Doc = serde_json::from_str(json).unwrap();
+        let bytes = postcard::to_allocvec(&Compact(&doc)).unwrap();
+        assert!(bytes.len() < json.len() / 4, "{} bytes", bytes.len());
+        let Compact(decoded): Compact<Doc> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_string(&decoded).unwrap(),
+            serde_json::to_string(&doc).unwrap()
+        );
+
+        #[cfg(feature = "cbor")]
+        {
+            let bytes = djot_ast::binary::to_cbor(&doc).unwrap();
+            assert_eq!(djot_ast::binary::from_cbor::<Doc>(&bytes).unwrap(), doc);
+        }
+        #[cfg(feature = "msgpack")]
+        {
+            let bytes = djot_ast::binary::to_msgpack(&doc).unwrap();
+            assert_eq!(djot_ast::binary::from_msgpack::<Doc>(&bytes).unwrap(), doc);
+        }
+        #[cfg(feature = "postcard")]
+        {
+            let node = AstNode::Doc(doc.clone());
+            let bytes = djot_ast::binary::to_postcard(&node).unwrap();
+            assert_eq!(
+                djot_ast::binary::from_postcard::<AstNode>(&bytes).unwrap(),
+                node
+            );
+        }
+    }
+}
+
+#[cfg(feature = "rkyv")]
+#[rstest]
+fn archive_fixtures(readme_json: &str, pandoc_json: &str) {
+    use djot_ast::archive;
+
+    for json in [readme_json, pandoc_json] {
+        let doc: Doc = serde_json::from_str(json).unwrap();
+        let bytes = archive::to_bytes(&doc).unwrap();
+        let archived = archive::access(&bytes).unwrap();
+        assert_eq!(archived.children.len(), doc.children.len());
+        for (a, b) in archived.children.iter().zip(&doc.children) {
+            assert_eq!(a.meta().pos(), b.pos().copied());
+            assert_eq!(a.meta().get_attribute("id"), b.id());
+        }
+        let labels: Vec<_> = archived
+            .auto_references
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        assert!(labels.iter().eq(doc.auto_references.keys()));
+        assert_eq!(archive::from_bytes(&bytes).unwrap(), doc);
+    }
+}
+
+#[rstest]
+fn extract_pandoc_sections(pandoc_json: &str) {
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    for idx in 0..doc.children.len() {
+        let extracted = doc.extract(&vec![idx].into()).unwrap();
+        let referenced: HashSet<_> = NodeRef::Doc(&extracted)
+            .descendants()
+            .chain(
+                extracted
+                    .footnotes
+                    .values()
+                    .flat_map(|f| NodeRef::Footnote(f).descendants()),
+            )
+            .filter_map(|n| match n {
+                NodeRef::Inline(Inline::FootnoteReference(f)) => Some(f.text.clone()),
+                _ => None,
+            })
+            .collect();
+        let included: HashSet<_> = extracted.footnotes.keys().cloned().collect();
+        let available: HashSet<_> = doc.footnotes.keys().cloned().collect();
+        assert_eq!(
+            included,
+            referenced
+                .intersection(&available)
+                .cloned()
+                .collect::<HashSet<_>>()
+        );
+    }
+}
+
+#[rstest]
+fn split_pandoc(pandoc_json: &str) {
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    let pages = split_by_heading(&doc, 1);
+    assert_eq!(pages.len(), doc.children.len());
+
+    let n_level2 = NodeRef::Doc(&doc)
+        .descendants()
+        .filter(|n| matches!(n, NodeRef::Block(Block::Heading(h)) if h.level <= 2))
+        .count();
+    assert_eq!(split_by_heading(&doc, 2).len(), n_level2);
+}
+
+#[rstest]
+fn chunk_pandoc(pandoc_json: &str) {
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    let budget = 500;
+    let chunks = Chunker::with_char_budget(budget).chunk(&doc);
+    assert!(!chunks.is_empty());
+    for chunk in chunks.iter() {
+        assert!(chunk.text.chars().count() <= budget);
+        assert!(chunk.pos.is_some());
+        assert!(!chunk.headings.is_empty());
+    }
+
+    for node in NodeRef::Doc(&doc).descendants() {
+        if let NodeRef::Block(Block::CodeBlock(c)) = node {
+            let text = c.text.trim_end_matches('\n');
+            if text.chars().count() <= budget {
+                assert!(chunks.iter().any(|ch| ch.text.contains(text)));
+            }
+        }
+    }
+}
+
+#[rstest]
+fn chunk_by_tokens(readme_json: &str) {
+    let doc: Doc = serde_json::from_str(readme_json).unwrap();
+    let n_words = |s: &str| s.split_whitespace().count();
+    let chunks = Chunker::with_measure(50, n_words).chunk(&doc);
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|c| n_words(&c.text) <= 50));
+    assert_eq!(chunks[0].headings, vec!["Djot".to_string()]);
+}
+
+#[test]
+fn chunk_captioned_table() {
+    // |aaaa|
+    // |bbbbbbbbbbbbbbbbbbbb|
+    // ^ caption
+    let pos = |line: u64, start: u64, end: u64| {
+        format!(
+            r#"{{"start": {{"line": {line}, "col": 1, "offset": {start}}}, "end": {{"line": {line}, "col": {}, "offset": {end}}}}}"#,
+            end - start + 1
+        )
+    };
+    let row = |text: &str, pos: String| {
+        format!(
+            r#"{{"tag": "row", "head": false, "pos": {pos}, "children": [
+                {{"tag": "cell", "head": false, "align": "default", "children": [{{"tag": "str", "text": "{text}"}}]}}
+            ]}}"#
+        )
+    };
+    let json = format!(
+        r#"{{"tag": "doc", "references": {{}}, "autoReferences": {{}}, "footnotes": {{}}, "children": [
+            {{"tag": "table", "pos": {table}, "children": [
+                {{"tag": "caption", "pos": {caption}, "children": [{{"tag": "str", "text": "caption"}}]}},
+                {rows}
+            ]}}
+        ]}}"#,
+        table = pos(1, 0, 40),
+        caption = pos(4, 31, 40),
+        rows = [
+            row("aaaa", pos(1, 0, 5)),
+            row(&"b".repeat(20), pos(2, 7, 28))
+        ]
+        .join(","),
+    );
+    let doc: Doc = serde_json::from_str(&json).unwrap();
+    let chunks = Chunker::with_char_budget(20).chunk(&doc);
+    let ranges: Vec<_> = chunks
+        .iter()
+        .map(|c| c.pos.map(|p| (p.start.offset, p.end.offset)))
+        .collect();
+    assert_eq!(ranges, vec![Some((0, 40)), Some((7, 28))]);
+}
+
+#[test]
+fn extract_text_with_bad_pos() {
+    // a position ending before it starts, as untrusted input may have
+    let json = r#"{"tag": "doc", "references": {}, "autoReferences": {}, "footnotes": {}, "children": [
+        {"tag": "para", "children": [{"tag": "str", "text": "abc", "pos": {
+            "start": {"line": 1, "col": 10, "offset": 9}, "end": {"line": 1, "col": 1, "offset": 0}
+        }}]}
+    ]}"#;
+    let doc: Doc = serde_json::from_str(json).unwrap();
+    let map = extract_text(&doc);
+    let loc = map.source_loc_at(1).unwrap();
+    assert_eq!(loc.offset, 9);
+}
+
+#[rstest]
+fn extract_text_maps_to_source(pandoc_json: &str, pandoc_dj: &str) {
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    // source offsets count UTF-16 code units
+    let source: Vec<u16> = pandoc_dj.encode_utf16().collect();
+    let map = extract_text(&doc);
+    assert!(map.text.contains("Pandoc’s enhanced version of Markdown"));
+
+    for (offset, c) in map.text.char_indices() {
+        let Some(segment) = map.segment_at(offset) else {
+            continue;
+        };
+        let Some(NodeRef::Inline(Inline::Str(s))) = doc.get(&segment.path) else {
+            continue;
+        };
+        let pos = s.pos().unwrap();
+        if s.text.encode_utf16().count() as u64 == pos.end.offset + 1 - pos.start.offset {
+            let loc = map.source_loc_at(offset).unwrap();
+            let start = loc.offset as usize;
+            let units = c.encode_utf16(&mut [0; 2]).to_vec();
+            assert_eq!(source[start..start + units.len()], units);
+        }
+    }
+}
+
+#[rstest]
+fn normalize_idempotent(pandoc_json: &str) {
+    let mut doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    normalize(&mut doc);
+    let once = doc.clone();
+    normalize(&mut doc);
+    assert_eq!(doc, once);
+}
+
+#[rstest]
+fn locate_pandoc(pandoc_json: &str) {
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+    let index = PosIndex::new(&doc);
+
+    let mut n_checked = 0;
+    let mut stack = vec![(NodeRef::Doc(&doc), NodePath::root())];
+    while let Some((node, path)) = stack.pop() {
+        for (idx, child) in node.children().into_iter().enumerate() {
+            stack.push((child, path.child(idx)));
+        }
+        let (NodeRef::Inline(Inline::Str(_)), Some(pos)) = (node, node.meta().pos()) else {
+            continue;
+        };
+        assert_eq!(index.node_at(pos.start.offset), Some(&path));
+        assert_eq!(index.node_at(pos.end.offset), Some(&path));
+        assert_eq!(index.node_at_loc(&pos.start), Some(&path));
+        assert!(index
+            .nodes_in_range(pos.start.offset, pos.end.offset)
+            .contains(&&path));
+        n_checked += 1;
+    }
+    assert!(n_checked > 1000);
+
+    let para = NodePath::root().child(0).child(1);
+    let pos = *doc.get(&para).unwrap().meta().pos().unwrap();
+    let in_range = doc.nodes_in_range(pos.start.offset, pos.end.offset);
+    assert_eq!(in_range[0], para);
+    assert_eq!(doc.node_at_loc(&pos.start), doc.node_at(pos.start.offset));
+    assert!(in_range.len() > 1);
+    assert!(in_range[1..].iter().all(|p| para.is_ancestor_of(p)));
+    assert_eq!(doc.node_at(pos.end.offset + 1000000), None);
+}
+
+#[rstest]
+fn slice_source(readme_json: &str, readme_dj: &str) {
+    let doc: Doc = serde_json::from_str(readme_json).unwrap();
+    let index = LineIndex::new(readme_dj);
+    let mut n_checked = 0;
+    for node in NodeRef::Doc(&doc).descendants() {
+        let NodeRef::Inline(Inline::Str(s)) = node else {
+            continue;
+        };
+        let pos = s.pos().unwrap();
+        if pos.end.offset + 1 - pos.start.offset == s.text.encode_utf16().count() as u64 {
+            assert_eq!(index.slice_node(s), Some(s.text.as_str()));
+            let start = index.utf16_position(&pos.start).unwrap();
+            assert_eq!(index.loc_from_utf16(start).as_ref(), Some(&pos.start));
+            n_checked += 1;
+        }
+    }
+    assert!(n_checked > 100);
+}
+
+#[rstest]
+fn validate_fixtures(readme_json: &str, pandoc_json: &str) {
+    for json in [readme_json, pandoc_json] {
+        let doc: Doc = serde_json::from_str(json).unwrap();
+        let diagnostics = validate(&doc);
+        assert!(
+            diagnostics.iter().all(|d| d.severity != Severity::Error),
+            "{diagnostics:?}"
+        );
+    }
+}
+
+#[rstest]
+fn select_pandoc(pandoc_json: &str) {
+    let doc: Doc = serde_json::from_str(pandoc_json).unwrap();
+
+    let headings = doc.select("section > heading").unwrap();
+    let sections = doc.select("section").unwrap();
+    assert_eq!(headings.len(), sections.len());
+    assert!(headings
+        .iter()
+        .all(|(path, _)| path.children.last() == Some(&0)));
+
+    let level_2 = doc.select("heading[level=2]").unwrap();
+    assert!(!level_2.is_empty());
+    assert!(level_2
+        .iter()
+        .all(|(_, node)| matches!(node, NodeRef::Block(Block::Heading(h)) if h.level == 2)));
+
+    let all: Vec<_> = doc.nodes().map(|(path, _)| path).collect();
+    let selected: Vec<_> = doc
+        .select("*")
+        .unwrap()
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    assert_eq!(selected, all);
+}
+
+#[rstest]
+#[case(AstVersion::V0_1, include_str!("fixtures/versions/djot-js-0.1.json"))]
+#[case(AstVersion::V0_2, include_str!("fixtures/versions/djot-js-0.2.json"))]
+#[case(AstVersion::V0_3, include_str!("fixtures/versions/djot-js-0.3.json"))]
+fn migrate_versions(#[case] version: AstVersion, #[case] json: &str) {
+    let current: Doc =
+        serde_json::from_str(include_str!("fixtures/versions/djot-js-0.3.json")).unwrap();
+
+    let value: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(AstVersion::detect(&value), version);
+    let Versioned(doc): Versioned<Doc> = serde_json::from_str(json).unwrap();
+    assert_eq!(doc, current);
+
+    let mut marked: serde_json::Value = serde_json::from_str(json).unwrap();
+    marked["djotJsVersion"] = version.to_string().into();
+    let Versioned(doc): Versioned<Doc> = serde_json::from_value(marked).unwrap();
+    assert_eq!(doc, current);
+
+    let written = serde_json::to_string(&Versioned(&doc)).unwrap();
+    let Versioned(reread): Versioned<Doc> = serde_json::from_str(&written).unwrap();
+    assert_eq!(reread, current);
+}